@@ -1,73 +1,228 @@
 mod asset_path;
+pub mod editor;
 pub mod image;
+mod media;
+mod search;
+pub mod thumbnail;
 mod ui;
+mod watch;
 
 pub use asset_path::AssetPath;
 pub use image::Image;
-pub use ui::{update, view};
+pub use media::Media;
+pub use ui::{preview, update, view};
+pub use watch::{FileEvent, watch};
 
-use std::{collections::HashMap, ops::Index, path::PathBuf, str::FromStr};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
 
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
 
-use crate::io::AssetsError;
+use crate::io::{self, AssetsError, write_index};
+
+/// Where deleted assets go, relative to the project folder, so deleting can be undone.
+const TRASH_FOLDER: &str = ".trash";
 
 #[derive(Debug, Clone)]
 pub enum Asset {
     Image(Image),
+    Audio(Media),
+    Video(Media),
+    Document(Media),
 }
 
 impl Asset {
     pub fn folder(&self) -> String {
-        match self {
-            Self::Image(_) => "images".to_string(),
-        }
+        self.kind().folder().to_string()
     }
 
-    #[allow(unused)]
     pub fn kind(&self) -> AssetKind {
         match self {
             Self::Image(_) => AssetKind::Image,
+            Self::Audio(_) => AssetKind::Audio,
+            Self::Video(_) => AssetKind::Video,
+            Self::Document(_) => AssetKind::Document,
+        }
+    }
+
+    /// Content hash of the asset's file.
+    pub fn hash(&self) -> &str {
+        match self {
+            Self::Image(img) => &img.hash,
+            Self::Audio(media) | Self::Video(media) | Self::Document(media) => &media.hash,
+        }
+    }
+
+    /// Size of the asset's file in bytes.
+    pub fn size(&self) -> u64 {
+        match self {
+            Self::Image(img) => img.size,
+            Self::Audio(media) | Self::Video(media) | Self::Document(media) => media.size,
+        }
+    }
+
+    /// What the asset looks like in lists, the thumbnail for images.
+    pub fn preview(&self) -> Option<&iced::widget::image::Handle> {
+        match self {
+            Self::Image(img) => Some(&img.thumbnail),
+            Self::Audio(media) | Self::Video(media) | Self::Document(media) => {
+                media.preview.as_ref()
+            }
+        }
+    }
+
+    /// The same asset after its file was moved to `path`.
+    pub fn moved_to(self, path: PathBuf) -> Self {
+        match self {
+            Self::Image(img) => Self::Image(Image {
+                handle: iced::widget::image::Handle::from_path(path),
+                ..img
+            }),
+            // previews are cached by content, they don't care where the file is
+            media => media,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct AssetHandle(u32);
 
-#[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum AssetKind {
+impl AssetHandle {
+    /// Never points to an asset, used for characters that don't have an image.
+    pub const NONE: Self = Self(u32::MAX);
+}
+
+/// Declares [`AssetKind`] together with the list returned by [`AssetKind::all`], so a new kind
+/// can't be forgotten in it.
+macro_rules! asset_kinds {
+    ($($(#[$attr:meta])* $kind:ident => $folder:literal,)*) => {
+        #[derive(Default, Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+        pub enum AssetKind {
+            $($(#[$attr])* $kind,)*
+        }
+
+        impl AssetKind {
+            /// Every kind, in the order they're declared in.
+            pub fn all() -> &'static [Self] {
+                &[$(Self::$kind,)*]
+            }
+
+            pub fn folder(&self) -> &'static str {
+                match self {
+                    $(Self::$kind => $folder,)*
+                }
+            }
+        }
+    };
+}
+
+asset_kinds! {
     #[default]
-    Image,
+    Image => "images",
+    Audio => "audio",
+    Video => "videos",
+    Document => "documents",
 }
 
 impl AssetKind {
-    pub fn folder(&self) -> &'static str {
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::Image => "images",
+            Self::Image => "Images",
+            Self::Audio => "Audio",
+            Self::Video => "Videos",
+            Self::Document => "Documents",
         }
     }
 
-    // NOTE: update this whenever a new kind of asset is added
-    // tried forcing this by also making use of a match but I can't because of temporary value
-    // shenanigans
-    pub fn all() -> &'static [Self] {
-        &[Self::Image]
+    /// The kind of asset a file with `media_type` becomes, `None` if it can't be one.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type.split_once('/')? {
+            ("image", _) => Some(Self::Image),
+            ("audio", _) => Some(Self::Audio),
+            ("video", _) => Some(Self::Video),
+            ("application", "pdf") => Some(Self::Document),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
     }
 }
 
 impl FromStr for AssetKind {
     type Err = ();
 
+    /// Accepts the folder name, or the kind's name in singular.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
-            "images" | "image" => Ok(Self::Image),
-            _ => Err(()),
+        let s = s.to_lowercase();
+
+        Self::all()
+            .iter()
+            .find(|kind| kind.folder() == s || format!("{kind:?}").to_lowercase() == s)
+            .copied()
+            .ok_or(())
+    }
+}
+
+/// What .index.ron remembers about an asset. The hash identifies its contents, to find
+/// duplicates and files that were renamed while the app wasn't running.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IndexEntry {
+    pub path: AssetPath,
+    /// Empty until the file has been read once, for indexes from before hashes were stored.
+    #[serde(default)]
+    pub hash: String,
+    /// Set by the user to find assets with the search box.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// When the asset was first indexed, `None` for indexes from before this was stored.
+    #[serde(default)]
+    pub added: Option<SystemTime>,
+    /// Part of an image characters show, the file itself stays whole.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<Crop>,
+}
+
+impl IndexEntry {
+    pub fn new(path: AssetPath, hash: impl Into<String>) -> Self {
+        Self {
+            path,
+            hash: hash.into(),
+            tags: Vec::new(),
+            added: Some(SystemTime::now()),
+            crop: None,
         }
     }
 }
 
+/// A rectangle of an image in pixels of its file, see [`IndexEntry::crop`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Crop {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An asset moved into the project's `.trash` by [`AssetsData::trash`], everything needed to put
+/// it back.
+#[derive(Debug, Clone)]
+pub struct TrashedAsset {
+    pub handle: AssetHandle,
+    pub entry: IndexEntry,
+    /// Name of the file in the trash, which differs from the asset's when something with the
+    /// same name was trashed before.
+    file_name: String,
+}
+
 #[derive(Default, Debug, Clone, Copy)]
 pub enum ViewMode {
     #[default]
@@ -75,45 +230,167 @@ pub enum ViewMode {
     List,
 }
 
+/// Order of the listed assets. Search results are ranked by how well they match first.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    #[default]
+    Name,
+    /// Most recently added first.
+    DateAdded,
+    /// Largest first.
+    Size,
+    /// Used by the most characters first.
+    Usage,
+}
+
+impl SortMode {
+    pub const ALL: [Self; 4] = [Self::Name, Self::DateAdded, Self::Size, Self::Usage];
+}
+
+impl std::fmt::Display for SortMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Name => "Name",
+            Self::DateAdded => "Date Added",
+            Self::Size => "Size",
+            Self::Usage => "Usage",
+        })
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetFilter {
+    #[default]
+    All,
+    /// Assets no character uses.
+    Unused,
+    /// Assets used by the selected characters.
+    UsedBySelection,
+}
+
+impl AssetFilter {
+    pub const ALL: [Self; 3] = [Self::All, Self::Unused, Self::UsedBySelection];
+}
+
+impl std::fmt::Display for AssetFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::All => "All",
+            Self::Unused => "Unused",
+            Self::UsedBySelection => "Used by Selection",
+        })
+    }
+}
+
+/// How the characters in the graph use assets, for sorting and filtering the list. Filled in by
+/// the app, which knows the characters.
+#[derive(Debug, Default)]
+pub struct Usage {
+    /// How many characters use each asset, as their image or an attachment.
+    pub counts: HashMap<AssetHandle, usize>,
+    /// The assets used by the selected characters.
+    pub selected: HashSet<AssetHandle>,
+}
+
+/// Progress of reading a folder's assets in the background.
+struct Loading {
+    load: LoadId,
+    /// Aborts reading when dropped, i.e. when another folder starts loading.
+    _task: iced::task::Handle,
+    loaded: usize,
+    total: usize,
+    /// Whether reading the files filled in hashes the index didn't have yet.
+    hashes_added: bool,
+}
+
+/// Tells the messages of one loading of a folder from those of the loading before it, which can
+/// still arrive after it was aborted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadId(u32);
+
 #[derive(Default)]
 pub struct AssetsData {
     view: AssetKind,
     view_mode: ViewMode,
     view_dropdown_open: bool,
+    sort: SortMode,
+    filter: AssetFilter,
     assets: HashMap<AssetPath, Asset>,
-    index: HashMap<u32, AssetPath>,
+    index: HashMap<u32, IndexEntry>,
     last_error: Option<anyhow::Error>,
     query: Option<String>,
     folder: Option<PathBuf>,
+    /// Sub-folders of the shown kind's folder leading to the one that's listed, outermost first.
+    open_folder: Vec<String>,
     renaming: Option<(AssetHandle, String)>,
-}
-
-impl Index<AssetHandle> for AssetsData {
-    type Output = Asset;
-
-    fn index(&self, index: AssetHandle) -> &Self::Output {
-        &self.assets[&self.index[&index.0]]
-    }
+    tagging: Option<(AssetHandle, String)>,
+    loading: Option<Loading>,
+    /// Id of the last loading that was started.
+    last_load: LoadId,
 }
 
 impl AssetsData {
     pub fn get(&self, handle: AssetHandle) -> Option<&Asset> {
         self.index
             .get(&handle.0)
-            .and_then(|asset_path| self.assets.get(asset_path))
+            .and_then(|entry| self.assets.get(&entry.path))
     }
 
-    #[allow(unused)]
     pub fn handle(&self, asset_path: AssetPath) -> Option<AssetHandle> {
-        self.assets.get(&asset_path).and_then(|_| {
-            self.index
-                .iter()
-                .find_map(|(id, path)| path.eq(&asset_path).then_some(AssetHandle(*id)))
-        })
+        self.assets
+            .get(&asset_path)
+            .and_then(|_| self.indexed(&asset_path))
+    }
+
+    /// Like [`AssetsData::handle`], but also finds indexed assets whose file is missing.
+    fn indexed(&self, asset_path: &AssetPath) -> Option<AssetHandle> {
+        self.index
+            .iter()
+            .find_map(|(id, entry)| entry.path.eq(asset_path).then_some(AssetHandle(*id)))
+    }
+
+    /// An asset with exactly the contents hashed to `hash`.
+    pub fn find_duplicate(&self, hash: &str) -> Option<(AssetHandle, &AssetPath)> {
+        self.iter()
+            .find(|(_, _, asset)| asset.hash() == hash)
+            .map(|(handle, path, _)| (handle, path))
     }
 
     pub fn path(&self, handle: AssetHandle) -> Option<&AssetPath> {
-        self.index.get(&handle.0)
+        self.index.get(&handle.0).map(|entry| &entry.path)
+    }
+
+    pub fn crop(&self, handle: AssetHandle) -> Option<Crop> {
+        self.index.get(&handle.0).and_then(|entry| entry.crop)
+    }
+
+    /// Whether `handle` is supposed to point to an asset, but its file is gone. Nothing counts as
+    /// missing while the assets are still loading.
+    pub fn is_missing(&self, handle: AssetHandle) -> bool {
+        handle != AssetHandle::NONE && self.loading.is_none() && self.get(handle).is_none()
+    }
+
+    /// Whether `load` is the loading that's still going on.
+    fn is_loading(&self, load: LoadId) -> bool {
+        self.loading
+            .as_ref()
+            .is_some_and(|loading| loading.load == load)
+    }
+
+    /// How many of the folder's assets have been read so far and how many there are, `None` once
+    /// they're all done.
+    pub fn loading_progress(&self) -> Option<(usize, usize)> {
+        self.loading
+            .as_ref()
+            .map(|loading| (loading.loaded, loading.total))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (AssetHandle, &AssetPath, &Asset)> {
+        self.index.iter().filter_map(|(id, entry)| {
+            self.assets
+                .get(&entry.path)
+                .map(|asset| (AssetHandle(*id), &entry.path, asset))
+        })
     }
 
     pub fn add(
@@ -134,13 +411,394 @@ impl AssetsData {
                 .context(format!("File {path:?} does not exist.")));
         }
 
-        let ids: Vec<&u32> = self.index.keys().collect();
-        let id = (0..).into_iter().find(|id| !ids.contains(&id)).unwrap();
+        // the folder watcher might have seen the file first, or it replaced an existing one
+        let handle = self.indexed(&asset_path).unwrap_or_else(|| {
+            let ids: Vec<&u32> = self.index.keys().collect();
+            AssetHandle((0..).into_iter().find(|id| !ids.contains(&id)).unwrap())
+        });
+
+        let entry = match self.index.get(&handle.0) {
+            Some(entry) => IndexEntry {
+                hash: asset.hash().to_string(),
+                // a crop of the replaced image doesn't fit a different one
+                crop: entry.crop.filter(|_| entry.hash == asset.hash()),
+                ..entry.clone()
+            },
+            None => IndexEntry::new(asset_path.clone(), asset.hash()),
+        };
 
-        self.assets.insert(asset_path.clone(), asset);
-        self.index.insert(id, asset_path);
+        self.index.insert(handle.0, entry);
 
-        Ok(AssetHandle(id))
+        let asset = self.with_crop(handle.0, asset);
+        self.insert(asset_path, asset);
+
+        write_index(&self.index, self.folder.clone())?;
+
+        Ok(handle)
+    }
+
+    /// Brings the index in line with a change on disk. Renamed files keep their handle, removed
+    /// ones keep it too so characters using them show up as missing until the file comes back.
+    pub fn file_changed(&mut self, event: FileEvent) -> Result<()> {
+        let folder = self.folder.clone().ok_or(AssetsError::NoFolderLoaded)?;
+
+        match event {
+            FileEvent::Written(path) => {
+                // anything that isn't an asset doesn't belong in the index
+                let Ok(asset) = io::load_file(&(&folder + path.clone()), &folder) else {
+                    return Ok(());
+                };
+
+                let id = match self.indexed(&path) {
+                    Some(handle) => handle.0,
+                    None => {
+                        let ids: Vec<&u32> = self.index.keys().collect();
+                        (0..).find(|id| !ids.contains(&id)).unwrap()
+                    }
+                };
+
+                // the tags and when it was added stay with a rewritten file, the crop only if
+                // it's still the same image
+                let entry = match self.index.get(&id) {
+                    Some(entry) => IndexEntry {
+                        hash: asset.hash().to_string(),
+                        crop: entry.crop.filter(|_| entry.hash == asset.hash()),
+                        ..entry.clone()
+                    },
+                    None => IndexEntry::new(path.clone(), asset.hash()),
+                };
+
+                if self.index.get(&id) != Some(&entry) {
+                    self.index.insert(id, entry);
+                    write_index(&self.index, Some(folder))?;
+                }
+
+                let asset = self.with_crop(id, asset);
+                self.insert(path, asset);
+            }
+            FileEvent::Removed(path) => {
+                self.assets.remove(&path);
+            }
+            FileEvent::Renamed { from, to } => {
+                // renames done by the app already updated the index
+                let Some(handle) = self.indexed(&from) else {
+                    return self.file_changed(FileEvent::Written(to));
+                };
+
+                // whatever got overwritten is gone now
+                if let Some(overwritten) = self.indexed(&to) {
+                    self.index.remove(&overwritten.0);
+                }
+
+                if let Some(entry) = self.index.get_mut(&handle.0) {
+                    entry.path = to.clone();
+                }
+
+                match self.assets.remove(&from) {
+                    Some(asset) => {
+                        self.insert(to.clone(), asset.moved_to(&folder + to));
+                    }
+                    None => match io::load_file(&(&folder + to.clone()), &folder) {
+                        Ok(asset) => {
+                            let asset = self.with_crop(handle.0, asset);
+                            self.insert(to, asset);
+                        }
+                        Err(_) => {
+                            self.assets.remove(&to);
+                        }
+                    },
+                }
+
+                write_index(&self.index, Some(folder))?;
+            }
+            FileEvent::FolderRenamed { kind, from, to } => {
+                if kind == self.view && self.open_folder.starts_with(&from) {
+                    self.open_folder.splice(..from.len(), to.iter().cloned());
+                }
+
+                // everything inside keeps its handle
+                for entry in self.index.values_mut() {
+                    if let Some(path) = entry
+                        .path
+                        .moved_folder(&from, &to)
+                        .filter(|path| path.kind() == kind)
+                    {
+                        entry.path = path;
+                    }
+                }
+
+                let moved: Vec<AssetPath> = self
+                    .assets
+                    .keys()
+                    .filter(|path| path.is_in(kind, &from))
+                    .cloned()
+                    .collect();
+
+                for old_path in moved {
+                    if let (Some(asset), Some(new_path)) = (
+                        self.assets.remove(&old_path),
+                        old_path.moved_folder(&from, &to),
+                    ) {
+                        self.insert(new_path.clone(), asset.moved_to(&folder + new_path));
+                    }
+                }
+
+                write_index(&self.index, Some(folder))?;
+            }
+            FileEvent::FolderRemoved { kind, folders } => {
+                self.assets.retain(|path, _| !path.is_in(kind, &folders));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renames the file behind `handle` and updates the index. `new_name` is relative to the
+    /// folder of the asset's kind, so it can move the file into other sub-folders, which get
+    /// created as needed. Returns the old path.
+    pub fn rename(&mut self, handle: AssetHandle, new_name: &str) -> Result<AssetPath> {
+        let old_path = self
+            .path(handle)
+            .cloned()
+            .ok_or(AssetsError::AssetNotFound)?;
+
+        let new_path = AssetPath::from_relative(old_path.kind(), new_name)
+            .map_err(|_| AssetsError::InvalidName)?;
+
+        let folder = self.folder.clone().ok_or(AssetsError::NoFolderLoaded)?;
+
+        let from = folder.clone() + old_path.clone();
+
+        let to = folder + new_path.clone();
+
+        let err_ctx = format!("Couldn't rename {from:?} to {to:?}");
+
+        // renaming would silently replace it
+        if to.exists() {
+            return Err(anyhow!(AssetsError::AlreadyExists).context(err_ctx));
+        }
+
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+        }
+
+        std::fs::rename(from, to.clone()).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+
+        if let Some(entry) = self.index.get_mut(&handle.0) {
+            entry.path = new_path.clone();
+        }
+
+        if let Some(asset) = self.assets.remove(&old_path) {
+            self.insert(new_path, asset.moved_to(to));
+        }
+
+        write_index(&self.index, self.folder.clone()).map_err(|err| err.context(err_ctx))?;
+
+        Ok(old_path)
+    }
+
+    /// Tags the asset behind `handle`, unless it already has the tag.
+    pub fn add_tag(&mut self, handle: AssetHandle, tag: &str) -> Result<()> {
+        let entry = self
+            .index
+            .get_mut(&handle.0)
+            .ok_or(AssetsError::AssetNotFound)?;
+
+        let tag = tag.trim();
+
+        if tag.is_empty() || entry.tags.iter().any(|existing| existing == tag) {
+            return Ok(());
+        }
+
+        entry.tags.push(tag.to_string());
+
+        write_index(&self.index, self.folder.clone())
+    }
+
+    pub fn remove_tag(&mut self, handle: AssetHandle, tag: &str) -> Result<()> {
+        let entry = self
+            .index
+            .get_mut(&handle.0)
+            .ok_or(AssetsError::AssetNotFound)?;
+
+        entry.tags.retain(|existing| existing != tag);
+
+        write_index(&self.index, self.folder.clone())
+    }
+
+    /// The sub-folders directly inside the listed folder that contain assets, sorted by name.
+    pub fn sub_folders(&self) -> Vec<&str> {
+        let mut folders: Vec<&str> = self
+            .assets
+            .keys()
+            .filter(|path| path.kind() == self.view)
+            .filter_map(|path| {
+                path.folders()
+                    .strip_prefix(self.open_folder.as_slice())?
+                    .first()
+                    .map(String::as_str)
+            })
+            .collect();
+
+        folders.sort_unstable();
+        folders.dedup();
+
+        folders
+    }
+
+    /// Moves the file behind `handle` into the project's `.trash` folder and drops it from the
+    /// index.
+    pub fn trash(&mut self, handle: AssetHandle) -> Result<TrashedAsset> {
+        let entry = self
+            .index
+            .get(&handle.0)
+            .cloned()
+            .ok_or(AssetsError::AssetNotFound)?;
+
+        let folder = self.folder.clone().ok_or(AssetsError::NoFolderLoaded)?;
+
+        let file_name =
+            io::free_file_name(&trash_folder(&folder, entry.path.kind()), entry.path.name());
+
+        let trashed = TrashedAsset {
+            handle,
+            entry,
+            file_name,
+        };
+
+        self.move_to_trash(&trashed)?;
+
+        Ok(trashed)
+    }
+
+    /// Moves an asset to the place in the trash recorded in `trashed`, to redo
+    /// [`AssetsData::trash`].
+    pub fn move_to_trash(&mut self, trashed: &TrashedAsset) -> Result<()> {
+        if self.index.get(&trashed.handle.0) != Some(&trashed.entry) {
+            return Err(anyhow!(AssetsError::AssetNotFound));
+        }
+
+        let folder = self.folder.clone().ok_or(AssetsError::NoFolderLoaded)?;
+
+        let from = &folder + trashed.entry.path.clone();
+        let trash = trash_folder(&folder, trashed.entry.path.kind());
+        let to = trash.join(&trashed.file_name);
+
+        let err_ctx = format!("Couldn't move {from:?} to the trash");
+
+        if to.exists() {
+            return Err(anyhow!(AssetsError::AlreadyExists).context(err_ctx));
+        }
+
+        std::fs::create_dir_all(&trash).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+        std::fs::rename(&from, to).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+
+        self.index.remove(&trashed.handle.0);
+        self.assets.remove(&trashed.entry.path);
+
+        write_index(&self.index, Some(folder)).map_err(|err| err.context(err_ctx))
+    }
+
+    /// Moves a trashed asset back where it was, under the same handle.
+    pub fn restore(&mut self, trashed: &TrashedAsset) -> Result<()> {
+        let folder = self.folder.clone().ok_or(AssetsError::NoFolderLoaded)?;
+
+        let from = trash_folder(&folder, trashed.entry.path.kind()).join(&trashed.file_name);
+        let to = &folder + trashed.entry.path.clone();
+
+        let err_ctx = format!("Couldn't restore {to:?} from the trash");
+
+        if to.exists() || self.index.contains_key(&trashed.handle.0) {
+            return Err(anyhow!(AssetsError::AlreadyExists).context(err_ctx));
+        }
+
+        // the asset's folder might have gone in the meantime
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+        }
+
+        std::fs::rename(from, &to).map_err(|err| anyhow!(err).context(err_ctx.clone()))?;
+
+        self.index.insert(trashed.handle.0, trashed.entry.clone());
+
+        if let Ok(asset) = io::load_file(&to, &folder) {
+            let asset = self.with_crop(trashed.handle.0, asset);
+            self.insert(trashed.entry.path.clone(), asset);
+        }
+
+        write_index(&self.index, Some(folder)).map_err(|err| err.context(err_ctx))
+    }
+
+    /// Stores the part of the image behind `handle` that characters show, `None` shows all of
+    /// it. Returns the crop it had before.
+    pub fn set_crop(&mut self, handle: AssetHandle, crop: Option<Crop>) -> Result<Option<Crop>> {
+        let entry = self
+            .index
+            .get_mut(&handle.0)
+            .ok_or(AssetsError::AssetNotFound)?;
+
+        let old_crop = std::mem::replace(&mut entry.crop, crop);
+        let path = entry.path.clone();
+
+        write_index(&self.index, self.folder.clone())?;
+
+        if let Some(Asset::Image(img)) = self.assets.remove(&path) {
+            let asset = self.with_crop(
+                handle.0,
+                Asset::Image(Image {
+                    cropped: None,
+                    ..img
+                }),
+            );
+            self.insert(path, asset);
+        }
+
+        Ok(old_crop)
+    }
+
+    /// Puts `asset` at `path`. An image that replaces a different one is shown from a copy, so
+    /// iced doesn't keep showing the old one it cached for the path.
+    fn insert(&mut self, path: AssetPath, asset: Asset) {
+        let replaces = self
+            .assets
+            .get(&path)
+            .is_some_and(|old| old.hash() != asset.hash());
+
+        let asset = match (asset, &self.folder) {
+            (Asset::Image(img), Some(folder)) if replaces => {
+                match thumbnail::replaced(folder, &img.hash, &(folder + path.clone())) {
+                    Ok(handle) => Asset::Image(Image {
+                        // the thumbnail is the image itself when it couldn't be made
+                        thumbnail: if img.thumbnail == img.handle {
+                            handle.clone()
+                        } else {
+                            img.thumbnail
+                        },
+                        handle,
+                        ..img
+                    }),
+                    Err(_) => Asset::Image(img),
+                }
+            }
+            (asset, _) => asset,
+        };
+
+        self.assets.insert(path, asset);
+    }
+
+    /// `asset` showing the crop stored for it in the index, if it's an image with one.
+    fn with_crop(&self, id: u32, asset: Asset) -> Asset {
+        match (&self.folder, self.index.get(&id)) {
+            (
+                Some(folder),
+                Some(IndexEntry {
+                    path,
+                    crop: Some(crop),
+                    ..
+                }),
+            ) => io::with_crop(asset, &(folder + path.clone()), folder, *crop),
+            _ => asset,
+        }
     }
 
     pub fn folder(&self) -> Option<&PathBuf> {
@@ -155,6 +813,12 @@ impl AssetsData {
         self.query.as_deref().unwrap_or("")
     }
 
+    /// Whether assets from every folder are listed instead of the open folder's, while searching
+    /// or filtering.
+    pub fn lists_all_folders(&self) -> bool {
+        !self.query().trim().is_empty() || self.filter != AssetFilter::All
+    }
+
     pub fn query_present(&self) -> bool {
         self.query.is_some()
     }
@@ -164,17 +828,42 @@ impl AssetsData {
     }
 }
 
+fn trash_folder(folder: &Path, kind: AssetKind) -> PathBuf {
+    folder.join(TRASH_FOLDER).join(kind.folder())
+}
+
 #[derive(Clone, Debug)]
 pub enum AssetsMessage {
     LoadAssets(PathBuf),
-    LoadCompleted(PathBuf, HashMap<u32, (AssetPath, Asset)>),
+    /// The index was read, the assets themselves follow as [`AssetsMessage::AssetLoaded`].
+    LoadStarted(PathBuf),
+    AssetLoaded(LoadId, u32, AssetPath, Option<Asset>),
+    LoadCompleted(LoadId),
     LoadFailed,
     OpenAsset(AssetHandle),
     SetPayload(Option<crate::Draggable>),
     QueryChanged(Option<String>),
     ViewChanged(ViewMode),
+    SortChanged(SortMode),
+    FilterChanged(AssetFilter),
+    KindChanged(AssetKind),
+    /// Lists the sub-folder at this path in the shown kind's folder.
+    OpenFolder(Vec<String>),
     ShowHideDropdown,
     SetRenameInput(Option<(AssetHandle, String)>),
     RenameAsset,
+    AssetRenamed(AssetHandle, AssetPath, AssetPath),
     RenameAssetFailed(AssetHandle),
+    SetTagInput(Option<(AssetHandle, String)>),
+    AddTag,
+    RemoveTag(AssetHandle, String),
+    TagAssetFailed(AssetHandle),
+    /// Opens the image editor. Handled by the app, which shows it over everything.
+    EditImage(AssetHandle),
+    /// Asks whether to move the asset to the trash. Handled by the app, which knows the
+    /// characters using it.
+    DeleteAsset(AssetHandle),
+    FileChanged(FileEvent),
+    /// Some or all of the asset folders aren't watched for changes anymore.
+    WatchFailed(String),
 }
@@ -4,9 +4,14 @@ use serde::{Deserialize, Serialize};
 
 use crate::assets::AssetKind;
 
+/// Where an asset is, relative to the project folder: the folder of its kind, any sub-folders of
+/// that and the file name.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct AssetPath {
     kind: AssetKind,
+    /// Sub-folders of the kind's folder, outermost first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    folders: Vec<String>,
     name: String,
 }
 
@@ -14,37 +19,87 @@ impl AssetPath {
     pub fn new(kind: AssetKind, name: impl Into<String>) -> Self {
         Self {
             kind,
+            folders: Vec::new(),
             name: name.into(),
         }
     }
 
+    /// Parses a path relative to the folder of `kind`, like `letters/1901/anna.pdf`.
+    pub fn from_relative(kind: AssetKind, relative: &str) -> Result<Self, ()> {
+        let mut components = components(relative)?;
+
+        let name = components.pop().ok_or(())?;
+
+        Ok(Self {
+            kind,
+            folders: components,
+            name,
+        })
+    }
+
     pub fn kind(&self) -> AssetKind {
         self.kind
     }
 
+    pub fn folders(&self) -> &[String] {
+        &self.folders
+    }
+
+    /// The file name.
     pub fn name(&self) -> &str {
         &self.name
     }
+
+    /// The path relative to the folder of its kind, what [`AssetPath::from_relative`] reads.
+    pub fn relative(&self) -> String {
+        self.folders
+            .iter()
+            .chain([&self.name])
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Whether the asset is somewhere inside `folders` of the folder of `kind`.
+    pub fn is_in(&self, kind: AssetKind, folders: &[String]) -> bool {
+        self.kind == kind && self.folders.starts_with(folders)
+    }
+
+    /// The same file after the folder `from` was renamed to `to`, if it's inside it.
+    pub fn moved_folder(&self, from: &[String], to: &[String]) -> Option<Self> {
+        let rest = self.folders.strip_prefix(from)?;
+
+        Some(Self {
+            kind: self.kind,
+            folders: to.iter().chain(rest).cloned().collect(),
+            name: self.name.clone(),
+        })
+    }
+}
+
+/// Splits a `/` separated path, rejecting empty components and ones that would leave the folder.
+fn components(path: &str) -> Result<Vec<String>, ()> {
+    path.split('/')
+        .map(|component| match component {
+            "" | "." | ".." => Err(()),
+            component => Ok(component.to_string()),
+        })
+        .collect()
 }
 
 impl TryFrom<&str> for AssetPath {
     type Error = ();
 
-    fn try_from(name: &str) -> Result<Self, Self::Error> {
-        let mut components = name.split('/');
-
-        let kind = AssetKind::from_str(components.next().ok_or(())?)?;
+    fn try_from(path: &str) -> Result<Self, Self::Error> {
+        let (kind, relative) = path.split_once('/').ok_or(())?;
 
-        Ok(AssetPath {
-            kind,
-            name: components.next().ok_or(())?.to_string(),
-        })
+        Self::from_relative(AssetKind::from_str(kind)?, relative)
     }
 }
 
 impl Display for AssetPath {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("{}/{}", self.kind.folder(), self.name))
+        f.write_fmt(format_args!("{}/{}", self.kind.folder(), self.relative()))
     }
 }
 
@@ -68,6 +123,6 @@ impl Add<String> for AssetKind {
     type Output = AssetPath;
 
     fn add(self, name: String) -> Self::Output {
-        AssetPath { kind: self, name }
+        AssetPath::new(self, name)
     }
 }
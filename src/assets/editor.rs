@@ -0,0 +1,404 @@
+use std::path::Path;
+
+use ::image::{DynamicImage, GenericImageView, imageops::FilterType};
+use anyhow::Result;
+use iced::{
+    Alignment, Element, Font,
+    Length::{Fill, Shrink},
+    Theme,
+    font::Weight,
+    widget::{
+        button, column, container, horizontal_space, image, mouse_area, opaque, row, slider, text,
+    },
+};
+
+use crate::{
+    assets::{AssetHandle, Crop},
+    style,
+};
+
+/// Size of the preview, the edited image is scaled to fit it.
+const PREVIEW_SIZE: u32 = 300;
+/// Smallest side a crop or a resized image can have.
+const MIN_SIZE: u32 = 16;
+
+#[derive(Debug, Clone)]
+pub enum EditorMessage {
+    CropToSquare,
+    ResetCrop,
+    CropSizeChanged(u32),
+    CropXChanged(u32),
+    CropYChanged(u32),
+    RotateLeft,
+    RotateRight,
+    FlipHorizontal,
+    FlipVertical,
+    SizeChanged(u32),
+    /// Stores the crop in the index, the file stays as it is.
+    SaveCrop,
+    /// Imports the edited image next to the original.
+    SaveVersion,
+    Cancel,
+}
+
+/// Edits an image for a character's portrait. A crop alone can be kept in the index without
+/// touching the file, everything else is saved as a new image.
+pub struct ImageEditor {
+    pub handle: AssetHandle,
+    name: String,
+    source: DynamicImage,
+    /// `source` scaled down to about the preview size, so editing stays fast for large images.
+    small_source: DynamicImage,
+    saved_crop: Option<Crop>,
+    crop: Option<Crop>,
+    /// Quarter turns clockwise.
+    quarter_turns: u8,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    /// Longest side of the result, `None` keeps it as large as it is.
+    size: Option<u32>,
+    preview: image::Handle,
+}
+
+impl ImageEditor {
+    /// Opens the image at `path`, with the crop the index has for it.
+    pub fn open(handle: AssetHandle, path: &Path, crop: Option<Crop>) -> Result<Self> {
+        let source = ::image::open(path)?;
+        let small_source = source.thumbnail(PREVIEW_SIZE, PREVIEW_SIZE);
+
+        let mut editor = Self {
+            handle,
+            name: path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            source,
+            small_source,
+            saved_crop: crop,
+            crop,
+            quarter_turns: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            size: None,
+            preview: image::Handle::from_rgba(0, 0, Vec::new()),
+        };
+
+        editor.update_preview();
+
+        Ok(editor)
+    }
+
+    pub fn update(&mut self, message: EditorMessage) {
+        let (width, height) = self.source.dimensions();
+
+        match message {
+            EditorMessage::CropToSquare => {
+                let side = width.min(height);
+
+                self.crop = Some(Crop {
+                    x: (width - side) / 2,
+                    y: (height - side) / 2,
+                    width: side,
+                    height: side,
+                });
+            }
+            EditorMessage::ResetCrop => self.crop = None,
+            EditorMessage::CropSizeChanged(side) => {
+                if let Some(crop) = &mut self.crop {
+                    let side = side.clamp(MIN_SIZE.min(width.min(height)), width.min(height));
+
+                    // shrinks and grows around its centre, as far as the image allows
+                    let center_x = crop.x + crop.width / 2;
+                    let center_y = crop.y + crop.height / 2;
+
+                    crop.x = center_x.saturating_sub(side / 2).min(width - side);
+                    crop.y = center_y.saturating_sub(side / 2).min(height - side);
+                    crop.width = side;
+                    crop.height = side;
+                }
+            }
+            EditorMessage::CropXChanged(x) => {
+                if let Some(crop) = &mut self.crop {
+                    crop.x = x.min(width.saturating_sub(crop.width));
+                }
+            }
+            EditorMessage::CropYChanged(y) => {
+                if let Some(crop) = &mut self.crop {
+                    crop.y = y.min(height.saturating_sub(crop.height));
+                }
+            }
+            EditorMessage::RotateLeft => self.quarter_turns = (self.quarter_turns + 3) % 4,
+            EditorMessage::RotateRight => self.quarter_turns = (self.quarter_turns + 1) % 4,
+            // flips apply after the rotation, so they're about what's on screen
+            EditorMessage::FlipHorizontal => self.flip_horizontal = !self.flip_horizontal,
+            EditorMessage::FlipVertical => self.flip_vertical = !self.flip_vertical,
+            EditorMessage::SizeChanged(size) => self.size = Some(size),
+            EditorMessage::SaveCrop | EditorMessage::SaveVersion | EditorMessage::Cancel => {}
+        }
+
+        // a crop smaller than the chosen size leaves nothing to scale down
+        let (width, height) = self.transformed_size();
+        self.size = self.size.filter(|size| *size < width.max(height));
+
+        self.update_preview();
+    }
+
+    /// The crop to store in the index.
+    pub fn crop(&self) -> Option<Crop> {
+        self.crop
+    }
+
+    /// The file name of the image, to name the new version after.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the only change is a crop different from the stored one, which can be kept
+    /// without a new image.
+    pub fn only_cropped(&self) -> bool {
+        self.crop != self.saved_crop && !self.transforms()
+    }
+
+    /// Whether saving a new version would change anything.
+    pub fn edited(&self) -> bool {
+        self.crop.is_some() || self.transforms()
+    }
+
+    /// The source image with every edit applied, at full resolution.
+    pub fn result(&self) -> DynamicImage {
+        let image = self.apply(&self.source, 1.0);
+
+        match self.size {
+            Some(size) => image.resize(size, size, FilterType::Lanczos3),
+            None => image,
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, EditorMessage> {
+        let mut title_font = Font::DEFAULT;
+        title_font.weight = Weight::Bold;
+
+        let (width, height) = self.source.dimensions();
+        let (result_width, result_height) = self.result_size();
+        let (transformed_width, transformed_height) = self.transformed_size();
+        let longest = transformed_width.max(transformed_height);
+
+        let crop_controls = self.crop.map(|crop| {
+            let short_side = width.min(height);
+
+            column![
+                labelled_slider(
+                    "Crop Size",
+                    slider(
+                        MIN_SIZE.min(short_side)..=short_side,
+                        crop.width.min(short_side),
+                        EditorMessage::CropSizeChanged
+                    )
+                ),
+                labelled_slider(
+                    "Horizontal",
+                    slider(
+                        0..=width.saturating_sub(crop.width),
+                        crop.x,
+                        EditorMessage::CropXChanged
+                    )
+                ),
+                labelled_slider(
+                    "Vertical",
+                    slider(
+                        0..=height.saturating_sub(crop.height),
+                        crop.y,
+                        EditorMessage::CropYChanged
+                    )
+                ),
+            ]
+            .spacing(4.0)
+        });
+
+        let controls = column![
+            row![
+                tool_button("Crop to Square", Some(EditorMessage::CropToSquare)),
+                tool_button(
+                    "Reset Crop",
+                    self.crop.is_some().then_some(EditorMessage::ResetCrop)
+                ),
+            ]
+            .spacing(6.0),
+        ]
+        .push_maybe(crop_controls)
+        .push(
+            row![
+                tool_button("Rotate Left", Some(EditorMessage::RotateLeft)),
+                tool_button("Rotate Right", Some(EditorMessage::RotateRight)),
+            ]
+            .spacing(6.0),
+        )
+        .push(
+            row![
+                tool_button("Flip Horizontal", Some(EditorMessage::FlipHorizontal)),
+                tool_button("Flip Vertical", Some(EditorMessage::FlipVertical)),
+            ]
+            .spacing(6.0),
+        )
+        .push(labelled_slider(
+            "Size",
+            slider(
+                MIN_SIZE.min(longest)..=longest,
+                self.size.unwrap_or(longest),
+                EditorMessage::SizeChanged,
+            ),
+        ))
+        .push(text(format!("{result_width} × {result_height} px")).size(14.0))
+        .spacing(8.0)
+        .width(260.0);
+
+        let card = container(
+            column![
+                text(format!("Edit {}", self.name))
+                    .font(title_font)
+                    .size(24.0),
+                row![
+                    container(
+                        image(self.preview.clone())
+                            .width(Shrink)
+                            .height(Shrink)
+                            .filter_method(image::FilterMethod::Nearest)
+                    )
+                    .center(PREVIEW_SIZE as f32),
+                    controls,
+                ]
+                .spacing(16.0),
+                row![
+                    button(text("Cancel").center())
+                        .on_press(EditorMessage::Cancel)
+                        .height(40.0)
+                        .style(style::secondary_button),
+                    horizontal_space(),
+                    button(text("Save Crop").center())
+                        .on_press_maybe(self.only_cropped().then_some(EditorMessage::SaveCrop))
+                        .height(40.0)
+                        .style(style::primary_button),
+                    button(text("Save as New Version").center())
+                        .on_press_maybe(self.edited().then_some(EditorMessage::SaveVersion))
+                        .height(40.0)
+                        .style(style::success_button),
+                ]
+                .spacing(10.0)
+                .width(Fill),
+            ]
+            .spacing(12.0),
+        )
+        .style(style::dialog)
+        .width(Shrink)
+        .padding(16.0);
+
+        opaque(
+            mouse_area(container(opaque(card)).style(backdrop).center(Fill))
+                .on_press(EditorMessage::Cancel),
+        )
+    }
+
+    /// Whether there's an edit a crop in the index can't express.
+    fn transforms(&self) -> bool {
+        self.quarter_turns != 0 || self.flip_horizontal || self.flip_vertical || self.size.is_some()
+    }
+
+    /// Crops, rotates and flips `image`, which is `source` scaled by `scale`.
+    fn apply(&self, image: &DynamicImage, scale: f32) -> DynamicImage {
+        let image = match self.crop {
+            Some(crop) => image.crop_imm(
+                (crop.x as f32 * scale) as u32,
+                (crop.y as f32 * scale) as u32,
+                ((crop.width as f32 * scale) as u32).max(1),
+                ((crop.height as f32 * scale) as u32).max(1),
+            ),
+            None => image.clone(),
+        };
+
+        let image = match self.quarter_turns {
+            1 => image.rotate90(),
+            2 => image.rotate180(),
+            3 => image.rotate270(),
+            _ => image,
+        };
+
+        let image = if self.flip_horizontal {
+            image.fliph()
+        } else {
+            image
+        };
+
+        if self.flip_vertical {
+            image.flipv()
+        } else {
+            image
+        }
+    }
+
+    /// Size of the cropped and rotated image, before it's resized.
+    fn transformed_size(&self) -> (u32, u32) {
+        let (width, height) = match self.crop {
+            Some(crop) => (crop.width, crop.height),
+            None => self.source.dimensions(),
+        };
+
+        if self.quarter_turns % 2 == 1 {
+            (height, width)
+        } else {
+            (width, height)
+        }
+    }
+
+    /// Size of [`Self::result`], without making it.
+    fn result_size(&self) -> (u32, u32) {
+        let (width, height) = self.transformed_size();
+
+        match self.size {
+            Some(size) => {
+                let scale = size as f32 / width.max(height) as f32;
+
+                (
+                    ((width as f32 * scale).round() as u32).max(1),
+                    ((height as f32 * scale).round() as u32).max(1),
+                )
+            }
+            None => (width, height),
+        }
+    }
+
+    fn update_preview(&mut self) {
+        let scale = self.small_source.width() as f32 / self.source.width().max(1) as f32;
+        let preview = self.apply(&self.small_source, scale).into_rgba8();
+
+        self.preview =
+            image::Handle::from_rgba(preview.width(), preview.height(), preview.into_raw());
+    }
+}
+
+fn tool_button(label: &str, on_press: Option<EditorMessage>) -> Element<'_, EditorMessage> {
+    button(text(label).center())
+        .on_press_maybe(on_press)
+        .width(Fill)
+        .style(style::secondary_button)
+        .into()
+}
+
+fn labelled_slider<'a>(
+    label: &'a str,
+    slider: slider::Slider<'a, u32, EditorMessage>,
+) -> Element<'a, EditorMessage> {
+    row![text(label).size(14.0).width(80.0), slider]
+        .spacing(8.0)
+        .align_y(Alignment::Center)
+        .into()
+}
+
+fn backdrop(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+
+    container::Style {
+        background: Some(palette.background.base.color.scale_alpha(0.4).into()),
+        ..container::transparent(theme)
+    }
+}
@@ -6,13 +6,21 @@ const DEFAULT_IMAGE: &[u8] = include_bytes!("../../assets/default.png").as_slice
 
 #[derive(Clone, Debug)]
 pub struct Image {
+    /// The full image, only read from disk once something draws it.
     pub handle: image::Handle,
+    pub thumbnail: image::Handle,
+    /// The part of the image set by the crop in the index, what characters show instead of the
+    /// whole image.
+    pub cropped: Option<image::Handle>,
+    /// Content hash of the file, see [`crate::assets::thumbnail::content_hash`].
+    pub hash: String,
+    /// Size of the file in bytes.
+    pub size: u64,
 }
 
 impl TryFrom<&Asset> for Image {
     type Error = ();
 
-    #[allow(unreachable_patterns)]
     fn try_from(asset: &Asset) -> Result<Self, Self::Error> {
         match asset {
             Asset::Image(image) => Ok(image.clone()),
@@ -0,0 +1,27 @@
+use iced::widget::image;
+
+use crate::assets::Asset;
+
+/// An audio, video or document asset. The app doesn't play or show these itself, they're opened
+/// with the system's default app and only their preview is drawn.
+#[derive(Clone, Debug)]
+pub struct Media {
+    /// Waveform, first frame or first page, `None` when it couldn't be made, see
+    /// [`crate::assets::thumbnail`].
+    pub preview: Option<image::Handle>,
+    /// Content hash of the file, see [`crate::assets::thumbnail::content_hash`].
+    pub hash: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+}
+
+impl TryFrom<&Asset> for Media {
+    type Error = ();
+
+    fn try_from(asset: &Asset) -> Result<Self, Self::Error> {
+        match asset {
+            Asset::Audio(media) | Asset::Video(media) | Asset::Document(media) => Ok(media.clone()),
+            Asset::Image(_) => Err(()),
+        }
+    }
+}
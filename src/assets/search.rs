@@ -0,0 +1,211 @@
+use crate::assets::IndexEntry;
+
+/// Bonus for a character matched right after the previous one. Higher than the word start
+/// bonus, so `anna` ranks `anna.pdf` above `a_n_n_a.pdf`.
+const CONSECUTIVE_BONUS: i32 = 8;
+/// Bonus for a character matched at the start of a word, like the `p` in `old/portrait.png`.
+const WORD_START_BONUS: i32 = 6;
+
+/// How well an asset matches a search, higher is better.
+#[derive(Debug, Clone, Default)]
+pub struct SearchMatch {
+    pub score: i32,
+    /// Char indices of [`crate::assets::AssetPath::relative`] that matched, to highlight them.
+    pub highlights: Vec<usize>,
+}
+
+/// Matches every word of `query` against the asset. A word starting with `#` has to be one of
+/// its tags, the others match its path fuzzily or are part of a tag. `None` if a word doesn't
+/// match at all.
+pub fn search(entry: &IndexEntry, query: &str) -> Option<SearchMatch> {
+    let path = entry.path.relative();
+    let tags: Vec<String> = entry.tags.iter().map(|tag| tag.to_lowercase()).collect();
+
+    query
+        .to_lowercase()
+        .split_whitespace()
+        .try_fold(SearchMatch::default(), |mut found, word| {
+            let (score, highlights) = match word.strip_prefix('#') {
+                Some(tag) => (tag.is_empty() || tags.iter().any(|t| t == tag))
+                    .then_some((tag_score(tag), Vec::new()))?,
+                None => {
+                    let in_tags = tags
+                        .iter()
+                        .any(|tag| tag.contains(word))
+                        .then_some((tag_score(word), Vec::new()));
+
+                    match (fuzzy_match(word, &path), in_tags) {
+                        (Some(in_path), Some(in_tags)) if in_tags.0 > in_path.0 => in_tags,
+                        (in_path, in_tags) => in_path.or(in_tags)?,
+                    }
+                }
+            };
+
+            found.score += score;
+            found.highlights.extend(highlights);
+
+            Some(found)
+        })
+}
+
+/// Score of a word found in the asset's tags, as good as matching it in one piece at the start of
+/// a word in the path.
+fn tag_score(word: &str) -> i32 {
+    let len = word.chars().count() as i32;
+
+    len + WORD_START_BONUS + (len - 1).max(0) * CONSECUTIVE_BONUS
+}
+
+/// Whether the characters of `pattern` appear in `text` in order, ignoring case. Returns the
+/// score of the best way they do and the char indices of `text` it matched.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i32, Vec<usize>)> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let first = *pattern.first()?;
+
+    // greedily matching from the first occurrence can miss a better match further on, like the
+    // `ph` in `pine/photo.png`, so every start is tried
+    (0..text.len())
+        .filter(|&start| lowercase_eq(text[start], first))
+        .filter_map(|start| match_from(&pattern, &text, start))
+        .max_by_key(|(score, _)| *score)
+}
+
+/// Matches `pattern` greedily, starting with its first character at `start`.
+fn match_from(pattern: &[char], text: &[char], start: usize) -> Option<(i32, Vec<usize>)> {
+    let mut indices = vec![start];
+    let mut at = start + 1;
+
+    for &c in &pattern[1..] {
+        let found = (at..text.len()).find(|&i| lowercase_eq(text[i], c))?;
+
+        indices.push(found);
+        at = found + 1;
+    }
+
+    let score = indices
+        .iter()
+        .enumerate()
+        .map(|(n, &i)| {
+            let consecutive = n > 0 && indices[n - 1] + 1 == i;
+            let word_start = i == 0
+                || matches!(text[i - 1], '/' | ' ' | '_' | '-' | '.')
+                || (text[i - 1].is_lowercase() && text[i].is_uppercase());
+
+            // characters skipped between matches count against it, so tighter matches rank
+            // higher
+            let gap = if n > 0 { i - indices[n - 1] - 1 } else { 0 };
+
+            1 + if consecutive { CONSECUTIVE_BONUS } else { 0 }
+                + if word_start { WORD_START_BONUS } else { 0 }
+                - gap.min(10) as i32
+        })
+        .sum();
+
+    Some((score, indices))
+}
+
+fn lowercase_eq(c: char, lowercase: char) -> bool {
+    c.to_lowercase().eq(std::iter::once(lowercase))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::AssetPath;
+
+    fn entry(path: &str, tags: &[&str]) -> IndexEntry {
+        let mut entry = IndexEntry::new(AssetPath::try_from(path).unwrap(), "");
+        entry.tags = tags.iter().map(|tag| tag.to_string()).collect();
+
+        entry
+    }
+
+    fn score(pattern: &str, text: &str) -> i32 {
+        fuzzy_match(pattern, text).unwrap().0
+    }
+
+    fn highlights(pattern: &str, text: &str) -> Vec<usize> {
+        fuzzy_match(pattern, text).unwrap().1
+    }
+
+    #[test]
+    fn consecutive_matches_rank_higher() {
+        assert!(score("anna", "anna.pdf") > score("anna", "a_n_n_a.pdf"));
+        assert!(score("anna", "a_n_n_a.pdf") > score("anna", "axxnxxnxxa.pdf"));
+    }
+
+    #[test]
+    fn word_starts_rank_higher() {
+        assert!(score("p", "old/portrait.jpg") > score("p", "old/snapshot.jpg"));
+        assert!(score("fp", "familyPortrait.png") > score("fp", "fishpond.png"));
+    }
+
+    #[test]
+    fn best_start_is_used() {
+        // the first `p` would only let the `h` match five characters later
+        assert_eq!(highlights("ph", "pine/photo.png"), [5, 6]);
+        assert_eq!(score("ph", "pine/photo.png"), score("ph", "photo.png"));
+    }
+
+    #[test]
+    fn highlights_are_char_indices() {
+        assert_eq!(highlights("ök", "björk.png"), [2, 4]);
+        assert_eq!(highlights("PNG", "Photo.png"), [6, 7, 8]);
+        assert_eq!(highlights("ph", "PHOTO.png"), [0, 1]);
+    }
+
+    #[test]
+    fn characters_have_to_be_in_order() {
+        assert!(fuzzy_match("ba", "abc").is_none());
+        assert!(fuzzy_match("abcd", "abc").is_none());
+        assert!(fuzzy_match("", "abc").is_none());
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let anna = entry("images/family/anna.png", &[]);
+
+        let found = search(&anna, "fam anna").unwrap();
+        assert_eq!(found.highlights, [0, 1, 2, 7, 8, 9, 10]);
+        assert_eq!(
+            found.score,
+            score("fam", "family/anna.png") + score("anna", "family/anna.png")
+        );
+
+        assert!(search(&anna, "anna ben").is_none());
+        assert!(search(&anna, "").is_some());
+    }
+
+    #[test]
+    fn hash_words_filter_by_tag() {
+        let anna = entry("images/anna.png", &["Main", "1900s"]);
+
+        let found = search(&anna, "#main").unwrap();
+        assert!(found.highlights.is_empty());
+        assert_eq!(found.score, tag_score("main"));
+
+        assert!(search(&anna, "#MAIN #1900s anna").is_some());
+        // tags have to match in full
+        assert!(search(&anna, "#mai").is_none());
+        assert!(search(&anna, "#side").is_none());
+        assert!(search(&anna, "#main ben").is_none());
+        // a lone `#` while typing doesn't hide anything
+        assert!(search(&anna, "#").is_some());
+    }
+
+    #[test]
+    fn plain_words_match_tags_too() {
+        let anna = entry("images/scan_01.png", &["Portrait"]);
+
+        // only in the tags
+        let found = search(&anna, "trait").unwrap();
+        assert!(found.highlights.is_empty());
+        assert_eq!(found.score, tag_score("trait"));
+
+        // the path matches better than the tag
+        let found = search(&anna, "scan").unwrap();
+        assert_eq!(found.highlights, [0, 1, 2, 3]);
+    }
+}
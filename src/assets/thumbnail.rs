@@ -0,0 +1,418 @@
+use std::{io::Cursor, path::Path, process::Command};
+
+use anyhow::{Result, anyhow};
+use iced::widget::image::Handle;
+use image::{DynamicImage, GenericImageView, ImageOutputFormat, Rgba, RgbaImage};
+use sha1::{Digest, Sha1};
+use thiserror::Error;
+
+use crate::{assets::Crop, io};
+
+/// Longest side of a thumbnail, enough for the 100×100 grid in the assets pane on high-DPI
+/// screens.
+const SIZE: u32 = 200;
+
+/// Where thumbnails are cached, relative to the project folder.
+const CACHE_FOLDER: &str = ".cache/thumbs";
+
+/// Where cropped images are cached, relative to the project folder.
+const CROP_FOLDER: &str = ".cache/crops";
+
+/// Where copies of images that replaced another one are kept, relative to the project folder.
+const REPLACED_FOLDER: &str = ".cache/replaced";
+
+/// Mid grey, readable on both the light and the dark theme.
+const WAVEFORM_COLOR: Rgba<u8> = Rgba([128, 140, 160, 255]);
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PreviewError {
+    #[error("Only uncompressed WAV files get a waveform.")]
+    UnsupportedAudio,
+    #[error("Running {0} failed, it might not be installed.")]
+    ToolFailed(&'static str),
+}
+
+/// Hex encoded SHA-1 of a file's contents.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha1::digest(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// The thumbnail of the image `bytes` with the content hash `hash`. It's made the first time and
+/// cached in the project's `.cache/thumbs`, named after the hash so renamed or copied images
+/// share it.
+pub fn thumbnail(project: &Path, hash: &str, bytes: &[u8]) -> Result<Handle> {
+    cached(project, hash, || Ok(image::load_from_memory(bytes)?))
+}
+
+/// The waveform of the WAV file `bytes`, cached like [`thumbnail`].
+pub fn waveform(project: &Path, hash: &str, bytes: &[u8]) -> Result<Handle> {
+    cached(project, hash, || {
+        let peaks = wav_peaks(bytes, SIZE as usize)?;
+        let height = SIZE / 2;
+
+        let mut image = RgbaImage::new(SIZE, height);
+
+        for (x, peak) in peaks.into_iter().enumerate() {
+            // silence still shows as a line
+            let half = ((peak * height as f32 / 2.0) as u32).max(1);
+
+            for y in (height / 2).saturating_sub(half)..(height / 2 + half).min(height) {
+                image.put_pixel(x as u32, y, WAVEFORM_COLOR);
+            }
+        }
+
+        Ok(DynamicImage::ImageRgba8(image))
+    })
+}
+
+/// The first frame of the video at `path`, taken with ffmpeg if it's installed. Cached like
+/// [`thumbnail`].
+pub fn video_frame(project: &Path, hash: &str, path: &Path) -> Result<Handle> {
+    cached(project, hash, || {
+        run(
+            "ffmpeg",
+            Command::new("ffmpeg")
+                .args(["-v", "error", "-i"])
+                .arg(path)
+                .args(["-frames:v", "1", "-f", "image2pipe", "-vcodec", "png", "-"]),
+        )
+    })
+}
+
+/// The first page of the PDF at `path`, rendered with poppler's pdftoppm if it's installed.
+/// Cached like [`thumbnail`].
+pub fn document_page(project: &Path, hash: &str, path: &Path) -> Result<Handle> {
+    cached(project, hash, || {
+        run(
+            "pdftoppm",
+            Command::new("pdftoppm")
+                .args(["-png", "-singlefile", "-f", "1", "-scale-to"])
+                .arg(SIZE.to_string())
+                .arg(path),
+        )
+    })
+}
+
+/// The part `crop` of the image at `path`, at full resolution so characters stay sharp. Cached in
+/// the project's `.cache/crops`, named after the hash and the rectangle.
+pub fn cropped(project: &Path, hash: &str, path: &Path, crop: Crop) -> Result<Handle> {
+    let Crop {
+        x,
+        y,
+        width,
+        height,
+    } = crop;
+
+    let cache_path = project
+        .join(CROP_FOLDER)
+        .join(format!("{hash}-{x}-{y}-{width}-{height}.png"));
+
+    if !cache_path.exists() {
+        let image = image::open(path)?.crop_imm(x, y, width, height);
+
+        let mut png = Cursor::new(Vec::new());
+        image.write_to(&mut png, ImageOutputFormat::Png)?;
+
+        std::fs::create_dir_all(project.join(CROP_FOLDER))?;
+        io::save(cache_path.clone(), png.into_inner())?;
+    }
+
+    Ok(Handle::from_path(cache_path))
+}
+
+/// A handle for the image at `path` that can't be mistaken for the image that was there before.
+/// iced caches images by the path of their handle, so it points to a copy named after the hash.
+pub fn replaced(project: &Path, hash: &str, path: &Path) -> Result<Handle> {
+    let cache_path = project
+        .join(REPLACED_FOLDER)
+        .join(hash)
+        .with_extension(path.extension().unwrap_or_default());
+
+    if !cache_path.exists() {
+        std::fs::create_dir_all(project.join(REPLACED_FOLDER))?;
+        std::fs::copy(path, &cache_path)?;
+    }
+
+    Ok(Handle::from_path(cache_path))
+}
+
+/// Takes the preview named after `hash` from the cache, or makes it with `make` and caches it.
+fn cached(
+    project: &Path,
+    hash: &str,
+    make: impl FnOnce() -> Result<DynamicImage>,
+) -> Result<Handle> {
+    let path = project.join(CACHE_FOLDER).join(hash).with_extension("png");
+
+    if !path.exists() {
+        let image = make()?;
+
+        // small images would get scaled up
+        let (width, height) = image.dimensions();
+        let thumbnail = if width > SIZE || height > SIZE {
+            image.thumbnail(SIZE, SIZE)
+        } else {
+            image
+        };
+
+        let mut png = Cursor::new(Vec::new());
+        thumbnail.write_to(&mut png, ImageOutputFormat::Png)?;
+
+        std::fs::create_dir_all(project.join(CACHE_FOLDER))?;
+        io::save(path.clone(), png.into_inner())?;
+    }
+
+    Ok(Handle::from_path(path))
+}
+
+/// Runs a tool that writes an image to stdout.
+fn run(name: &'static str, command: &mut Command) -> Result<DynamicImage> {
+    let output = command
+        .output()
+        .map_err(|err| anyhow!(err).context(PreviewError::ToolFailed(name)))?;
+
+    if !output.status.success() {
+        return Err(anyhow!(PreviewError::ToolFailed(name)));
+    }
+
+    Ok(image::load_from_memory(&output.stdout)?)
+}
+
+/// The loudest sample in each of `columns` slices of a WAV file, from 0 to 1. Only PCM and
+/// float samples are understood, compressed WAVs are rare enough.
+fn wav_peaks(bytes: &[u8], columns: usize) -> Result<Vec<f32>, PreviewError> {
+    let u16_at = |at: usize| Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?));
+    let u32_at = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+
+    if bytes.get(0..4) != Some(b"RIFF") || bytes.get(8..12) != Some(b"WAVE") {
+        return Err(PreviewError::UnsupportedAudio);
+    }
+
+    let mut format = None;
+    let mut data = None;
+    let mut at = 12;
+
+    while let (Some(id), Some(size)) = (bytes.get(at..at + 4), u32_at(at + 4)) {
+        let body = at + 8;
+
+        match id {
+            b"fmt " => {
+                let tag = match u16_at(body) {
+                    // WAVE_FORMAT_EXTENSIBLE keeps the actual format in its sub format
+                    Some(0xFFFE) => u16_at(body + 24),
+                    tag => tag,
+                };
+
+                format = tag.zip(u16_at(body + 2)).zip(u16_at(body + 14));
+            }
+            b"data" => data = bytes.get(body..(body + size as usize).min(bytes.len())),
+            _ => {}
+        }
+
+        // chunks are padded to an even length
+        at = body + size as usize + size as usize % 2;
+    }
+
+    let (((tag, channels), bits), data) = format.zip(data).ok_or(PreviewError::UnsupportedAudio)?;
+
+    let sample: fn(&[u8]) -> f32 = match (tag, bits) {
+        (1, 8) => |s| (s[0] as f32 - 128.0) / 128.0,
+        (1, 16) => |s| i16::from_le_bytes([s[0], s[1]]) as f32 / i16::MAX as f32,
+        (1, 24) => |s| i32::from_le_bytes([0, s[0], s[1], s[2]]) as f32 / i32::MAX as f32,
+        (1, 32) => |s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / i32::MAX as f32,
+        (3, 32) => |s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+        _ => return Err(PreviewError::UnsupportedAudio),
+    };
+
+    let frame = (bits as usize / 8) * channels.max(1) as usize;
+    let frames = data.len() / frame;
+
+    if frames == 0 {
+        return Ok(vec![0.0; columns]);
+    }
+
+    Ok((0..columns)
+        .map(|column| {
+            let start = column * frames / columns;
+            let end = ((column + 1) * frames / columns).max(start + 1).min(frames);
+
+            data[start * frame..end * frame]
+                .chunks_exact(bits as usize / 8)
+                .map(|s| sample(s).abs())
+                .fold(0.0, f32::max)
+                .min(1.0)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A RIFF chunk, padded to an even length.
+    fn chunk(id: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut chunk = id.to_vec();
+        chunk.extend((body.len() as u32).to_le_bytes());
+        chunk.extend(body);
+
+        if body.len() % 2 == 1 {
+            chunk.push(0);
+        }
+
+        chunk
+    }
+
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+
+        let mut wav = b"RIFF".to_vec();
+        wav.extend((body.len() as u32 + 4).to_le_bytes());
+        wav.extend(b"WAVE");
+        wav.extend(body);
+
+        wav
+    }
+
+    fn fmt(tag: u16, channels: u16, bits: u16) -> Vec<u8> {
+        let block_align = channels * bits / 8;
+
+        let mut body = Vec::new();
+        body.extend(tag.to_le_bytes());
+        body.extend(channels.to_le_bytes());
+        body.extend(8000u32.to_le_bytes());
+        body.extend((8000 * block_align as u32).to_le_bytes());
+        body.extend(block_align.to_le_bytes());
+        body.extend(bits.to_le_bytes());
+
+        chunk(b"fmt ", &body)
+    }
+
+    /// A WAVE_FORMAT_EXTENSIBLE format chunk, the sub format GUID starts with `tag`.
+    fn extensible_fmt(tag: u16, channels: u16, bits: u16) -> Vec<u8> {
+        let mut body = fmt(0xFFFE, channels, bits)[8..].to_vec();
+        body.extend(22u16.to_le_bytes());
+        body.extend(bits.to_le_bytes());
+        body.extend(0u32.to_le_bytes());
+        body.extend(tag.to_le_bytes());
+        body.extend(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xaa\x00\x38\x9b\x71");
+
+        chunk(b"fmt ", &body)
+    }
+
+    fn assert_peaks(peaks: Vec<f32>, expected: &[f32]) {
+        assert_eq!(peaks.len(), expected.len(), "{peaks:?}");
+
+        for (peak, expected) in peaks.iter().zip(expected) {
+            assert!((peak - expected).abs() < 0.01, "{peaks:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn pcm_8_bit() {
+        let file = wav(&[fmt(1, 1, 8), chunk(b"data", &[128, 192, 128, 0])]);
+
+        assert_peaks(wav_peaks(&file, 2).unwrap(), &[0.5, 1.0]);
+    }
+
+    #[test]
+    fn pcm_16_bit_stereo() {
+        let samples: Vec<u8> = [0i16, -16384, i16::MAX, 0, 100, -100]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect();
+        let file = wav(&[fmt(1, 2, 16), chunk(b"data", &samples)]);
+
+        // each column covers one frame, with both channels
+        assert_peaks(wav_peaks(&file, 3).unwrap(), &[0.5, 1.0, 0.003]);
+    }
+
+    #[test]
+    fn pcm_24_bit() {
+        let samples: Vec<u8> = [0x40_0000i32, -0x80_0000, 0x20_0000]
+            .into_iter()
+            .flat_map(|sample| sample.to_le_bytes()[..3].to_vec())
+            .collect();
+        let file = wav(&[fmt(1, 1, 24), chunk(b"data", &samples)]);
+
+        assert_peaks(wav_peaks(&file, 3).unwrap(), &[0.5, 1.0, 0.25]);
+    }
+
+    #[test]
+    fn float() {
+        let samples: Vec<u8> = [0.25f32, -0.75, 2.0, 0.0]
+            .into_iter()
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        let file = wav(&[fmt(3, 1, 32), chunk(b"data", &samples)]);
+
+        // louder than full scale is cut off
+        assert_peaks(wav_peaks(&file, 2).unwrap(), &[0.75, 1.0]);
+    }
+
+    #[test]
+    fn extensible_format() {
+        let samples: Vec<u8> = [8192i16, -32768]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect();
+        let pcm = wav(&[extensible_fmt(1, 1, 16), chunk(b"data", &samples)]);
+        assert_peaks(wav_peaks(&pcm, 2).unwrap(), &[0.25, 1.0]);
+
+        let adpcm = wav(&[extensible_fmt(2, 1, 16), chunk(b"data", &samples)]);
+        assert_eq!(wav_peaks(&adpcm, 2), Err(PreviewError::UnsupportedAudio));
+    }
+
+    #[test]
+    fn truncated_data() {
+        let samples: Vec<u8> = [16384i16, i16::MAX, 0]
+            .into_iter()
+            .flat_map(i16::to_le_bytes)
+            .collect();
+        let mut data = chunk(b"data", &samples);
+        // the header promises a lot more than there is, and the last sample is cut in half
+        data[4..8].copy_from_slice(&1000u32.to_le_bytes());
+        data.pop();
+
+        let file = wav(&[fmt(1, 1, 16), data]);
+
+        assert_peaks(wav_peaks(&file, 2).unwrap(), &[0.5, 1.0]);
+    }
+
+    #[test]
+    fn odd_sized_chunks() {
+        let file = wav(&[
+            chunk(b"LIST", b"odd"),
+            fmt(1, 1, 8),
+            chunk(b"junk", &[1]),
+            chunk(b"data", &[128, 255, 64]),
+        ]);
+
+        assert_peaks(wav_peaks(&file, 3).unwrap(), &[0.0, 0.99, 0.5]);
+    }
+
+    #[test]
+    fn empty_data() {
+        let file = wav(&[fmt(1, 1, 16), chunk(b"data", &[])]);
+
+        assert_eq!(wav_peaks(&file, 4), Ok(vec![0.0; 4]));
+    }
+
+    #[test]
+    fn unsupported_files() {
+        let samples = [0u8; 8];
+
+        for file in [
+            b"not a wav file".to_vec(),
+            wav(&[fmt(1, 1, 16)]),
+            wav(&[chunk(b"data", &samples)]),
+            // ADPCM
+            wav(&[fmt(2, 1, 4), chunk(b"data", &samples)]),
+            wav(&[fmt(1, 1, 12), chunk(b"data", &samples)]),
+        ] {
+            assert_eq!(wav_peaks(&file, 4), Err(PreviewError::UnsupportedAudio));
+        }
+    }
+}
@@ -2,23 +2,25 @@ use std::io;
 
 use crate::{
     assets::{
-        Asset, AssetHandle, AssetKind, AssetPath, AssetsData, AssetsMessage, Image, ViewMode,
+        Asset, AssetFilter, AssetHandle, AssetKind, AssetPath, AssetsData, AssetsMessage,
+        IndexEntry, LoadId, Loading, SortMode, Usage, ViewMode,
+        search::{self, SearchMatch},
     },
-    io::{AssetsError, load_dir, write_index},
+    io::{load_assets, load_index, write_index},
     style,
     widgets::{self, dnd::dnd_provider, dropdown, icons},
 };
 
 use iced::{
-    Alignment, Element,
+    Alignment, Element, Font,
     Length::{Fill, Shrink},
-    Task,
+    Task, font,
     widget::{
-        self, button, column, container, horizontal_space, responsive, row, scrollable, text,
-        text_input,
+        self, button, column, container, horizontal_space, pick_list, responsive, rich_text, row,
+        scrollable, span, text, text_input,
     },
 };
-use iced_aw::ContextMenu;
+use iced_aw::{ContextMenu, TabBar, TabLabel};
 
 use anyhow::anyhow;
 
@@ -43,8 +45,35 @@ pub fn update(state: &mut AssetsData, message: AssetsMessage) -> Task<AssetsMess
                 );
                 AssetsMessage::LoadFailed
             } else {
-                match load_dir(path.clone()) {
-                    Ok(files) => AssetsMessage::LoadCompleted(path, files),
+                match load_index(path.clone()) {
+                    Ok(index) => {
+                        state.assets.clear();
+                        state.index = index.clone();
+
+                        let load = LoadId(state.last_load.0.wrapping_add(1));
+                        state.last_load = load;
+
+                        let (task, handle) = Task::run(
+                            load_assets(path.clone(), index),
+                            move |(id, asset_path, asset)| {
+                                AssetsMessage::AssetLoaded(load, id, asset_path, asset)
+                            },
+                        )
+                        .chain(Task::done(AssetsMessage::LoadCompleted(load)))
+                        .abortable();
+
+                        // replacing the previous folder's loading cancels it, what it already
+                        // sent is ignored
+                        state.loading = Some(Loading {
+                            load,
+                            _task: handle.abort_on_drop(),
+                            loaded: 0,
+                            total: state.index.len(),
+                            hashes_added: false,
+                        });
+
+                        return Task::done(AssetsMessage::LoadStarted(path)).chain(task);
+                    }
                     Err(err) => {
                         state.last_error =
                             Some(anyhow!(err).context(format!("Couldn't load {path:?}")));
@@ -56,10 +85,46 @@ pub fn update(state: &mut AssetsData, message: AssetsMessage) -> Task<AssetsMess
 
             Task::done(message)
         }
-        AssetsMessage::LoadCompleted(_, assets) => {
-            for (id, (asset_path, asset)) in assets.into_iter() {
-                state.index.insert(id, asset_path.clone());
-                state.assets.insert(asset_path, asset);
+        AssetsMessage::LoadStarted(_) => Task::none(),
+        AssetsMessage::AssetLoaded(load, id, asset_path, asset) => {
+            if !state.is_loading(load) {
+                return Task::none();
+            }
+
+            // the index might have changed in the meantime
+            if let Some(asset) = asset
+                && let Some(entry) = state.index.get_mut(&id)
+                && entry.path == asset_path
+            {
+                if entry.hash != asset.hash() {
+                    entry.hash = asset.hash().to_string();
+
+                    if let Some(loading) = &mut state.loading {
+                        loading.hashes_added = true;
+                    }
+                }
+
+                state.insert(asset_path, asset);
+            }
+
+            if let Some(loading) = &mut state.loading {
+                loading.loaded += 1;
+            }
+
+            Task::none()
+        }
+        AssetsMessage::LoadCompleted(load) => {
+            if !state.is_loading(load) {
+                return Task::none();
+            }
+
+            if let Some(Loading {
+                hashes_added: true, ..
+            }) = state.loading.take()
+                && let Err(err) = write_index(&state.index, state.folder.clone())
+            {
+                state.last_error = Some(err.context("Couldn't save the content hashes"));
+                return Task::done(AssetsMessage::LoadFailed);
             }
 
             Task::none()
@@ -81,6 +146,23 @@ pub fn update(state: &mut AssetsData, message: AssetsMessage) -> Task<AssetsMess
             state.view_mode = view;
             Task::none()
         }
+        AssetsMessage::SortChanged(sort) => {
+            state.sort = sort;
+            Task::none()
+        }
+        AssetsMessage::FilterChanged(filter) => {
+            state.filter = filter;
+            Task::none()
+        }
+        AssetsMessage::KindChanged(kind) => {
+            state.view = kind;
+            state.open_folder.clear();
+            Task::none()
+        }
+        AssetsMessage::OpenFolder(folders) => {
+            state.open_folder = folders;
+            Task::none()
+        }
         AssetsMessage::ShowHideDropdown => {
             state.view_dropdown_open = !state.view_dropdown_open;
             Task::none()
@@ -94,65 +176,133 @@ pub fn update(state: &mut AssetsData, message: AssetsMessage) -> Task<AssetsMess
                 return Task::none();
             };
 
-            let Some(old_path) = state.index.get(&handle.0).cloned() else {
-                return Task::none();
-            };
-
-            let new_path = AssetPath::new(old_path.kind(), new_name.clone());
+            match state.rename(handle, &new_name) {
+                Ok(old_path) => {
+                    let new_path = state.index[&handle.0].path.clone();
 
-            state.index.insert(handle.0, new_path.clone());
-
-            let folder = state.folder.clone().unwrap();
+                    Task::done(AssetsMessage::AssetRenamed(handle, old_path, new_path))
+                }
+                Err(err) => {
+                    state.last_error = Some(err);
 
-            let from = folder.clone() + old_path.clone();
+                    Task::done(AssetsMessage::RenameAssetFailed(handle))
+                }
+            }
+        }
+        AssetsMessage::AssetRenamed(..) => Task::none(),
+        AssetsMessage::RenameAssetFailed(..) => Task::none(),
+        AssetsMessage::SetTagInput(val) => {
+            state.tagging = val;
+            Task::none()
+        }
+        AssetsMessage::AddTag => {
+            let Some((handle, tag)) = state.tagging.take() else {
+                return Task::none();
+            };
 
-            let to = folder + new_path.clone();
+            if let Err(err) = state.add_tag(handle, &tag) {
+                state.last_error = Some(err);
+                return Task::done(AssetsMessage::TagAssetFailed(handle));
+            }
 
-            let err_ctx = format!("Couldn't rename {from:?} to {to:?}");
+            Task::none()
+        }
+        AssetsMessage::RemoveTag(handle, tag) => {
+            if let Err(err) = state.remove_tag(handle, &tag) {
+                state.last_error = Some(err);
+                return Task::done(AssetsMessage::TagAssetFailed(handle));
+            }
 
-            if let Err(err) = std::fs::rename(from, to) {
-                state.last_error = Some(anyhow!(err).context(err_ctx));
+            Task::none()
+        }
+        AssetsMessage::TagAssetFailed(..) => Task::none(),
+        AssetsMessage::EditImage(..)
+        | AssetsMessage::DeleteAsset(..)
+        | AssetsMessage::WatchFailed(..) => Task::none(),
+        AssetsMessage::FileChanged(event) => {
+            if let Err(err) = state.file_changed(event) {
+                state.last_error = Some(err);
+                return Task::done(AssetsMessage::LoadFailed);
+            }
 
-                return Task::done(AssetsMessage::RenameAssetFailed(handle));
-            };
+            Task::none()
+        }
+    }
+}
 
-            let res = write_index(&state.index, state.folder.clone());
+/// `label` with the characters at the char indices `highlights` in bold and underlined.
+fn highlighted<'a>(
+    label: &str,
+    highlights: &[usize],
+) -> widget::text::Rich<'a, AssetsMessage, iced::Theme, iced::Renderer> {
+    let bold = Font {
+        weight: font::Weight::Bold,
+        ..Font::DEFAULT
+    };
 
-            match res {
-                Ok(_) => {
-                    let asset = state.assets.remove(&old_path).unwrap();
-                    state.assets.insert(new_path.clone(), asset);
+    let mut runs: Vec<(bool, String)> = Vec::new();
 
-                    Task::none()
-                }
-                Err(err) => {
-                    if let Some(err) = err.downcast_ref::<AssetsError>() {
-                        state.last_error = Some(anyhow!(err.clone()).context(err_ctx));
+    for (i, c) in label.chars().enumerate() {
+        let is_match = highlights.contains(&i);
 
-                        return Task::done(AssetsMessage::RenameAssetFailed(handle));
-                    }
-                    Task::none()
-                }
-            }
+        match runs.last_mut() {
+            Some((run_is_match, run)) if *run_is_match == is_match => run.push(c),
+            _ => runs.push((is_match, c.to_string())),
         }
-        AssetsMessage::RenameAssetFailed(..) => Task::none(),
     }
+
+    rich_text(
+        runs.into_iter()
+            .map(|(is_match, run)| {
+                span(run)
+                    .font_maybe(is_match.then_some(bold))
+                    .underline(is_match)
+            })
+            .collect::<Vec<_>>(),
+    )
 }
 
-fn image_item<'a>(
+fn asset_item<'a>(
     i: usize,
     handle: AssetHandle,
-    path: &'a AssetPath,
+    entry: &'a IndexEntry,
     state: &'a AssetsData,
-    img: &'a Image,
+    asset: &'a Asset,
+    found: Option<SearchMatch>,
 ) -> Element<'a, AssetsMessage, iced::Theme, iced::Renderer> {
-    let rename_input = state.renaming.as_ref().map(|(rn_handle, input)| {
-        text_input("Rename...", input.as_str())
-            .on_input(|input| AssetsMessage::SetRenameInput(Some((*rn_handle, input))))
-            .on_submit(AssetsMessage::RenameAsset)
-    });
-
-    let file_name = path.name();
+    let rename_input = state
+        .renaming
+        .as_ref()
+        .filter(|(rn_handle, _)| *rn_handle == handle)
+        .map(|(rn_handle, input)| {
+            text_input("Rename, / moves to a folder...", input.as_str())
+                .on_input(|input| AssetsMessage::SetRenameInput(Some((*rn_handle, input))))
+                .on_submit(AssetsMessage::RenameAsset)
+        });
+
+    let tag_input = state
+        .tagging
+        .as_ref()
+        .filter(|(tag_handle, _)| *tag_handle == handle)
+        .map(|(tag_handle, input)| {
+            text_input("Tag...", input.as_str())
+                .on_input(|input| AssetsMessage::SetTagInput(Some((*tag_handle, input))))
+                .on_submit(AssetsMessage::AddTag)
+                .style(style::text_input)
+        });
+
+    // search and filter results come from every folder
+    let label = if state.lists_all_folders() {
+        highlighted(
+            &entry.path.relative(),
+            found
+                .map(|found| found.highlights)
+                .as_deref()
+                .unwrap_or_default(),
+        )
+    } else {
+        highlighted(entry.path.name(), &[])
+    };
 
     dnd_provider(
         AssetsMessage::SetPayload,
@@ -160,14 +310,12 @@ fn image_item<'a>(
         match state.view_mode {
             ViewMode::Thumbnails => button(
                 column![
-                    widget::image(&img.handle)
-                        .height(100.0)
-                        .width(100.0)
-                        .filter_method(widget::image::FilterMethod::Nearest),
+                    preview(asset, &entry.path, 100.0),
                     rename_input
                         .map(|ri| Element::from(ri.width(100.0).align_x(Alignment::Center)))
-                        .unwrap_or(text(file_name).width(100.0).center().into())
+                        .unwrap_or(label.width(100.0).center().into())
                 ]
+                .push_maybe(tag_input.map(|input| input.width(100.0)))
                 .spacing(5.0)
                 .padding(5.0),
             )
@@ -176,14 +324,18 @@ fn image_item<'a>(
             .on_press(AssetsMessage::OpenAsset(handle)),
             ViewMode::List => button(
                 row![
-                    widget::image(&img.handle)
-                        .height(30)
-                        .width(30)
-                        .filter_method(widget::image::FilterMethod::Nearest),
-                    rename_input
-                        .map(Element::from)
-                        .unwrap_or(text(file_name).into())
+                    preview(asset, &entry.path, 30.0),
+                    rename_input.map(Element::from).unwrap_or(label.into()),
+                    horizontal_space(),
+                    row(entry.tags.iter().map(|tag| {
+                        container(text(tag).size(12.0))
+                            .padding([1.0, 6.0])
+                            .style(style::tag)
+                            .into()
+                    }))
+                    .spacing(4.0),
                 ]
+                .push_maybe(tag_input.map(|input| input.width(120.0)))
                 .width(Fill)
                 .height(40)
                 .spacing(10)
@@ -192,59 +344,190 @@ fn image_item<'a>(
             )
             .on_press(AssetsMessage::OpenAsset(handle))
             .padding(0)
-            .style(style::list_item(i % 2 == 0)),
+            .style(style::list_item(i.is_multiple_of(2))),
         },
     )
 }
 
-pub fn view(state: &AssetsData) -> Element<'_, AssetsMessage> {
-    let content = match state.view {
-        AssetKind::Image => {
-            let mut images: Vec<_> = state
-                .index
-                .iter()
-                .filter_map(|(id, asset_path)| {
-                    state.assets.get(asset_path).and_then(|asset| {
-                        asset_path
-                            .to_string()
-                            .to_lowercase()
-                            .contains(&state.query().to_lowercase())
-                            .then_some(asset_path)
-                            // WARNING: change to .and_then when adding new types of assets
-                            .map(|path| match asset {
-                                Asset::Image(img) => (*id, path, img),
-                            })
-                    })
-                })
-                .collect();
-
-            images.sort_by(|a, b| a.0.cmp(&b.0));
-
-            let images = images.into_iter().enumerate().map(|(i, (id, path, img))| {
-                let handle = AssetHandle(id);
-                let img_element = image_item(i, handle, path, state, img);
-
-                ContextMenu::new(img_element, move || {
-                    container(column![widgets::menu_button(
-                        "Rename",
-                        AssetsMessage::SetRenameInput(Some((handle, path.to_string())))
-                    )])
-                    .padding(4)
-                    .style(style::dropdown)
-                    .into()
-                })
+/// A sub-folder of the listed folder, opened by clicking it.
+fn folder_item<'a>(
+    i: usize,
+    name: &'a str,
+    state: &'a AssetsData,
+) -> Element<'a, AssetsMessage, iced::Theme, iced::Renderer> {
+    let mut path = state.open_folder.clone();
+    path.push(name.to_string());
+
+    let icon = |size: f32| {
+        container(text("Folder").size(size / 4.0))
+            .center(size)
+            .style(container::bordered_box)
+    };
+
+    match state.view_mode {
+        ViewMode::Thumbnails => button(
+            column![icon(100.0), text(name).width(100.0).center()]
+                .spacing(5.0)
+                .padding(5.0),
+        )
+        .padding(0)
+        .style(style::list_item(false)),
+        ViewMode::List => button(
+            row![icon(30.0), text(name)]
+                .width(Fill)
+                .height(40)
+                .spacing(10)
+                .padding(5)
+                .align_y(Alignment::Center),
+        )
+        .padding(0)
+        .style(style::list_item(i.is_multiple_of(2))),
+    }
+    .on_press(AssetsMessage::OpenFolder(path))
+    .into()
+}
+
+/// The asset's preview in a `size` square, or its file extension if it doesn't have one.
+pub fn preview<'a, Message: 'a>(
+    asset: &'a Asset,
+    path: &AssetPath,
+    size: f32,
+) -> Element<'a, Message, iced::Theme, iced::Renderer> {
+    match asset.preview() {
+        Some(preview) => widget::image(preview)
+            .height(size)
+            .width(size)
+            .filter_method(widget::image::FilterMethod::Nearest)
+            .into(),
+        None => {
+            let extension = path
+                .name()
+                .rsplit_once('.')
+                .map(|(_, extension)| extension.to_uppercase())
+                .unwrap_or_else(|| path.kind().name().to_string());
+
+            container(text(extension).size(size / 4.0))
+                .center(size)
+                .style(container::bordered_box)
                 .into()
-            });
+        }
+    }
+}
 
-            let layout = match state.view_mode {
-                ViewMode::Thumbnails => Element::from(row(images).spacing(5).width(Fill).wrap()),
-                ViewMode::List => Element::from(column(images).spacing(2).width(Fill)),
+pub fn view<'a>(state: &'a AssetsData, usage: &Usage) -> Element<'a, AssetsMessage> {
+    let flat = state.lists_all_folders();
+
+    let mut assets: Vec<_> = state
+        .index
+        .iter()
+        .filter(|(_, entry)| entry.path.kind() == state.view)
+        .filter(|(id, _)| match state.filter {
+            AssetFilter::All => true,
+            AssetFilter::Unused => !usage.counts.contains_key(&AssetHandle(**id)),
+            AssetFilter::UsedBySelection => usage.selected.contains(&AssetHandle(**id)),
+        })
+        .filter_map(|(id, entry)| {
+            let asset = state.assets.get(&entry.path)?;
+
+            let found = if state.query().trim().is_empty() {
+                None
+            } else {
+                Some(search::search(entry, state.query())?)
             };
 
-            scrollable(layout).style(style::scrollable)
-        }
+            (flat || entry.path.folders() == state.open_folder).then_some((
+                AssetHandle(*id),
+                entry,
+                asset,
+                found,
+            ))
+        })
+        .collect();
+
+    let uses = |handle: &AssetHandle| usage.counts.get(handle).copied().unwrap_or_default();
+
+    // the best matches first, then the chosen order, then the order they were added in
+    assets.sort_by(|a, b| {
+        let score = |found: &Option<SearchMatch>| found.as_ref().map(|found| found.score);
+
+        score(&b.3)
+            .cmp(&score(&a.3))
+            .then_with(|| match state.sort {
+                SortMode::Name => {
+                    a.1.path
+                        .relative()
+                        .to_lowercase()
+                        .cmp(&b.1.path.relative().to_lowercase())
+                }
+                SortMode::DateAdded => b.1.added.cmp(&a.1.added),
+                SortMode::Size => b.2.size().cmp(&a.2.size()),
+                SortMode::Usage => uses(&b.0).cmp(&uses(&a.0)),
+            })
+            .then(a.0.0.cmp(&b.0.0))
+    });
+
+    let folders = if flat {
+        Vec::new()
+    } else {
+        state.sub_folders()
     };
 
+    let folder_count = folders.len();
+
+    let folder_items = folders
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| folder_item(i, name, state));
+
+    let asset_items = assets
+        .into_iter()
+        .enumerate()
+        .map(|(i, (handle, entry, asset, found))| {
+            let item = asset_item(folder_count + i, handle, entry, state, asset, found);
+
+            ContextMenu::new(item, move || {
+                container(
+                    column![
+                        widgets::menu_button(
+                            "Rename",
+                            AssetsMessage::SetRenameInput(Some((handle, entry.path.relative())))
+                        ),
+                        widgets::menu_button(
+                            "Add Tag",
+                            AssetsMessage::SetTagInput(Some((handle, String::new())))
+                        ),
+                    ]
+                    .push_maybe(matches!(asset, Asset::Image(_)).then(|| {
+                        widgets::menu_button("Edit Image", AssetsMessage::EditImage(handle))
+                    }))
+                    .extend(entry.tags.iter().map(|tag| {
+                        widgets::menu_button(
+                            format!("Remove Tag \"{tag}\""),
+                            AssetsMessage::RemoveTag(handle, tag.clone()),
+                        )
+                        .into()
+                    }))
+                    .push(widgets::menu_button(
+                        "Delete",
+                        AssetsMessage::DeleteAsset(handle),
+                    )),
+                )
+                .padding(4)
+                .style(style::dropdown)
+                .into()
+            })
+            .into()
+        });
+
+    let items = folder_items.chain(asset_items);
+
+    let layout = match state.view_mode {
+        ViewMode::Thumbnails => Element::from(row(items).spacing(5).width(Fill).wrap()),
+        ViewMode::List => Element::from(column(items).spacing(2).width(Fill)),
+    };
+
+    let content = scrollable(layout).style(style::scrollable);
+
     let top_row = responsive(move |size| {
         let dropdown = dropdown(
             state.view_dropdown_open,
@@ -273,7 +556,17 @@ pub fn view(state: &AssetsData) -> Element<'_, AssetsMessage> {
             .on_press(AssetsMessage::QueryChanged(Some("".to_string())))
             .style(style::menu_button);
 
-        let top_right_row = row![dropdown].spacing(4.0);
+        let sort =
+            pick_list(SortMode::ALL, Some(state.sort), AssetsMessage::SortChanged).text_size(13.0);
+
+        let filter = pick_list(
+            AssetFilter::ALL,
+            Some(state.filter),
+            AssetsMessage::FilterChanged,
+        )
+        .text_size(13.0);
+
+        let top_right_row = row![sort, filter, dropdown].spacing(4.0);
 
         if let Some(query) = &state.query {
             let search_bar = text_input("Search...", query)
@@ -305,7 +598,55 @@ pub fn view(state: &AssetsData) -> Element<'_, AssetsMessage> {
         }
     });
 
-    column![container(top_row).height(30), content,]
+    let tabs = AssetKind::all()
+        .iter()
+        .fold(TabBar::new(AssetsMessage::KindChanged), |tabs, kind| {
+            let count = state
+                .assets
+                .keys()
+                .filter(|path| path.kind() == *kind)
+                .count();
+
+            tabs.push(*kind, TabLabel::Text(format!("{} ({count})", kind.name())))
+        })
+        .set_active_tab(&state.view)
+        .text_size(14.0)
+        .padding(4.0)
+        .spacing(2.0)
+        .style(style::tab_bar);
+
+    // the way back up, hidden at the top of the kind's folder
+    let breadcrumbs = (!state.open_folder.is_empty() && !flat).then(|| {
+        let up_to = |depth: usize| -> Element<'_, AssetsMessage> {
+            let button = button(text(match depth {
+                0 => state.view.name(),
+                depth => state.open_folder[depth - 1].as_str(),
+            }))
+            .padding([2, 6])
+            .style(style::menu_button)
+            .on_press_maybe(
+                (depth < state.open_folder.len())
+                    .then(|| AssetsMessage::OpenFolder(state.open_folder[..depth].to_vec())),
+            );
+
+            match depth {
+                0 => button.into(),
+                _ => row![text("/"), button]
+                    .spacing(2.0)
+                    .align_y(Alignment::Center)
+                    .into(),
+            }
+        };
+
+        row((0..=state.open_folder.len()).map(up_to))
+            .spacing(2.0)
+            .align_y(Alignment::Center)
+            .wrap()
+    });
+
+    column![tabs, container(top_row).height(30)]
+        .push_maybe(breadcrumbs)
+        .push(content)
         .spacing(4.0)
         .into()
 }
@@ -0,0 +1,413 @@
+use std::path::PathBuf;
+
+use iced::{
+    Subscription,
+    futures::{SinkExt, StreamExt, channel::mpsc},
+};
+
+use crate::assets::{AssetKind, AssetPath};
+
+/// A change to a file in one of a project's asset folders, made by the app or by anything else.
+#[derive(Debug, Clone)]
+pub enum FileEvent {
+    /// The file was created or written to.
+    Written(AssetPath),
+    /// The file was deleted or moved out of the asset folders.
+    Removed(AssetPath),
+    Renamed {
+        from: AssetPath,
+        to: AssetPath,
+    },
+    /// A sub-folder of the folder of `kind` was renamed, along with everything in it.
+    FolderRenamed {
+        kind: AssetKind,
+        from: Vec<String>,
+        to: Vec<String>,
+    },
+    /// A sub-folder of the folder of `kind` was deleted or moved out of the asset folders.
+    FolderRemoved {
+        kind: AssetKind,
+        folders: Vec<String>,
+    },
+}
+
+/// Watches the asset folders of the project in `folder`. Only implemented with inotify on
+/// Linux, on other platforms nothing is ever published. Failures are published as errors,
+/// nothing follows one that stops the watch, others only leave a folder unwatched.
+pub fn watch(folder: PathBuf) -> Subscription<Result<FileEvent, String>> {
+    Subscription::run_with_id(
+        folder.clone(),
+        iced::stream::channel(100, move |mut output| async move {
+            let (sender, mut receiver) = mpsc::unbounded();
+
+            let stop = match platform::Stop::new() {
+                Ok(stop) => stop,
+                Err(err) => {
+                    let _ = output
+                        .send(Err(format!("Couldn't watch {folder:?}: {err}")))
+                        .await;
+                    return;
+                }
+            };
+
+            // reading blocks, so it's done on its own thread. `stop` goes with the
+            // subscription, which wakes the thread up to close the watch.
+            let listener = stop.listener();
+
+            std::thread::spawn(move || {
+                let publish = |event| sender.unbounded_send(event).is_ok();
+
+                if let Err(err) = platform::watch(&folder, &listener, publish) {
+                    let _ =
+                        sender.unbounded_send(Err(format!("Stopped watching {folder:?}: {err}")));
+                }
+            });
+
+            while let Some(event) = receiver.next().await {
+                if output.send(event).await.is_err() {
+                    break;
+                }
+            }
+        }),
+    )
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::{collections::HashMap, mem::MaybeUninit, os::fd::OwnedFd, path::Path, sync::Arc};
+
+    use anyhow::Result;
+    use rustix::{
+        event::{EventfdFlags, PollFd, PollFlags, eventfd, poll},
+        fs::inotify::{self, CreateFlags, ReadFlags, Reader, WatchFlags},
+        io::Errno,
+    };
+
+    use super::FileEvent;
+    use crate::{
+        assets::{AssetKind, AssetPath},
+        io,
+    };
+
+    /// The folder a watch is on, as the kind whose folder it's in and the sub-folders.
+    type Watched = HashMap<i32, (AssetKind, Vec<String>)>;
+
+    /// Something that was moved away, waiting for the MOVED_TO with the same cookie.
+    struct MovedFrom {
+        cookie: u32,
+        is_dir: bool,
+        from: Option<(AssetKind, Vec<String>)>,
+    }
+
+    /// Stops the thread watching with its [`Listener`] when dropped, also while it waits for
+    /// an event.
+    pub struct Stop(Arc<OwnedFd>);
+
+    /// The watching thread's side of a [`Stop`].
+    pub struct Listener(Arc<OwnedFd>);
+
+    impl Stop {
+        pub fn new() -> Result<Self> {
+            Ok(Self(Arc::new(eventfd(0, EventfdFlags::CLOEXEC)?)))
+        }
+
+        pub fn listener(&self) -> Listener {
+            Listener(self.0.clone())
+        }
+    }
+
+    impl Drop for Stop {
+        fn drop(&mut self) {
+            let _ = rustix::io::write(&*self.0, &1u64.to_ne_bytes());
+        }
+    }
+
+    /// Publishes events until `publish` returns false, `stop` is dropped or watching fails.
+    pub fn watch(
+        folder: &Path,
+        stop: &Listener,
+        mut publish: impl FnMut(Result<FileEvent, String>) -> bool,
+    ) -> Result<()> {
+        let fd = inotify::init(CreateFlags::CLOEXEC)?;
+
+        let mut watched = Watched::new();
+
+        for kind in AssetKind::all() {
+            add_watches(&fd, &mut watched, folder, *kind, Vec::new())?;
+        }
+
+        let mut buffer = [MaybeUninit::uninit(); 4096];
+        let mut reader = Reader::new(&fd, &mut buffer);
+
+        let mut moved_from: Option<MovedFrom> = None;
+
+        loop {
+            if reader.is_buffer_empty() {
+                let mut fds = [
+                    PollFd::new(&fd, PollFlags::IN),
+                    PollFd::new(&*stop.0, PollFlags::IN),
+                ];
+
+                match poll(&mut fds, None) {
+                    Ok(_) if !fds[1].revents().is_empty() => return Ok(()),
+                    Ok(_) | Err(Errno::INTR) => {}
+                    Err(err) => return Err(err.into()),
+                }
+
+                if fds[0].revents().is_empty() {
+                    continue;
+                }
+            }
+
+            let (wd, cookie, flags, name) = {
+                let event = reader.next()?;
+
+                // temporary and hidden files, like the ones written by editors saving
+                // atomically, aren't assets
+                let name = event
+                    .file_name()
+                    .and_then(|name| name.to_str().ok())
+                    .filter(|name| !name.starts_with('.'))
+                    .map(str::to_string);
+
+                (event.wd(), event.cookie(), event.events(), name)
+            };
+
+            // the folder is gone, along with its watch
+            if flags.contains(ReadFlags::IGNORED) {
+                watched.remove(&wd);
+                continue;
+            }
+
+            // where the event happened, the folders ending in the file or folder's name
+            let location = watched.get(&wd).zip(name).map(|((kind, folders), name)| {
+                let mut folders = folders.clone();
+                folders.push(name);
+                (*kind, folders)
+            });
+
+            let is_dir = flags.contains(ReadFlags::ISDIR);
+
+            let mut events = Vec::new();
+
+            if let Some(moved) = moved_from.take() {
+                if flags.contains(ReadFlags::MOVED_TO) && moved.cookie == cookie {
+                    match (moved.is_dir, moved.from, location) {
+                        (true, Some((from_kind, from)), Some((kind, to))) if from_kind == kind => {
+                            // watches follow the folder, they only need their new path
+                            for (_, folders) in watched.values_mut() {
+                                if folders.starts_with(&from) {
+                                    folders.splice(..from.len(), to.iter().cloned());
+                                }
+                            }
+
+                            events.push(Ok(FileEvent::FolderRenamed { kind, from, to }));
+                        }
+                        (true, from, to) => {
+                            if let Some((kind, folders)) = from {
+                                remove_watches(&fd, &mut watched, kind, &folders);
+                                events.push(Ok(FileEvent::FolderRemoved { kind, folders }));
+                            }
+
+                            if let Some((kind, folders)) = to {
+                                events.extend(folder_added(
+                                    &fd,
+                                    &mut watched,
+                                    folder,
+                                    kind,
+                                    folders,
+                                ));
+                            }
+                        }
+                        (false, from, to) => {
+                            let from = from.and_then(|(kind, folders)| file(kind, &folders));
+                            let to = to.and_then(|(kind, folders)| file(kind, &folders));
+
+                            events.extend(
+                                match (from, to) {
+                                    (Some(from), Some(to)) => Some(FileEvent::Renamed { from, to }),
+                                    (Some(from), None) => Some(FileEvent::Removed(from)),
+                                    (None, Some(to)) => Some(FileEvent::Written(to)),
+                                    (None, None) => None,
+                                }
+                                .map(Ok),
+                            );
+                        }
+                    }
+
+                    if !events.into_iter().all(&mut publish) {
+                        return Ok(());
+                    }
+
+                    continue;
+                }
+
+                if let Some((kind, folders)) = moved.from {
+                    if moved.is_dir {
+                        remove_watches(&fd, &mut watched, kind, &folders);
+                        events.push(Ok(FileEvent::FolderRemoved { kind, folders }));
+                    } else {
+                        events.extend(file(kind, &folders).map(FileEvent::Removed).map(Ok));
+                    }
+                }
+            }
+
+            if flags.contains(ReadFlags::MOVED_FROM) {
+                // both halves of a rename are queued together, if nothing else is buffered the
+                // file was moved out of the folder
+                if reader.is_buffer_empty() {
+                    if let Some((kind, folders)) = location {
+                        if is_dir {
+                            remove_watches(&fd, &mut watched, kind, &folders);
+                            events.push(Ok(FileEvent::FolderRemoved { kind, folders }));
+                        } else {
+                            events.extend(file(kind, &folders).map(FileEvent::Removed).map(Ok));
+                        }
+                    }
+                } else {
+                    moved_from = Some(MovedFrom {
+                        cookie,
+                        is_dir,
+                        from: location,
+                    });
+                }
+            } else if let Some((kind, folders)) = location {
+                match (is_dir, flags) {
+                    (true, flags) if flags.intersects(ReadFlags::CREATE | ReadFlags::MOVED_TO) => {
+                        events.extend(folder_added(&fd, &mut watched, folder, kind, folders));
+                    }
+                    (true, flags) if flags.contains(ReadFlags::DELETE) => {
+                        events.push(Ok(FileEvent::FolderRemoved { kind, folders }));
+                    }
+                    (false, flags)
+                        if flags.intersects(ReadFlags::CLOSE_WRITE | ReadFlags::MOVED_TO) =>
+                    {
+                        events.extend(file(kind, &folders).map(FileEvent::Written).map(Ok));
+                    }
+                    (false, flags) if flags.contains(ReadFlags::DELETE) => {
+                        events.extend(file(kind, &folders).map(FileEvent::Removed).map(Ok));
+                    }
+                    _ => {}
+                }
+            }
+
+            if !events.into_iter().all(&mut publish) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn file(kind: AssetKind, folders: &[String]) -> Option<AssetPath> {
+        AssetPath::from_relative(kind, &folders.join("/")).ok()
+    }
+
+    /// Watches the sub-folder `folders` of the folder of `kind` and every folder below it.
+    fn add_watches(
+        fd: &OwnedFd,
+        watched: &mut Watched,
+        project: &Path,
+        kind: AssetKind,
+        folders: Vec<String>,
+    ) -> Result<()> {
+        let dir = folders
+            .iter()
+            .fold(project.join(kind.folder()), |dir, folder| dir.join(folder));
+
+        let wd = inotify::add_watch(
+            fd,
+            &dir,
+            WatchFlags::CREATE
+                | WatchFlags::CLOSE_WRITE
+                | WatchFlags::DELETE
+                | WatchFlags::MOVED_FROM
+                | WatchFlags::MOVED_TO
+                | WatchFlags::ONLYDIR,
+        )?;
+
+        watched.insert(wd, (kind, folders.clone()));
+
+        for entry in std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            if !name.starts_with('.') && entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                let mut folders = folders.clone();
+                folders.push(name);
+
+                add_watches(fd, watched, project, kind, folders)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn remove_watches(fd: &OwnedFd, watched: &mut Watched, kind: AssetKind, folders: &[String]) {
+        watched.retain(|wd, (watched_kind, watched_folders)| {
+            let inside = *watched_kind == kind && watched_folders.starts_with(folders);
+
+            if inside {
+                let _ = inotify::remove_watch(fd, *wd);
+            }
+
+            !inside
+        });
+    }
+
+    /// Starts watching a folder that was created or moved in, the files it came with are new.
+    fn folder_added(
+        fd: &OwnedFd,
+        watched: &mut Watched,
+        project: &Path,
+        kind: AssetKind,
+        folders: Vec<String>,
+    ) -> Vec<Result<FileEvent, String>> {
+        let dir = folders
+            .iter()
+            .fold(project.join(kind.folder()), |dir, folder| dir.join(folder));
+
+        let watching = add_watches(fd, watched, project, kind, folders.clone())
+            .map_err(|err| format!("Couldn't watch {dir:?}: {err}"));
+
+        watching
+            .err()
+            .map(Err)
+            .into_iter()
+            .chain(
+                io::asset_files(&dir, kind, &folders)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|path| Ok(FileEvent::Written(path))),
+            )
+            .collect()
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod platform {
+    use std::path::Path;
+
+    use anyhow::Result;
+
+    use super::FileEvent;
+
+    pub struct Stop;
+
+    pub struct Listener;
+
+    impl Stop {
+        pub fn new() -> Result<Self> {
+            Ok(Self)
+        }
+
+        pub fn listener(&self) -> Listener {
+            Listener
+        }
+    }
+
+    pub fn watch(
+        _folder: &Path,
+        _stop: &Listener,
+        _publish: impl FnMut(Result<FileEvent, String>) -> bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::io;
+
+/// How many project folders are remembered in File > Open Recent.
+const MAX_RECENT_PROJECTS: usize = 10;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    #[error("No config directory could be found for the current user.")]
+    NoConfigDir,
+}
+
+/// Per-user settings, stored in `config.ron` under the platform's config directory
+/// (`$XDG_CONFIG_HOME/iced_stuff` on Linux).
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    /// Project folders, the most recently opened one first.
+    #[serde(default)]
+    pub recent_projects: Vec<PathBuf>,
+    #[serde(default)]
+    pub reopen_last_project: bool,
+    /// How long to wait after the first unsaved change before saving, `None` turns it off.
+    #[serde(default)]
+    pub autosave: Option<Duration>,
+}
+
+impl Config {
+    /// Reads the config, a missing file is the same as an empty one.
+    pub fn load() -> Result<Self> {
+        let path = path()?;
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        Ok(ron::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = path()?;
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        io::save(path, ron::ser::to_string_pretty(self, PrettyConfig::new())?)
+    }
+
+    /// Moves `folder` to the top of the recent projects, dropping the oldest ones past the limit.
+    pub fn add_recent_project(&mut self, folder: PathBuf) {
+        self.remove_recent_project(&folder);
+        self.recent_projects.insert(0, folder);
+        self.recent_projects.truncate(MAX_RECENT_PROJECTS);
+    }
+
+    pub fn remove_recent_project(&mut self, folder: &Path) {
+        self.recent_projects.retain(|recent| recent != folder);
+    }
+
+    /// The project to open on start, if that's turned on.
+    pub fn last_project(&self) -> Option<&PathBuf> {
+        self.recent_projects
+            .first()
+            .filter(|_| self.reopen_last_project)
+    }
+}
+
+fn path() -> Result<PathBuf> {
+    let dir = dirs::config_dir().ok_or(anyhow!(ConfigError::NoConfigDir))?;
+
+    Ok(dir.join(env!("CARGO_PKG_NAME")).join("config.ron"))
+}
@@ -0,0 +1,170 @@
+use crate::{
+    Node,
+    graph::{GraphData, NodeId, RelativeAttachment, line_styles::AxisAligned},
+    relationships::Relationships,
+    widgets,
+};
+
+type FamilyTree = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+fn quote(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+fn node_name(id: NodeId) -> String {
+    format!("n{id}")
+}
+
+fn attachment_name(attachment: &RelativeAttachment<AxisAligned>) -> String {
+    match attachment {
+        RelativeAttachment::Center => "center".to_string(),
+        RelativeAttachment::Edge { edge, .. } => edge.to_string(),
+    }
+}
+
+/// Writes the graph as an undirected Graphviz graph. Characters are boxes labelled with their
+/// name and lifespan, family nodes are points. Both ends of an edge are labelled with the node
+/// edge they're attached to, edges with a relationship kind also get its label and colour.
+pub fn export(graph: &FamilyTree, relationships: &Relationships) -> String {
+    let mut lines = vec!["graph {".to_string(), "  node [shape=box];".to_string()];
+
+    for node in graph.iter() {
+        let attributes = match node.data() {
+            Node::Character(chara) => {
+                let lifespan = widgets::lifespan(chara);
+
+                let label = if lifespan.is_empty() {
+                    chara.name.clone()
+                } else {
+                    format!("{}\n{lifespan}", chara.name)
+                };
+
+                format!("label={}", quote(&label))
+            }
+            Node::Family => "shape=point".to_string(),
+        };
+
+        lines.push(format!("  {} [{attributes}];", node_name(node.id())));
+    }
+
+    for conn in graph.connections() {
+        let mut attributes = vec![
+            format!("taillabel={}", quote(&attachment_name(&conn.a.1))),
+            format!("headlabel={}", quote(&attachment_name(&conn.b.1))),
+        ];
+
+        if let Some(kind) = conn.kind.and_then(|kind| relationships.get(kind)) {
+            let [r, g, b] = kind.color;
+
+            attributes.push(format!("label={}", quote(&kind.label)));
+            attributes.push(format!("color=\"#{r:02x}{g:02x}{b:02x}\""));
+            attributes.push(format!("style={}", kind.pattern.to_string().to_lowercase()));
+        }
+
+        lines.push(format!(
+            "  {} -- {} [{}];",
+            node_name(conn.a.0),
+            node_name(conn.b.0),
+            attributes.join(", ")
+        ));
+    }
+
+    lines.push("}".to_string());
+
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::Point;
+
+    use super::*;
+    use crate::{Character, assets::AssetHandle};
+
+    fn character(graph: &mut FamilyTree, name: &str, birth: &str, death: &str) -> NodeId {
+        let mut chara = Character::new(name, AssetHandle::NONE);
+        chara.birth = birth.to_string();
+        chara.death = death.to_string();
+
+        graph.add(Node::Character(chara), Point::ORIGIN)
+    }
+
+    #[test]
+    fn writes_nodes() {
+        let mut graph = FamilyTree::default();
+        let anna = character(&mut graph, "Anna", "1900", "1980");
+        let ben = character(&mut graph, "Ben", "", "");
+        let family = graph.add(Node::Family, Point::ORIGIN);
+
+        let dot = export(&graph, &Relationships::default());
+
+        assert!(dot.starts_with("graph {\n  node [shape=box];\n"), "{dot}");
+        assert!(dot.ends_with("}\n"), "{dot}");
+        assert!(dot.contains(&format!("  n{anna} [label=\"Anna\\n1900 – 1980\"];\n")));
+        assert!(dot.contains(&format!("  n{ben} [label=\"Ben\"];\n")));
+        assert!(dot.contains(&format!("  n{family} [shape=point];\n")));
+    }
+
+    #[test]
+    fn writes_edges() {
+        let relationships = Relationships::default();
+        let rivals = relationships
+            .iter()
+            .find_map(|(kind, relationship)| (relationship.label == "Rivals").then_some(kind));
+
+        let mut graph = FamilyTree::default();
+        let anna = character(&mut graph, "Anna", "", "");
+        let ben = character(&mut graph, "Ben", "", "");
+        let family = graph.add(Node::Family, Point::ORIGIN);
+
+        graph
+            .connect(
+                anna,
+                RelativeAttachment::bottom(),
+                family,
+                RelativeAttachment::Center,
+            )
+            .unwrap();
+        graph
+            .connect_with_kind(
+                anna,
+                RelativeAttachment::right(),
+                ben,
+                RelativeAttachment::left(),
+                rivals,
+            )
+            .unwrap();
+
+        let dot = export(&graph, &relationships);
+
+        assert!(dot.contains(&format!(
+            "  n{anna} -- n{family} [taillabel=\"bottom\", headlabel=\"center\"];\n"
+        )));
+        assert!(dot.contains(&format!(
+            "  n{anna} -- n{ben} [taillabel=\"right\", headlabel=\"left\", label=\"Rivals\", \
+            color=\"#e88a2e\", style=dotted];\n"
+        )));
+    }
+
+    #[test]
+    fn escapes_names() {
+        let mut graph = FamilyTree::default();
+        let anna = character(&mut graph, "Anna \"Nan\"\nBerg \\ Smith", "", "");
+
+        let dot = export(&graph, &Relationships::default());
+
+        assert!(
+            dot.contains(&format!(
+                "  n{anna} [label=\"Anna \\\"Nan\\\"\\nBerg \\\\ Smith\"];\n"
+            )),
+            "{dot}"
+        );
+        // a name can't end the graph early
+        assert_eq!(dot.lines().count(), 4);
+    }
+}
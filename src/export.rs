@@ -0,0 +1,731 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use iced::{
+    Background, Color, Font, Point, Rectangle, Size, Theme, Vector,
+    advanced::{
+        graphics::text::{cosmic_text, font_system, to_attributes, to_color},
+        image::Handle,
+    },
+    border::Radius,
+    widget::{canvas::Path, container},
+};
+use image::{ColorType, ImageEncoder, RgbaImage, codecs::png::PngEncoder};
+use lyon_algorithms::path::PathEvent;
+use thiserror::Error;
+
+use crate::{
+    Node,
+    assets::{AssetHandle, AssetsData, Image, image::default_image},
+    graph::{GraphData, LinePattern, RelativeAttachment, line_styles::AxisAligned},
+    relationships::Relationships,
+    style,
+    widgets::{self, CHARACTER_SIZE, FAMILY_SIZE, NODE_PADDING},
+};
+
+type FamilyTree = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+/// Empty space around the graph.
+const MARGIN: f32 = 40.0;
+/// Connections are drawn this wide by the graph at any zoom level.
+const CONNECTION_WIDTH: f32 = 5.0;
+const NAME_SIZE: f32 = 16.0;
+const LIFESPAN_SIZE: f32 = 12.0;
+/// iced's default relative line height.
+const LINE_HEIGHT: f32 = 1.3;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExportError {
+    #[error("The graph is empty, there is nothing to export.")]
+    EmptyGraph,
+    #[error("The image would be too large to render.")]
+    TooLarge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Svg,
+    /// Rendered at `scale` pixels per graph unit.
+    Png(f32),
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Svg => "svg",
+            Self::Png(_) => "png",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Svg => "SVG",
+            Self::Png(_) => "PNG",
+        }
+    }
+}
+
+/// Everything that ends up in the exported image, already laid out in image coordinates.
+struct Scene {
+    size: Size,
+    background: Color,
+    connections: Vec<Line>,
+    nodes: Vec<Shape>,
+    /// Decoded node images, shared by all nodes showing the same asset.
+    images: Vec<RgbaImage>,
+}
+
+struct Line {
+    path: Path,
+    color: Color,
+    pattern: LinePattern,
+}
+
+struct Shape {
+    bounds: Rectangle,
+    style: container::Style,
+    /// Index into [`Scene::images`] and where the image gets drawn.
+    image: Option<(usize, Rectangle)>,
+    labels: Vec<Label>,
+}
+
+struct Label {
+    content: String,
+    bounds: Rectangle,
+    size: f32,
+    color: Color,
+}
+
+fn node_size(node: &Node) -> Size {
+    match node {
+        Node::Character(_) => CHARACTER_SIZE,
+        Node::Family => FAMILY_SIZE,
+    }
+}
+
+/// Lays out the graph the same way `widgets::node` and the graph widget do, with every node at
+/// its graph position.
+fn scene(
+    graph: &FamilyTree,
+    assets: &AssetsData,
+    relationships: &Relationships,
+    theme: &Theme,
+) -> Result<Scene> {
+    let palette = theme.extended_palette();
+
+    let node_bounds = |id| {
+        graph
+            .get(id)
+            .map(|node| Rectangle::new(node.position(), node_size(node.data())))
+    };
+
+    let mut bounds = graph
+        .iter()
+        .filter_map(|node| node_bounds(node.id()))
+        .reduce(|a, b| a.union(&b))
+        .ok_or(ExportError::EmptyGraph)?;
+
+    let node_size = |id| {
+        node_bounds(id)
+            .map(|bounds| bounds.size())
+            .unwrap_or(Size::ZERO)
+    };
+
+    // connections can bend outside of the nodes they connect
+    for connection in graph.connections() {
+        if let Some(path) = graph.connection_path(connection, node_size, Vector::ZERO) {
+            for event in path.raw().iter() {
+                let point = event.to();
+                bounds = bounds.union(&Rectangle::new(Point::new(point.x, point.y), Size::ZERO));
+            }
+        }
+    }
+
+    let offset = Vector::new(bounds.x - MARGIN, bounds.y - MARGIN);
+
+    let connections = graph
+        .connections()
+        .filter_map(|connection| {
+            let style = relationships.style(connection.kind);
+
+            Some(Line {
+                path: graph.connection_path(connection, node_size, offset)?,
+                color: style.color.unwrap_or(palette.secondary.strong.color),
+                pattern: style.pattern,
+            })
+        })
+        .collect();
+
+    let mut images = Vec::new();
+    let mut image_indices: HashMap<AssetHandle, usize> = HashMap::new();
+    let mut nodes = Vec::new();
+
+    for node in graph.iter() {
+        let bounds = Rectangle::new(node.position() - offset, node_size(node.id()));
+
+        let Node::Character(chara) = node.data() else {
+            nodes.push(Shape {
+                bounds,
+                style: style::family_node(theme),
+                image: None,
+                labels: Vec::new(),
+            });
+
+            continue;
+        };
+
+        let index = match image_indices.get(&chara.img) {
+            Some(index) => *index,
+            None => {
+                let handle = assets
+                    .get(chara.img)
+                    .and_then(|asset| Image::try_from(asset).ok())
+                    .map(|img| img.cropped.unwrap_or(img.handle))
+                    .unwrap_or(default_image());
+
+                // an image that can't be read is drawn like a missing one instead of failing
+                // the whole export
+                let image = match decode(&handle) {
+                    Ok(image) => image,
+                    Err(_) => decode(&default_image())?,
+                };
+
+                images.push(image);
+                image_indices.insert(chara.img, images.len() - 1);
+
+                images.len() - 1
+            }
+        };
+
+        let style = style::node(false)(theme);
+        let color = style.text_color.unwrap_or(theme.palette().text);
+
+        let content = bounds.shrink(NODE_PADDING);
+        let name_height = NAME_SIZE * LINE_HEIGHT;
+        let lifespan_height = LIFESPAN_SIZE * LINE_HEIGHT;
+
+        let image_area = Rectangle {
+            height: content.height - name_height - lifespan_height - NODE_PADDING * 2.0,
+            ..content
+        };
+
+        let name = Rectangle {
+            y: image_area.y + image_area.height + NODE_PADDING,
+            height: name_height,
+            ..content
+        };
+
+        let lifespan = Rectangle {
+            y: name.y + name.height + NODE_PADDING,
+            height: lifespan_height,
+            ..content
+        };
+
+        nodes.push(Shape {
+            bounds,
+            style,
+            image: Some((index, contain(&images[index], image_area))),
+            labels: vec![
+                Label {
+                    content: chara.name.clone(),
+                    bounds: name,
+                    size: NAME_SIZE,
+                    color,
+                },
+                Label {
+                    content: widgets::lifespan(chara),
+                    bounds: lifespan,
+                    size: LIFESPAN_SIZE,
+                    color,
+                },
+            ],
+        });
+    }
+
+    Ok(Scene {
+        size: Size::new(bounds.width + MARGIN * 2.0, bounds.height + MARGIN * 2.0),
+        background: palette.background.base.color,
+        connections,
+        nodes,
+        images,
+    })
+}
+
+fn decode(handle: &Handle) -> Result<RgbaImage> {
+    Ok(match handle {
+        Handle::Path(_, path) => image::open(path)?.into_rgba8(),
+        Handle::Bytes(_, bytes) => image::load_from_memory(bytes)?.into_rgba8(),
+        Handle::Rgba {
+            width,
+            height,
+            pixels,
+            ..
+        } => RgbaImage::from_raw(*width, *height, pixels.to_vec())
+            .ok_or_else(|| anyhow!("Invalid image data"))?,
+    })
+}
+
+/// Where an image ends up when scaled to fit `area` and centered in it, like
+/// [`iced::ContentFit::Contain`].
+fn contain(image: &RgbaImage, area: Rectangle) -> Rectangle {
+    let size = Size::new(image.width() as f32, image.height() as f32);
+    let scale = (area.width / size.width).min(area.height / size.height);
+    let size = size * scale;
+
+    Rectangle::new(
+        area.center() - Vector::new(size.width / 2.0, size.height / 2.0),
+        size,
+    )
+}
+
+fn background_path(shape: &Shape) -> Path {
+    Path::rounded_rectangle(
+        shape.bounds.position(),
+        shape.bounds.size(),
+        shape.style.border.radius,
+    )
+}
+
+/// The border is drawn inside the bounds, so its path runs half its width further in.
+fn border_path(shape: &Shape) -> Path {
+    let inset = shape.style.border.width / 2.0;
+    let radius: [f32; 4] = shape.style.border.radius.into();
+    let [top_left, top_right, bottom_right, bottom_left] = radius.map(|r| (r - inset).max(0.0));
+
+    Path::rounded_rectangle(
+        shape.bounds.position() + Vector::new(inset, inset),
+        shape.bounds.shrink(inset).size(),
+        Radius {
+            top_left,
+            top_right,
+            bottom_right,
+            bottom_left,
+        },
+    )
+}
+
+/// Renders the graph as an SVG document, with node images embedded as PNGs.
+pub fn svg(
+    graph: &FamilyTree,
+    assets: &AssetsData,
+    relationships: &Relationships,
+    theme: &Theme,
+) -> Result<String> {
+    let scene = scene(graph, assets, relationships, theme)?;
+    let mut svg = String::new();
+
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        scene.size.width, scene.size.height
+    )?;
+
+    writeln!(svg, "<defs>")?;
+
+    for (i, image) in scene.images.iter().enumerate() {
+        let mut png = Vec::new();
+        PngEncoder::new(&mut png).write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            ColorType::Rgba8,
+        )?;
+
+        writeln!(
+            svg,
+            r#"<image id="image-{i}" width="{}" height="{}" style="image-rendering:pixelated" href="data:image/png;base64,{}"/>"#,
+            image.width(),
+            image.height(),
+            STANDARD.encode(png)
+        )?;
+    }
+
+    for (i, shape) in scene.nodes.iter().enumerate() {
+        if let Some(Background::Gradient(iced::Gradient::Linear(linear))) = shape.style.background {
+            let (start, end) = linear.angle.to_distance(&shape.bounds);
+
+            writeln!(
+                svg,
+                r#"<linearGradient id="node-{i}" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                start.x, start.y, end.x, end.y
+            )?;
+
+            for stop in linear.stops.iter().flatten() {
+                writeln!(
+                    svg,
+                    r#"<stop offset="{}" {}/>"#,
+                    stop.offset,
+                    svg_color("stop-color", stop.color)
+                )?;
+            }
+
+            writeln!(svg, "</linearGradient>")?;
+        }
+    }
+
+    writeln!(svg, "</defs>")?;
+
+    writeln!(
+        svg,
+        r#"<rect width="100%" height="100%" {}/>"#,
+        svg_color("fill", scene.background)
+    )?;
+
+    for line in &scene.connections {
+        let dash = match line.pattern.segments(CONNECTION_WIDTH) {
+            Some([dash, gap]) => format!(r#" stroke-dasharray="{dash} {gap}""#),
+            None => String::new(),
+        };
+
+        writeln!(
+            svg,
+            r#"<path d="{}" fill="none" {} stroke-width="{CONNECTION_WIDTH}" stroke-linecap="round" stroke-linejoin="bevel"{dash}/>"#,
+            svg_path(&line.path),
+            svg_color("stroke", line.color)
+        )?;
+    }
+
+    for (i, shape) in scene.nodes.iter().enumerate() {
+        let fill = match shape.style.background {
+            Some(Background::Color(color)) => svg_color("fill", color),
+            Some(Background::Gradient(_)) => format!(r#"fill="url(#node-{i})""#),
+            None => r#"fill="none""#.to_string(),
+        };
+
+        writeln!(
+            svg,
+            r#"<path d="{}" {fill}/>"#,
+            svg_path(&background_path(shape))
+        )?;
+
+        if shape.style.border.width > 0.0 {
+            writeln!(
+                svg,
+                r#"<path d="{}" fill="none" {} stroke-width="{}"/>"#,
+                svg_path(&border_path(shape)),
+                svg_color("stroke", shape.style.border.color),
+                shape.style.border.width
+            )?;
+        }
+
+        if let Some((index, bounds)) = shape.image {
+            let image = &scene.images[index];
+
+            writeln!(
+                svg,
+                r##"<use href="#image-{index}" transform="translate({} {}) scale({} {})"/>"##,
+                bounds.x,
+                bounds.y,
+                bounds.width / image.width() as f32,
+                bounds.height / image.height() as f32
+            )?;
+        }
+
+        for label in shape
+            .labels
+            .iter()
+            .filter(|label| !label.content.is_empty())
+        {
+            let center = label.bounds.center();
+
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" {}>{}</text>"#,
+                center.x,
+                center.y,
+                label.size,
+                svg_color("fill", label.color),
+                escape(&label.content)
+            )?;
+        }
+    }
+
+    writeln!(svg, "</svg>")?;
+
+    Ok(svg)
+}
+
+/// `attribute` set to the color along with the matching opacity attribute.
+fn svg_color(attribute: &str, color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+
+    let opacity = match attribute {
+        "stop-color" => "stop-opacity".to_string(),
+        attribute => format!("{attribute}-opacity"),
+    };
+
+    format!(
+        r##"{attribute}="#{r:02x}{g:02x}{b:02x}" {opacity}="{}""##,
+        color.a
+    )
+}
+
+fn svg_path(path: &Path) -> String {
+    let mut d = String::new();
+
+    for event in path.raw().iter() {
+        let _ = match event {
+            PathEvent::Begin { at } => write!(d, "M{} {} ", at.x, at.y),
+            PathEvent::Line { to, .. } => write!(d, "L{} {} ", to.x, to.y),
+            PathEvent::Quadratic { ctrl, to, .. } => {
+                write!(d, "Q{} {} {} {} ", ctrl.x, ctrl.y, to.x, to.y)
+            }
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => write!(
+                d,
+                "C{} {} {} {} {} {} ",
+                ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y
+            ),
+            PathEvent::End { close: true, .. } => write!(d, "Z "),
+            PathEvent::End { close: false, .. } => Ok(()),
+        };
+    }
+
+    d.trim_end().to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Rasterises the graph into a PNG on the CPU, `scale` being the number of pixels per graph unit.
+pub fn png(
+    graph: &FamilyTree,
+    assets: &AssetsData,
+    relationships: &Relationships,
+    theme: &Theme,
+    scale: f32,
+) -> Result<Vec<u8>> {
+    use tiny_skia::{
+        FilterQuality, GradientStop, IntSize, LinearGradient, Paint, Pattern, Pixmap, Rect, Shader,
+        SpreadMode, Stroke, StrokeDash, Transform,
+    };
+
+    let scene = scene(graph, assets, relationships, theme)?;
+
+    let mut pixmap = Pixmap::new(
+        (scene.size.width * scale).ceil() as u32,
+        (scene.size.height * scale).ceil() as u32,
+    )
+    .ok_or(ExportError::TooLarge)?;
+
+    let transform = Transform::from_scale(scale, scale);
+
+    let paint = |shader| Paint {
+        shader,
+        anti_alias: true,
+        ..Paint::default()
+    };
+
+    pixmap.fill(skia_color(scene.background));
+
+    for line in &scene.connections {
+        let Some(path) = skia_path(&line.path) else {
+            continue;
+        };
+
+        let stroke = Stroke {
+            width: CONNECTION_WIDTH,
+            line_cap: tiny_skia::LineCap::Round,
+            line_join: tiny_skia::LineJoin::Bevel,
+            dash: line
+                .pattern
+                .segments(CONNECTION_WIDTH)
+                .and_then(|segments| StrokeDash::new(segments.to_vec(), 0.0)),
+            ..Stroke::default()
+        };
+
+        pixmap.stroke_path(
+            &path,
+            &paint(Shader::SolidColor(skia_color(line.color))),
+            &stroke,
+            transform,
+            None,
+        );
+    }
+
+    // node images, premultiplied like tiny-skia expects them
+    let images = scene
+        .images
+        .iter()
+        .map(|image| {
+            let pixels = image
+                .pixels()
+                .flat_map(|pixel| {
+                    let [r, g, b, a] = pixel.0;
+                    let color = tiny_skia::ColorU8::from_rgba(r, g, b, a).premultiply();
+
+                    [color.red(), color.green(), color.blue(), color.alpha()]
+                })
+                .collect();
+
+            IntSize::from_wh(image.width(), image.height())
+                .and_then(|size| Pixmap::from_vec(pixels, size))
+                .ok_or_else(|| anyhow!("Invalid image size"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut font_system = font_system().write().expect("Write font system");
+    let font_system = font_system.raw();
+    let mut swash_cache = cosmic_text::SwashCache::new();
+
+    for shape in &scene.nodes {
+        if let Some(path) = skia_path(&background_path(shape)) {
+            let shader = match shape.style.background {
+                Some(Background::Color(color)) => Some(Shader::SolidColor(skia_color(color))),
+                Some(Background::Gradient(iced::Gradient::Linear(linear))) => {
+                    let (start, end) = linear.angle.to_distance(&shape.bounds);
+
+                    LinearGradient::new(
+                        tiny_skia::Point::from_xy(start.x, start.y),
+                        tiny_skia::Point::from_xy(end.x, end.y),
+                        linear
+                            .stops
+                            .iter()
+                            .flatten()
+                            .map(|stop| GradientStop::new(stop.offset, skia_color(stop.color)))
+                            .collect(),
+                        SpreadMode::Pad,
+                        Transform::identity(),
+                    )
+                }
+                None => None,
+            };
+
+            if let Some(shader) = shader {
+                pixmap.fill_path(
+                    &path,
+                    &paint(shader),
+                    tiny_skia::FillRule::Winding,
+                    transform,
+                    None,
+                );
+            }
+        }
+
+        if shape.style.border.width > 0.0
+            && let Some(path) = skia_path(&border_path(shape))
+        {
+            pixmap.stroke_path(
+                &path,
+                &paint(Shader::SolidColor(skia_color(shape.style.border.color))),
+                &Stroke {
+                    width: shape.style.border.width,
+                    ..Stroke::default()
+                },
+                transform,
+                None,
+            );
+        }
+
+        if let Some((image, bounds)) = shape.image
+            && let Some(rect) = Rect::from_xywh(bounds.x, bounds.y, bounds.width, bounds.height)
+        {
+            let image = &images[image];
+
+            let pattern = Pattern::new(
+                image.as_ref(),
+                SpreadMode::Pad,
+                FilterQuality::Nearest,
+                1.0,
+                Transform::from_row(
+                    bounds.width / image.width() as f32,
+                    0.0,
+                    0.0,
+                    bounds.height / image.height() as f32,
+                    bounds.x,
+                    bounds.y,
+                ),
+            );
+
+            pixmap.fill_rect(rect, &paint(pattern), transform, None);
+        }
+
+        for label in &shape.labels {
+            let metrics =
+                cosmic_text::Metrics::new(label.size * scale, label.size * LINE_HEIGHT * scale);
+
+            let mut buffer = cosmic_text::Buffer::new(font_system, metrics);
+
+            buffer.set_size(
+                font_system,
+                Some(label.bounds.width * scale),
+                Some(label.bounds.height * scale),
+            );
+            buffer.set_text(
+                font_system,
+                &label.content,
+                to_attributes(Font::DEFAULT),
+                cosmic_text::Shaping::Advanced,
+            );
+
+            for line in &mut buffer.lines {
+                line.set_align(Some(cosmic_text::Align::Center));
+            }
+
+            buffer.shape_until_scroll(font_system, false);
+
+            let origin = label.bounds.position() * iced::Transformation::scale(scale);
+
+            buffer.draw(
+                font_system,
+                &mut swash_cache,
+                to_color(label.color),
+                |x, y, width, height, color| {
+                    let Some(rect) = Rect::from_xywh(
+                        origin.x + x as f32,
+                        origin.y + y as f32,
+                        width as f32,
+                        height as f32,
+                    ) else {
+                        return;
+                    };
+
+                    let [r, g, b, a] = color.as_rgba();
+
+                    pixmap.fill_rect(
+                        rect,
+                        &Paint {
+                            shader: Shader::SolidColor(tiny_skia::Color::from_rgba8(r, g, b, a)),
+                            ..Paint::default()
+                        },
+                        Transform::identity(),
+                        None,
+                    );
+                },
+            );
+        }
+    }
+
+    Ok(pixmap.encode_png()?)
+}
+
+fn skia_color(color: Color) -> tiny_skia::Color {
+    tiny_skia::Color::from_rgba(color.r, color.g, color.b, color.a)
+        .unwrap_or(tiny_skia::Color::TRANSPARENT)
+}
+
+fn skia_path(path: &Path) -> Option<tiny_skia::Path> {
+    let mut builder = tiny_skia::PathBuilder::new();
+
+    for event in path.raw().iter() {
+        match event {
+            PathEvent::Begin { at } => builder.move_to(at.x, at.y),
+            PathEvent::Line { to, .. } => builder.line_to(to.x, to.y),
+            PathEvent::Quadratic { ctrl, to, .. } => builder.quad_to(ctrl.x, ctrl.y, to.x, to.y),
+            PathEvent::Cubic {
+                ctrl1, ctrl2, to, ..
+            } => builder.cubic_to(ctrl1.x, ctrl1.y, ctrl2.x, ctrl2.y, to.x, to.y),
+            PathEvent::End { close: true, .. } => builder.close(),
+            PathEvent::End { close: false, .. } => (),
+        }
+    }
+
+    builder.finish()
+}
@@ -0,0 +1,664 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, anyhow};
+use iced::Point;
+use thiserror::Error;
+
+use crate::{
+    Character, Node,
+    assets::{AssetHandle, AssetKind, AssetPath, AssetsData},
+    graph::{GraphData, NodeId, RelativeAttachment, line_styles::AxisAligned},
+};
+
+type FamilyTree = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+/// Longest value written on one line, GEDCOM allows 255 characters for the whole line. Longer
+/// ones are continued with CONC.
+const MAX_VALUE_LEN: usize = 200;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GedcomError {
+    #[error("Line {0} is not a valid GEDCOM line.")]
+    InvalidLine(usize),
+    #[error("The file doesn't contain any individuals.")]
+    NoIndividuals,
+    #[error("{0} are partners in the same family, GEDCOM only allows two.")]
+    TooManyPartners(String),
+}
+
+/// One line of a GEDCOM file together with the lines nested below it.
+#[derive(Debug, Default)]
+struct Record {
+    xref: Option<String>,
+    tag: String,
+    value: String,
+    children: Vec<Record>,
+}
+
+impl Record {
+    fn child(&self, tag: &str) -> Option<&Record> {
+        self.children.iter().find(|child| child.tag == tag)
+    }
+
+    fn children<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a Record> {
+        self.children.iter().filter(move |child| child.tag == tag)
+    }
+
+    /// The value with its CONT and CONC continuation lines joined back together.
+    fn text(&self) -> String {
+        self.children
+            .iter()
+            .fold(self.value.clone(), |mut text, child| {
+                match child.tag.as_str() {
+                    "CONT" => {
+                        text.push('\n');
+                        text.push_str(&child.value);
+                    }
+                    "CONC" => text.push_str(&child.value),
+                    _ => (),
+                }
+                text
+            })
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Record>, GedcomError> {
+    let mut roots: Vec<Record> = Vec::new();
+    // records that can still get children, indexed by their level
+    let mut open: Vec<Record> = Vec::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let invalid = || GedcomError::InvalidLine(i + 1);
+
+        let (level, rest) = line.split_once(' ').ok_or_else(invalid)?;
+        let level: usize = level.parse().map_err(|_| invalid())?;
+
+        let (xref, rest) = match rest.strip_prefix('@') {
+            Some(rest) => {
+                let (xref, rest) = rest.split_once("@ ").ok_or_else(invalid)?;
+                (Some(xref.to_string()), rest)
+            }
+            None => (None, rest),
+        };
+
+        let (tag, value) = rest.split_once(' ').unwrap_or((rest, ""));
+
+        if level > open.len() {
+            return Err(invalid());
+        }
+
+        while open.len() > level {
+            let record = open.pop().unwrap();
+
+            match open.last_mut() {
+                Some(parent) => parent.children.push(record),
+                None => roots.push(record),
+            }
+        }
+
+        open.push(Record {
+            xref,
+            tag: tag.to_string(),
+            value: value.to_string(),
+            children: Vec::new(),
+        });
+    }
+
+    while let Some(record) = open.pop() {
+        match open.last_mut() {
+            Some(parent) => parent.children.push(record),
+            None => roots.push(record),
+        }
+    }
+
+    Ok(roots)
+}
+
+/// Reads INDI records as characters and FAM records as family nodes. Partners get attached to
+/// the top of their family and children to its bottom, all nodes start at the origin and get
+/// placed by the graph's positioning scheme.
+pub fn import(source: &str, assets: &AssetsData) -> Result<FamilyTree> {
+    let records = parse(source)?;
+
+    let notes: HashMap<_, _> = records
+        .iter()
+        .filter(|record| record.tag == "NOTE")
+        .filter_map(|record| Some((record.xref.as_deref()?, record.text())))
+        .collect();
+
+    let mut graph = FamilyTree::default();
+    let mut individuals = HashMap::new();
+
+    for record in records.iter().filter(|record| record.tag == "INDI") {
+        let name = record
+            .child("NAME")
+            .map(|name| name.value.replace('/', " "))
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let date = |event| {
+            record
+                .child(event)
+                .and_then(|event| event.child("DATE"))
+                .map(|date| date.value.clone())
+                .unwrap_or_default()
+        };
+
+        // the first image is the character's, every other file is attached
+        let (images, attachments): (Vec<_>, Vec<_>) = record
+            .children("OBJE")
+            .filter_map(|obje| obje.child("FILE"))
+            .filter_map(|file| AssetPath::try_from(file.value.as_str()).ok())
+            .partition(|path| path.kind() == AssetKind::Image);
+
+        let img = images
+            .into_iter()
+            .find_map(|path| assets.handle(path))
+            .unwrap_or(AssetHandle::NONE);
+
+        let mut chara = Character::new(name, img);
+        chara.attachments = attachments
+            .into_iter()
+            .filter_map(|path| assets.handle(path))
+            .collect();
+        chara.birth = date("BIRT");
+        chara.death = date("DEAT");
+        chara.notes = record
+            .children("NOTE")
+            .map(|note| {
+                // notes are either written inline or point to a NOTE record
+                note.value
+                    .strip_prefix('@')
+                    .and_then(|xref| notes.get(xref.trim_end_matches('@')).cloned())
+                    .unwrap_or_else(|| note.text())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let id = graph.add(Node::Character(chara), Point::ORIGIN);
+
+        if let Some(xref) = &record.xref {
+            individuals.insert(xref.trim_matches('@'), id);
+        }
+    }
+
+    if individuals.is_empty() {
+        return Err(anyhow!(GedcomError::NoIndividuals));
+    }
+
+    let individual = |pointer: &str| individuals.get(pointer.trim_matches('@')).copied();
+
+    for record in records.iter().filter(|record| record.tag == "FAM") {
+        let family = graph.add(Node::Family, Point::ORIGIN);
+
+        let partners = [
+            ("HUSB", RelativeAttachment::right()),
+            ("WIFE", RelativeAttachment::left()),
+        ];
+
+        for (tag, attachment) in partners {
+            if let Some(partner) = record
+                .child(tag)
+                .and_then(|partner| individual(&partner.value))
+            {
+                let _ = graph.connect(partner, attachment, family, RelativeAttachment::top());
+            }
+        }
+
+        for child in record
+            .children("CHIL")
+            .filter_map(|child| individual(&child.value))
+        {
+            let _ = graph.connect(
+                child,
+                RelativeAttachment::top(),
+                family,
+                RelativeAttachment::bottom(),
+            );
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Writes characters as INDI and family nodes as FAM records. Connections with a relationship
+/// kind have no GEDCOM equivalent and are left out.
+pub fn export(graph: &FamilyTree, assets: &AssetsData) -> Result<String> {
+    let mut xrefs: HashMap<NodeId, String> = HashMap::new();
+    let mut individuals = 0;
+    let mut families = 0;
+
+    for node in graph.iter() {
+        let xref = match node.data() {
+            Node::Character(_) => {
+                individuals += 1;
+                format!("@I{individuals}@")
+            }
+            Node::Family => {
+                families += 1;
+                format!("@F{families}@")
+            }
+        };
+
+        xrefs.insert(node.id(), xref);
+    }
+
+    // (family, partner, attachment on the partner's side) and (family, child)
+    let mut partners = Vec::new();
+    let mut children = Vec::new();
+
+    for conn in graph.connections().filter(|conn| conn.kind.is_none()) {
+        for ((family, family_att), (other, other_att)) in [(&conn.a, &conn.b), (&conn.b, &conn.a)] {
+            let is = |id: &NodeId, family: bool| {
+                graph
+                    .get(*id)
+                    .is_some_and(|node| matches!(node.data(), Node::Family) == family)
+            };
+
+            if !is(family, true) || !is(other, false) {
+                continue;
+            }
+
+            if family_att.is_top() {
+                partners.push((*family, *other, other_att.clone()));
+            } else if family_att.is_bottom() {
+                children.push((*family, *other));
+            }
+        }
+    }
+
+    let mut lines = vec![
+        "0 HEAD".to_string(),
+        format!("1 SOUR {}", env!("CARGO_PKG_NAME")),
+        format!("2 VERS {}", env!("CARGO_PKG_VERSION")),
+        "1 GEDC".to_string(),
+        "2 VERS 5.5.1".to_string(),
+        "2 FORM LINEAGE-LINKED".to_string(),
+        "1 CHAR UTF-8".to_string(),
+    ];
+
+    for node in graph.iter() {
+        let xref = &xrefs[&node.id()];
+
+        match node.data() {
+            Node::Character(chara) => {
+                lines.push(format!("0 {xref} INDI"));
+                lines.push(format!("1 NAME {}", gedcom_name(&chara.name)));
+
+                for (tag, date) in [("BIRT", &chara.birth), ("DEAT", &chara.death)] {
+                    if !date.trim().is_empty() {
+                        lines.push(format!("1 {tag}"));
+                        lines.push(format!("2 DATE {}", date.trim()));
+                    }
+                }
+
+                let files = std::iter::once(chara.img).chain(chara.attachments.iter().copied());
+
+                for path in files.filter_map(|handle| assets.path(handle)) {
+                    lines.push("1 OBJE".to_string());
+                    lines.push(format!("2 FILE {path}"));
+                }
+
+                if !chara.notes.is_empty() {
+                    lines.extend(text_lines(1, "NOTE", &chara.notes));
+                }
+
+                for (family, ..) in partners.iter().filter(|(_, id, _)| *id == node.id()) {
+                    lines.push(format!("1 FAMS {}", xrefs[family]));
+                }
+
+                for (family, _) in children.iter().filter(|(_, id)| *id == node.id()) {
+                    lines.push(format!("1 FAMC {}", xrefs[family]));
+                }
+            }
+            Node::Family => {
+                lines.push(format!("0 {xref} FAM"));
+
+                let mut family_partners: Vec<_> = partners
+                    .iter()
+                    .filter(|(family, ..)| *family == node.id())
+                    .collect();
+
+                // the partner on the left comes first, like it's drawn
+                family_partners.sort_by_key(|(.., attachment)| !attachment.is_right());
+
+                if family_partners.len() > 2 {
+                    let names: Vec<_> = family_partners
+                        .iter()
+                        .filter_map(|(_, partner, _)| match graph.get(*partner)?.data() {
+                            Node::Character(chara) => Some(chara.name.as_str()),
+                            Node::Family => None,
+                        })
+                        .collect();
+
+                    return Err(anyhow!(GedcomError::TooManyPartners(names.join(", "))));
+                }
+
+                for (tag, (_, partner, _)) in ["HUSB", "WIFE"].into_iter().zip(family_partners) {
+                    lines.push(format!("1 {tag} {}", xrefs[partner]));
+                }
+
+                for (_, child) in children.iter().filter(|(family, _)| *family == node.id()) {
+                    lines.push(format!("1 CHIL {}", xrefs[child]));
+                }
+            }
+        }
+    }
+
+    lines.push("0 TRLR".to_string());
+
+    Ok(lines.join("\n") + "\n")
+}
+
+/// A name in GEDCOM's `Given Names /Surname/` form. Characters only have one name, so its last
+/// word is taken as the surname.
+fn gedcom_name(name: &str) -> String {
+    match name.trim().rsplit_once(' ') {
+        Some((given, surname)) => format!("{} /{surname}/", given.trim_end()),
+        None => name.trim().to_string(),
+    }
+}
+
+/// `text` as a `tag` line at `level`, with a CONT line for every further line of it and CONC
+/// lines for the parts that don't fit on one.
+fn text_lines(level: usize, tag: &str, text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for (i, line) in text.split('\n').enumerate() {
+        for (j, chunk) in split_value(line).into_iter().enumerate() {
+            let line = match (i, j) {
+                (0, 0) => format!("{level} {tag} {chunk}"),
+                (_, 0) => format!("{} CONT {chunk}", level + 1),
+                _ => format!("{} CONC {chunk}", level + 1),
+            };
+
+            lines.push(line.trim_end().to_string());
+        }
+    }
+
+    lines
+}
+
+/// Splits a value into parts of at most [`MAX_VALUE_LEN`] characters. Readers trim lines, so
+/// it's split in the middle of a word, never next to a space.
+fn split_value(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = value;
+
+    while let Some((limit, _)) = rest.char_indices().nth(MAX_VALUE_LEN) {
+        let next_to_space = |at: usize| {
+            rest[..at].ends_with(char::is_whitespace) || rest[at..].starts_with(char::is_whitespace)
+        };
+
+        // a value without words long enough to split gets split anywhere
+        let end = (1..=limit)
+            .rev()
+            .filter(|at| rest.is_char_boundary(*at))
+            .find(|at| !next_to_space(*at))
+            .unwrap_or(limit);
+
+        parts.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+
+    parts.push(rest);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{graph::GraphNode, kinship};
+
+    const FAMILY: &str = include_str!("../tests/fixtures/gedcom/family.ged");
+    const PARENTLESS_SIBLINGS: &str =
+        include_str!("../tests/fixtures/gedcom/parentless_siblings.ged");
+
+    fn characters(graph: &FamilyTree) -> Vec<&Character> {
+        graph
+            .iter()
+            .filter_map(|node| match node.data() {
+                Node::Character(chara) => Some(chara),
+                Node::Family => None,
+            })
+            .collect()
+    }
+
+    fn name(graph: &FamilyTree, id: NodeId) -> String {
+        match graph.get(id).map(|node| node.data()) {
+            Some(Node::Character(chara)) => chara.name.clone(),
+            _ => String::new(),
+        }
+    }
+
+    /// Every family as the names of its partners and children, to compare graphs with different
+    /// ids.
+    fn families(graph: &FamilyTree) -> Vec<(Vec<String>, Vec<String>)> {
+        let mut families: Vec<_> = graph
+            .iter()
+            .filter(|node| matches!(node.data(), Node::Family))
+            .map(|family| {
+                let names = |top| {
+                    let mut names: Vec<_> = graph
+                        .connections()
+                        .filter_map(|conn| {
+                            let (family_side, other) = if conn.a.0 == family.id() {
+                                (&conn.a.1, conn.b.0)
+                            } else if conn.b.0 == family.id() {
+                                (&conn.b.1, conn.a.0)
+                            } else {
+                                return None;
+                            };
+
+                            (family_side.is_top() == top).then(|| name(graph, other))
+                        })
+                        .collect();
+
+                    names.sort();
+                    names
+                };
+
+                (names(true), names(false))
+            })
+            .collect();
+
+        families.sort();
+        families
+    }
+
+    fn round_trip(source: &str) -> (FamilyTree, FamilyTree) {
+        let assets = AssetsData::default();
+
+        let imported = import(source, &assets).unwrap();
+        let exported = export(&imported, &assets).unwrap();
+        let reimported = import(&exported, &assets).unwrap();
+
+        (imported, reimported)
+    }
+
+    #[test]
+    fn imports_individuals() {
+        let graph = import(FAMILY, &AssetsData::default()).unwrap();
+        let characters = characters(&graph);
+
+        let names: Vec<_> = characters.iter().map(|chara| chara.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Arthur Pendragon",
+                "Guinevere Leodegrance",
+                "Mordred Pendragon",
+                "Elaine"
+            ]
+        );
+
+        assert_eq!(characters[0].birth, "12 MAR 1850");
+        assert_eq!(characters[0].death, "1901");
+        assert_eq!(characters[1].birth, "ABT 1855");
+        assert_eq!(
+            characters[0].notes,
+            "King of the Britons.\nPulled the sword from the stone."
+        );
+        assert_eq!(
+            characters[2].notes,
+            "Raised far from court, returned as a knight.\nSecond line of the note."
+        );
+    }
+
+    #[test]
+    fn imports_families() {
+        let graph = import(FAMILY, &AssetsData::default()).unwrap();
+
+        assert_eq!(
+            families(&graph),
+            [(
+                vec![
+                    "Arthur Pendragon".to_string(),
+                    "Guinevere Leodegrance".to_string()
+                ],
+                vec!["Elaine".to_string(), "Mordred Pendragon".to_string()]
+            )]
+        );
+    }
+
+    #[test]
+    fn round_trips_individuals_and_families() {
+        for source in [FAMILY, PARENTLESS_SIBLINGS] {
+            let (imported, reimported) = round_trip(source);
+
+            let summary = |graph: &FamilyTree| {
+                characters(graph)
+                    .into_iter()
+                    .map(|chara| {
+                        (
+                            chara.name.clone(),
+                            chara.birth.clone(),
+                            chara.death.clone(),
+                            chara.notes.clone(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+
+            assert_eq!(summary(&imported), summary(&reimported));
+            assert_eq!(families(&imported), families(&reimported));
+        }
+    }
+
+    #[test]
+    fn parentless_siblings_stay_siblings() {
+        let (_, reimported) = round_trip(PARENTLESS_SIBLINGS);
+
+        let ids: Vec<_> = reimported
+            .iter()
+            .filter(|node| matches!(node.data(), Node::Character(_)))
+            .map(GraphNode::id)
+            .collect();
+
+        assert_eq!(
+            kinship::relationship_between(&reimported, ids[0], ids[1]),
+            Some(kinship::Kinship::Sibling)
+        );
+    }
+
+    #[test]
+    fn exports_surnames() {
+        let assets = AssetsData::default();
+        let exported = export(&import(FAMILY, &assets).unwrap(), &assets).unwrap();
+
+        assert!(exported.contains("1 NAME Arthur /Pendragon/\n"));
+        assert!(exported.contains("1 NAME Elaine\n"));
+    }
+
+    #[test]
+    fn splits_long_notes() {
+        let assets = AssetsData::default();
+        let mut graph = import(FAMILY, &assets).unwrap();
+
+        let notes = format!(
+            "{}\n\n{}",
+            "a long sentence that goes on and on ".repeat(20).trim_end(),
+            "x".repeat(600)
+        );
+
+        let id = graph.iter().next().unwrap().id();
+        if let Some(Node::Character(chara)) = graph.get_mut(id).map(|node| node.data_mut()) {
+            chara.notes = notes.clone();
+        }
+
+        let exported = export(&graph, &assets).unwrap();
+
+        assert!(exported.lines().all(|line| line.chars().count() <= 255));
+        assert!(exported.lines().any(|line| line.starts_with("2 CONC ")));
+        assert!(
+            exported
+                .lines()
+                .all(|line| line.trim_end() == line && !line.contains("CONC  "))
+        );
+
+        let reimported = import(&exported, &assets).unwrap();
+        assert_eq!(characters(&reimported)[0].notes, notes);
+    }
+
+    #[test]
+    fn refuses_more_than_two_partners() {
+        let assets = AssetsData::default();
+        let mut graph = import(FAMILY, &assets).unwrap();
+
+        let family = graph
+            .iter()
+            .find(|node| matches!(node.data(), Node::Family))
+            .unwrap()
+            .id();
+        let third = graph.add(
+            Node::Character(Character::new("Lancelot", AssetHandle::NONE)),
+            Point::ORIGIN,
+        );
+        graph
+            .connect(
+                third,
+                RelativeAttachment::right(),
+                family,
+                RelativeAttachment::top(),
+            )
+            .unwrap();
+
+        let err = export(&graph, &assets).unwrap_err();
+
+        assert!(matches!(
+            err.downcast_ref::<GedcomError>(),
+            Some(GedcomError::TooManyPartners(names)) if names.contains("Lancelot")
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_files() {
+        let assets = AssetsData::default();
+        let error = |source| {
+            import(source, &assets)
+                .unwrap_err()
+                .downcast::<GedcomError>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            error("0 HEAD\n1 CHAR UTF-8\nnot a line\n"),
+            GedcomError::InvalidLine(3)
+        );
+        assert_eq!(error("0 HEAD\n2 VERS 5.5.1\n"), GedcomError::InvalidLine(2));
+        assert_eq!(
+            error("0 @I1 INDI\n1 NAME Nobody\n"),
+            GedcomError::InvalidLine(1)
+        );
+        assert_eq!(error("0 HEAD\n0 TRLR\n"), GedcomError::NoIndividuals);
+        assert_eq!(error(""), GedcomError::NoIndividuals);
+    }
+}
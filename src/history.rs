@@ -0,0 +1,624 @@
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use iced::Point;
+
+use crate::{
+    assets::{AssetHandle, AssetPath, AssetsData, Crop, TrashedAsset},
+    graph::{Attachment, Connection, GraphData, NodeId, RemovedNode},
+    relationships::Relationships,
+};
+
+/// A single invertible change to the project.
+#[derive(Debug, Clone)]
+pub enum Command<Data, A>
+where
+    Data: std::fmt::Debug,
+    A: Attachment,
+{
+    AddNode(RemovedNode<Data, A>),
+    RemoveNode(RemovedNode<Data, A>),
+    MoveNode {
+        id: NodeId,
+        from: Point,
+        to: Point,
+    },
+    EditNode {
+        id: NodeId,
+        from: Data,
+        to: Data,
+    },
+    Connect(Connection<A>),
+    Disconnect(Connection<A>),
+    RenameAsset {
+        handle: AssetHandle,
+        from: AssetPath,
+        to: AssetPath,
+    },
+    TrashAsset(TrashedAsset),
+    CropAsset {
+        handle: AssetHandle,
+        from: Option<Crop>,
+        to: Option<Crop>,
+    },
+    /// Replaces every relationship kind, like when an import brings new ones.
+    SetRelationships {
+        from: Relationships,
+        to: Relationships,
+    },
+}
+
+impl<Data, A> Command<Data, A>
+where
+    Data: std::fmt::Debug + Clone,
+    A: Attachment + PartialEq,
+{
+    fn undo(
+        &self,
+        nodes: &mut GraphData<Data, A>,
+        assets: &mut AssetsData,
+        relationships: &mut Relationships,
+    ) -> Result<()> {
+        match self {
+            Self::AddNode(added) => {
+                nodes.remove(added.id());
+            }
+            Self::RemoveNode(removed) => nodes.restore(removed.clone()),
+            Self::MoveNode { id, from, .. } => {
+                if let Some(node) = nodes.get_mut(*id) {
+                    node.move_to(*from);
+                }
+            }
+            Self::EditNode { id, from, .. } => {
+                if let Some(node) = nodes.get_mut(*id) {
+                    *node.data_mut() = from.clone();
+                }
+            }
+            Self::Connect(connection) => {
+                nodes.remove_connection(connection.id);
+            }
+            Self::Disconnect(connection) => nodes.restore_connection(connection.clone()),
+            Self::RenameAsset { handle, from, .. } => {
+                assets.rename(*handle, &from.relative())?;
+            }
+            Self::TrashAsset(trashed) => assets.restore(trashed)?,
+            Self::CropAsset { handle, from, .. } => {
+                assets.set_crop(*handle, *from)?;
+            }
+            Self::SetRelationships { from, .. } => *relationships = from.clone(),
+        }
+
+        Ok(())
+    }
+
+    fn redo(
+        &self,
+        nodes: &mut GraphData<Data, A>,
+        assets: &mut AssetsData,
+        relationships: &mut Relationships,
+    ) -> Result<()> {
+        match self {
+            Self::AddNode(added) => nodes.restore(added.clone()),
+            Self::RemoveNode(removed) => {
+                nodes.remove(removed.id());
+            }
+            Self::MoveNode { id, to, .. } => {
+                if let Some(node) = nodes.get_mut(*id) {
+                    node.move_to(*to);
+                }
+            }
+            Self::EditNode { id, to, .. } => {
+                if let Some(node) = nodes.get_mut(*id) {
+                    *node.data_mut() = to.clone();
+                }
+            }
+            Self::Connect(connection) => nodes.restore_connection(connection.clone()),
+            Self::Disconnect(connection) => {
+                nodes.remove_connection(connection.id);
+            }
+            Self::RenameAsset { handle, to, .. } => {
+                assets.rename(*handle, &to.relative())?;
+            }
+            Self::TrashAsset(trashed) => assets.move_to_trash(trashed)?,
+            Self::CropAsset { handle, to, .. } => {
+                assets.set_crop(*handle, *to)?;
+            }
+            Self::SetRelationships { to, .. } => *relationships = to.clone(),
+        }
+
+        Ok(())
+    }
+}
+
+/// How soon after the last one an edit of the same node still counts as part of it, like the
+/// keystrokes of typing a name.
+const EDIT_MERGE_WINDOW: Duration = Duration::from_secs(1);
+
+/// Undo and redo stacks. Every entry is a list of commands that get undone together.
+#[derive(Debug)]
+pub struct History<Data, A>
+where
+    Data: std::fmt::Debug,
+    A: Attachment,
+{
+    undo: Vec<Vec<Command<Data, A>>>,
+    redo: Vec<Vec<Command<Data, A>>>,
+    open: Option<Vec<Command<Data, A>>>,
+    /// When a command was last recorded outside of an open entry, see [`EDIT_MERGE_WINDOW`].
+    last_edit: Option<Instant>,
+    /// Bumped by every change to the undo stack, including undoing and redoing.
+    revision: u64,
+}
+
+impl<Data, A> Default for History<Data, A>
+where
+    Data: std::fmt::Debug,
+    A: Attachment,
+{
+    fn default() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            open: None,
+            last_edit: None,
+            revision: 0,
+        }
+    }
+}
+
+impl<Data, A> History<Data, A>
+where
+    Data: std::fmt::Debug + Clone,
+    A: Attachment + PartialEq,
+{
+    /// Starts merging every recorded command into one entry until [`History::commit`] is
+    /// called. Does nothing if an entry is already open.
+    pub fn begin(&mut self) {
+        if self.open.is_none() {
+            self.open = Some(Vec::new());
+        }
+    }
+
+    pub fn commit(&mut self) {
+        if let Some(commands) = self.open.take()
+            && !commands.is_empty()
+        {
+            self.undo.push(commands);
+            self.redo.clear();
+            self.last_edit = None;
+            self.revision += 1;
+        }
+    }
+
+    pub fn record(&mut self, command: Command<Data, A>) {
+        let Some(open) = &mut self.open else {
+            let now = Instant::now();
+            let continues_edit = self
+                .last_edit
+                .replace(now)
+                .is_some_and(|last| now - last < EDIT_MERGE_WINDOW);
+
+            // typing into a field sends an edit for every keystroke, keep editing the same node
+            // without a pause as one entry
+            if let Command::EditNode { id, to, .. } = &command
+                && continues_edit
+                && let Some(
+                    [
+                        Command::EditNode {
+                            id: prev_id,
+                            to: prev_to,
+                            ..
+                        },
+                    ],
+                ) = self.undo.last_mut().map(Vec::as_mut_slice)
+                && prev_id == id
+            {
+                *prev_to = to.clone();
+                self.redo.clear();
+                self.revision += 1;
+                return;
+            }
+
+            self.undo.push(vec![command]);
+            self.redo.clear();
+            self.revision += 1;
+            return;
+        };
+
+        // a drag publishes a move for every cursor movement, only keep where it started
+        if let Command::MoveNode { id, to, .. } = &command
+            && let Some(Command::MoveNode { to: prev_to, .. }) = open
+                .iter_mut()
+                .find(|cmd| matches!(cmd, Command::MoveNode { id: prev_id, .. } if prev_id == id))
+        {
+            *prev_to = *to;
+            return;
+        }
+
+        open.push(command);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty() || self.open.as_ref().is_some_and(|open| !open.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(
+        &mut self,
+        nodes: &mut GraphData<Data, A>,
+        assets: &mut AssetsData,
+        relationships: &mut Relationships,
+    ) -> Result<()> {
+        self.commit();
+
+        let Some(commands) = self.undo.pop() else {
+            return Ok(());
+        };
+
+        // a command failing halfway, like renaming back to a name that got taken since, leaves
+        // the entry where it was, with the commands before it put back the way they were
+        for (i, command) in commands.iter().enumerate().rev() {
+            if let Err(err) = command.undo(nodes, assets, relationships) {
+                for undone in &commands[i + 1..] {
+                    let _ = undone.redo(nodes, assets, relationships);
+                }
+
+                self.undo.push(commands);
+                return Err(err);
+            }
+        }
+
+        self.redo.push(commands);
+        self.last_edit = None;
+        self.revision += 1;
+
+        Ok(())
+    }
+
+    pub fn redo(
+        &mut self,
+        nodes: &mut GraphData<Data, A>,
+        assets: &mut AssetsData,
+        relationships: &mut Relationships,
+    ) -> Result<()> {
+        self.commit();
+
+        let Some(commands) = self.redo.pop() else {
+            return Ok(());
+        };
+
+        for (i, command) in commands.iter().enumerate() {
+            if let Err(err) = command.redo(nodes, assets, relationships) {
+                for redone in commands[..i].iter().rev() {
+                    let _ = redone.undo(nodes, assets, relationships);
+                }
+
+                self.redo.push(commands);
+                return Err(err);
+            }
+        }
+
+        self.undo.push(commands);
+        self.last_edit = None;
+        self.revision += 1;
+
+        Ok(())
+    }
+
+    /// Changes whenever the project gets changed through the history, to tell if anything
+    /// happened since an earlier point.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.open = None;
+        self.last_edit = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assets::AssetKind,
+        graph::{LinePattern, RelativeAttachment},
+        relationships::RelationshipKind,
+    };
+
+    type Graph = GraphData<String, RelativeAttachment>;
+
+    fn position(graph: &Graph, id: NodeId) -> Point {
+        graph.get(id).unwrap().position()
+    }
+
+    fn name(graph: &Graph, id: NodeId) -> &str {
+        graph.get(id).unwrap().data()
+    }
+
+    /// Renames the node and records it, like the inspector does.
+    fn edit(
+        history: &mut History<String, RelativeAttachment>,
+        graph: &mut Graph,
+        id: NodeId,
+        to: &str,
+    ) {
+        let node = graph.get_mut(id).unwrap();
+        let from = std::mem::replace(node.data_mut(), to.to_string());
+
+        history.record(Command::EditNode {
+            id,
+            from,
+            to: to.to_string(),
+        });
+    }
+
+    #[test]
+    fn undo_and_redo_round_trip() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        history.record(Command::AddNode(graph.snapshot(anna).unwrap()));
+
+        let to = Point::new(10.0, 20.0);
+        graph.get_mut(anna).unwrap().move_to(to);
+        history.record(Command::MoveNode {
+            id: anna,
+            from: Point::ORIGIN,
+            to,
+        });
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(position(&graph, anna), Point::ORIGIN);
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert!(!graph.contains(anna));
+        assert!(!history.can_undo());
+
+        history
+            .redo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        history
+            .redo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(position(&graph, anna), to);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn merges_edits_within_the_window() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("A".into(), Point::ORIGIN);
+        edit(&mut history, &mut graph, anna, "An");
+        edit(&mut history, &mut graph, anna, "Ann");
+        edit(&mut history, &mut graph, anna, "Anna");
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(name(&graph, anna), "A");
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn doesnt_merge_edits_after_a_pause() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("A".into(), Point::ORIGIN);
+        edit(&mut history, &mut graph, anna, "An");
+        history.last_edit = Some(Instant::now() - EDIT_MERGE_WINDOW * 2);
+        edit(&mut history, &mut graph, anna, "Anna");
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(name(&graph, anna), "An");
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(name(&graph, anna), "A");
+    }
+
+    #[test]
+    fn doesnt_merge_edits_of_other_nodes() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("A".into(), Point::ORIGIN);
+        let ben = graph.add("B".into(), Point::ORIGIN);
+        edit(&mut history, &mut graph, anna, "Anna");
+        edit(&mut history, &mut graph, ben, "Ben");
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(name(&graph, anna), "Anna");
+        assert_eq!(name(&graph, ben), "B");
+    }
+
+    #[test]
+    fn undoes_a_drag_in_one_step() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        let ben = graph.add("Ben".into(), Point::new(50.0, 0.0));
+
+        history.begin();
+        for step in 1..=5 {
+            let offset = iced::Vector::new(step as f32, step as f32);
+
+            for (id, start) in [(anna, Point::ORIGIN), (ben, Point::new(50.0, 0.0))] {
+                let from = position(&graph, id);
+                graph.get_mut(id).unwrap().move_to(start + offset);
+                history.record(Command::MoveNode {
+                    id,
+                    from,
+                    to: start + offset,
+                });
+            }
+        }
+        history.commit();
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(position(&graph, anna), Point::ORIGIN);
+        assert_eq!(position(&graph, ben), Point::new(50.0, 0.0));
+        assert!(!history.can_undo());
+
+        history
+            .redo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(position(&graph, anna), Point::new(5.0, 5.0));
+        assert_eq!(position(&graph, ben), Point::new(55.0, 5.0));
+    }
+
+    #[test]
+    fn undoes_a_multi_delete_in_one_step() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        let ben = graph.add("Ben".into(), Point::ORIGIN);
+        graph
+            .connect(
+                anna,
+                RelativeAttachment::Center,
+                ben,
+                RelativeAttachment::Center,
+            )
+            .unwrap();
+
+        history.begin();
+        for id in [anna, ben] {
+            history.record(Command::RemoveNode(graph.remove(id).unwrap()));
+        }
+        history.commit();
+        assert_eq!(graph.num_nodes(), 0);
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(graph.num_nodes(), 2);
+        assert_eq!(graph.connections().count(), 1);
+    }
+
+    #[test]
+    fn a_new_command_clears_redo() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("A".into(), Point::ORIGIN);
+        edit(&mut history, &mut graph, anna, "Anna");
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert!(history.can_redo());
+
+        edit(&mut history, &mut graph, anna, "Ann");
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn undoes_added_relationship_kinds_with_the_nodes() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        // what importing a file with a new kind does
+        history.begin();
+        let from = relationships.clone();
+        let pen_pals = relationships.add(RelationshipKind::new(
+            "Pen pals",
+            [0, 0, 0],
+            LinePattern::Solid,
+        ));
+        history.record(Command::SetRelationships {
+            from,
+            to: relationships.clone(),
+        });
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        history.record(Command::AddNode(graph.snapshot(anna).unwrap()));
+        history.commit();
+
+        history
+            .undo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert!(relationships.get(pen_pals).is_none());
+        assert!(!graph.contains(anna));
+
+        history
+            .redo(&mut graph, &mut assets, &mut relationships)
+            .unwrap();
+        assert_eq!(relationships.get(pen_pals).unwrap().label, "Pen pals");
+        assert!(graph.contains(anna));
+    }
+
+    #[test]
+    fn rolls_back_an_entry_that_fails_halfway() {
+        let mut graph = Graph::default();
+        let mut assets = AssetsData::default();
+        let mut relationships = Relationships::default();
+        let mut history = History::default();
+
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        let to = Point::new(10.0, 0.0);
+
+        // renaming an asset that doesn't exist fails once the move after it got undone
+        history.begin();
+        history.record(Command::RenameAsset {
+            handle: AssetHandle::NONE,
+            from: AssetPath::new(AssetKind::Image, "a.png"),
+            to: AssetPath::new(AssetKind::Image, "b.png"),
+        });
+        graph.get_mut(anna).unwrap().move_to(to);
+        history.record(Command::MoveNode {
+            id: anna,
+            from: Point::ORIGIN,
+            to,
+        });
+        history.commit();
+
+        let revision = history.revision();
+        assert!(
+            history
+                .undo(&mut graph, &mut assets, &mut relationships)
+                .is_err()
+        );
+
+        assert_eq!(position(&graph, anna), to);
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+        assert_eq!(history.revision(), revision);
+    }
+}
@@ -0,0 +1,487 @@
+use iced::{
+    Alignment, Element, Font,
+    Length::Fill,
+    Task,
+    font::Weight,
+    widget::{
+        button, column, container, pick_list, row, scrollable, text, text_editor, text_input,
+    },
+};
+
+use crate::{
+    Character, Message, Node, State,
+    assets::{self, Asset, AssetHandle, AssetPath, AssetsMessage},
+    graph::{GraphData, NodeId},
+    history::Command,
+    kinship,
+    relationships::{self, RelationshipsMessage},
+    style,
+    widgets::icons,
+};
+
+/// Editor state of the inspector pane that doesn't live in the graph itself.
+#[derive(Default)]
+pub struct Inspector {
+    node: Option<NodeId>,
+    notes: text_editor::Content,
+    tag_input: String,
+}
+
+impl Inspector {
+    /// Makes the inspector follow the selection. Needs to be called whenever the graph might
+    /// have changed.
+    pub fn sync<A>(&mut self, nodes: &GraphData<Node, A>)
+    where
+        A: crate::graph::Attachment + PartialEq,
+    {
+        let selected = single_selection(nodes);
+
+        let notes = selected
+            .and_then(|id| nodes.get(id))
+            .and_then(|node| match node.data() {
+                Node::Character(chara) => Some(chara.notes.as_str()),
+                Node::Family => None,
+            })
+            .unwrap_or("");
+
+        if selected != self.node {
+            self.node = selected;
+            self.tag_input.clear();
+            self.notes = text_editor::Content::with_text(notes);
+        } else if editor_text(&self.notes) != notes {
+            self.notes = text_editor::Content::with_text(notes);
+        }
+    }
+
+    /// Whether a character is shown that assets can be attached to.
+    pub fn accepts<A>(&self, nodes: &GraphData<Node, A>) -> bool
+    where
+        A: crate::graph::Attachment + PartialEq,
+    {
+        self.node
+            .and_then(|id| nodes.get(id))
+            .is_some_and(|node| matches!(node.data(), Node::Character(_)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InspectorMessage {
+    NameChanged(String),
+    BirthChanged(String),
+    DeathChanged(String),
+    NotesEdited(text_editor::Action),
+    TagInputChanged(String),
+    AddTag,
+    RemoveTag(usize),
+    ImageSelected(AssetPath),
+    /// Attaches an asset, or makes it the image if it is one.
+    Attach(AssetHandle),
+    AttachmentSelected(AssetPath),
+    Detach(AssetHandle),
+    OpenAttachment(AssetHandle),
+    Relationships(RelationshipsMessage),
+}
+
+pub fn update(state: &mut State, message: InspectorMessage) -> Task<Message> {
+    let inspected = state
+        .inspector
+        .node
+        .and_then(|id| match state.nodes.get(id)?.data() {
+            Node::Character(chara) => Some((id, chara.clone())),
+            Node::Family => None,
+        });
+
+    match (message, inspected) {
+        (InspectorMessage::Relationships(message), _) => {
+            relationships::update(&mut state.relationships, message);
+            // relationship kinds aren't part of the history, so update can't notice them changing
+            state.dirty = true;
+
+            Task::none()
+        }
+        (_, None) => Task::none(),
+        (InspectorMessage::NameChanged(name), Some((id, mut chara))) => {
+            chara.name = name;
+            edit(state, id, chara)
+        }
+        (InspectorMessage::BirthChanged(birth), Some((id, mut chara))) => {
+            chara.birth = birth;
+            edit(state, id, chara)
+        }
+        (InspectorMessage::DeathChanged(death), Some((id, mut chara))) => {
+            chara.death = death;
+            edit(state, id, chara)
+        }
+        (InspectorMessage::NotesEdited(action), Some((id, mut chara))) => {
+            let is_edit = action.is_edit();
+
+            state.inspector.notes.perform(action);
+
+            if !is_edit {
+                return Task::none();
+            }
+
+            chara.notes = editor_text(&state.inspector.notes);
+            edit(state, id, chara)
+        }
+        (InspectorMessage::TagInputChanged(input), Some(_)) => {
+            state.inspector.tag_input = input;
+            Task::none()
+        }
+        (InspectorMessage::AddTag, Some((id, mut chara))) => {
+            let tag = std::mem::take(&mut state.inspector.tag_input);
+            let tag = tag.trim();
+
+            if tag.is_empty() || chara.tags.iter().any(|t| t == tag) {
+                return Task::none();
+            }
+
+            chara.tags.push(tag.to_string());
+            edit(state, id, chara)
+        }
+        (InspectorMessage::RemoveTag(i), Some((id, mut chara))) => {
+            if i >= chara.tags.len() {
+                return Task::none();
+            }
+
+            chara.tags.remove(i);
+            edit(state, id, chara)
+        }
+        (InspectorMessage::ImageSelected(path), Some((id, mut chara))) => {
+            let Some(handle) = state.assets.handle(path) else {
+                return Task::none();
+            };
+
+            chara.img = handle;
+            edit(state, id, chara)
+        }
+        (InspectorMessage::Attach(handle), Some((id, mut chara))) => {
+            match state.assets.get(handle) {
+                Some(Asset::Image(_)) => chara.img = handle,
+                Some(_) if !chara.attachments.contains(&handle) => chara.attachments.push(handle),
+                _ => return Task::none(),
+            }
+
+            edit(state, id, chara)
+        }
+        (InspectorMessage::AttachmentSelected(path), Some(_)) => {
+            let Some(handle) = state.assets.handle(path) else {
+                return Task::none();
+            };
+
+            update(state, InspectorMessage::Attach(handle))
+        }
+        (InspectorMessage::Detach(handle), Some((id, mut chara))) => {
+            chara.attachments.retain(|h| *h != handle);
+            edit(state, id, chara)
+        }
+        (InspectorMessage::OpenAttachment(handle), Some(_)) => {
+            Task::done(Message::AssetsMessage(AssetsMessage::OpenAsset(handle)))
+        }
+    }
+}
+
+/// Puts the edited character back into the graph, recording the change.
+fn edit(state: &mut State, id: NodeId, chara: Character) -> Task<Message> {
+    let Some(node) = state.nodes.get_mut(id) else {
+        return Task::none();
+    };
+
+    let from = std::mem::replace(node.data_mut(), Node::Character(chara.clone()));
+
+    state.history.record(Command::EditNode {
+        id,
+        from,
+        to: Node::Character(chara),
+    });
+
+    Task::none()
+}
+
+pub fn view(state: &State) -> Element<'_, InspectorMessage> {
+    let Some(node) = state.inspector.node.and_then(|id| state.nodes.get(id)) else {
+        // without a selection there's nothing to inspect but the project itself
+        let mut selection = state.nodes.selection();
+
+        let relationship = match (selection.next(), selection.next(), selection.next()) {
+            (Some(a), Some(b), None) => kinship_text(state, a, b),
+            _ => None,
+        };
+
+        return scrollable(
+            column![
+                relationship.map_or_else(
+                    || text("Select a single node to see its properties.").size(14.0),
+                    |relationship| text(relationship).size(14.0)
+                ),
+                field(
+                    "Relationship kinds",
+                    relationships::view(&state.relationships).map(InspectorMessage::Relationships)
+                ),
+            ]
+            .spacing(10.0)
+            .padding(5.0),
+        )
+        .style(style::scrollable)
+        .into();
+    };
+
+    let Node::Character(chara) = node.data() else {
+        return placeholder("Family nodes don't have any properties.");
+    };
+
+    let mut images: Vec<_> = state
+        .assets
+        .iter()
+        .filter(|(_, _, asset)| matches!(asset, Asset::Image(_)))
+        .map(|(_, path, _)| path.clone())
+        .collect();
+
+    images.sort_by_key(|path| path.to_string());
+
+    let mut attachable: Vec<_> = state
+        .assets
+        .iter()
+        .filter(|(handle, _, asset)| {
+            !matches!(asset, Asset::Image(_)) && !chara.attachments.contains(handle)
+        })
+        .map(|(_, path, _)| path.clone())
+        .collect();
+
+    attachable.sort_by_key(|path| path.to_string());
+
+    let attachments = column(chara.attachments.iter().map(|&handle| {
+        let name = match state.assets.path(handle) {
+            Some(path) => text(path.name()),
+            None => text("Missing file").style(text::danger),
+        };
+
+        row![]
+            .push_maybe(
+                state
+                    .assets
+                    .get(handle)
+                    .zip(state.assets.path(handle))
+                    .map(|(asset, path)| assets::preview(asset, path, 30.0)),
+            )
+            .push(
+                button(name.size(13.0))
+                    .on_press_maybe(
+                        state
+                            .assets
+                            .get(handle)
+                            .map(|_| InspectorMessage::OpenAttachment(handle)),
+                    )
+                    .padding(2.0)
+                    .width(Fill)
+                    .style(style::menu_button),
+            )
+            .push(
+                button(icons::close().size(10.0).center())
+                    .on_press(InspectorMessage::Detach(handle))
+                    .padding(2.0)
+                    .style(style::menu_button),
+            )
+            .spacing(6.0)
+            .align_y(Alignment::Center)
+            .into()
+    }))
+    .spacing(4.0);
+
+    let tags = row(chara.tags.iter().enumerate().map(|(i, tag)| {
+        container(
+            row![
+                text(tag).size(13.0),
+                button(icons::close().size(10.0).center())
+                    .on_press(InspectorMessage::RemoveTag(i))
+                    .padding(2.0)
+                    .style(style::menu_button),
+            ]
+            .spacing(4.0)
+            .align_y(Alignment::Center),
+        )
+        .padding([2.0, 8.0])
+        .style(style::tag)
+        .into()
+    }))
+    .spacing(4.0)
+    .wrap();
+
+    scrollable(
+        column![
+            field(
+                "Name",
+                text_input("Name", &chara.name)
+                    .on_input(InspectorMessage::NameChanged)
+                    .style(style::text_input)
+            ),
+            row![
+                field(
+                    "Born",
+                    text_input("e.g. 12 Mar 1850", &chara.birth)
+                        .on_input(InspectorMessage::BirthChanged)
+                        .style(style::text_input)
+                ),
+                field(
+                    "Died",
+                    text_input("e.g. 1921", &chara.death)
+                        .on_input(InspectorMessage::DeathChanged)
+                        .style(style::text_input)
+                ),
+            ]
+            .spacing(8.0),
+            field(
+                "Image",
+                column![
+                    pick_list(
+                        images,
+                        state.assets.path(chara.img).cloned(),
+                        InspectorMessage::ImageSelected
+                    )
+                    .placeholder("No image")
+                    .width(Fill)
+                ]
+                .push_maybe(state.assets.is_missing(chara.img).then(|| {
+                    text("The image file is missing, it was deleted or moved outside the app.")
+                        .size(13.0)
+                        .style(text::danger)
+                }))
+                .spacing(4.0)
+            ),
+            field(
+                "Attachments",
+                column![
+                    attachments,
+                    pick_list(
+                        attachable,
+                        None::<AssetPath>,
+                        InspectorMessage::AttachmentSelected
+                    )
+                    .placeholder("Attach an asset, or drop one here...")
+                    .width(Fill)
+                ]
+                .spacing(4.0)
+            ),
+            field(
+                "Tags",
+                column![
+                    tags,
+                    text_input("Add tag...", &state.inspector.tag_input)
+                        .on_input(InspectorMessage::TagInputChanged)
+                        .on_submit(InspectorMessage::AddTag)
+                        .style(style::text_input)
+                ]
+                .spacing(4.0)
+            ),
+            field(
+                "Notes",
+                text_editor(&state.inspector.notes)
+                    .placeholder("Notes...")
+                    .on_action(InspectorMessage::NotesEdited)
+                    .height(200.0)
+                    .style(style::text_editor)
+            ),
+            field("Family", family(state, node.id())),
+        ]
+        .spacing(10.0)
+        .padding(5.0),
+    )
+    .style(style::scrollable)
+    .into()
+}
+
+/// Close relatives of a character and how far the tree reaches around them.
+fn family(state: &State, id: NodeId) -> Element<'_, InspectorMessage> {
+    let nodes = &state.nodes;
+
+    let ancestors = kinship::ancestors(nodes, id).len();
+    let descendants = kinship::descendants(nodes, id).len();
+
+    let lines = [
+        ("Parents", kinship::parents(nodes, id)),
+        ("Partners", kinship::partners(nodes, id)),
+        ("Children", kinship::children(nodes, id)),
+        ("Siblings", kinship::siblings(nodes, id)),
+    ]
+    .into_iter()
+    .filter(|(_, relatives)| !relatives.is_empty())
+    .map(|(label, relatives)| text(format!("{label}: {}", names(state, &relatives))).size(13.0));
+
+    column![
+        text(format!(
+            "Generation {}, {ancestors} ancestors, {descendants} descendants",
+            kinship::generation(nodes, id)
+        ))
+        .size(13.0)
+    ]
+    .extend(lines.map(Element::from))
+    .spacing(2.0)
+    .into()
+}
+
+/// Describes how two selected characters are related, `None` if one of them isn't a character.
+fn kinship_text(state: &State, a: NodeId, b: NodeId) -> Option<String> {
+    let [a_name, b_name] = [a, b].map(|id| match state.nodes.get(id).map(|node| node.data()) {
+        Some(Node::Character(chara)) => Some(chara.name.as_str()),
+        _ => None,
+    });
+    let (a_name, b_name) = (a_name?, b_name?);
+
+    Some(match kinship::relationship_between(&state.nodes, a, b) {
+        Some(kinship) => format!("{a_name} is {b_name}'s {kinship}."),
+        None => format!("{a_name} and {b_name} aren't related."),
+    })
+}
+
+fn names(state: &State, ids: &[NodeId]) -> String {
+    ids.iter()
+        .filter_map(|id| match state.nodes.get(*id)?.data() {
+            Node::Character(chara) => Some(chara.name.as_str()),
+            Node::Family => None,
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn field<'a>(
+    label: &'a str,
+    input: impl Into<Element<'a, InspectorMessage>>,
+) -> Element<'a, InspectorMessage> {
+    let mut font = Font::DEFAULT;
+    font.weight = Weight::Semibold;
+
+    column![text(label).size(13.0).font(font), input.into()]
+        .spacing(4.0)
+        .into()
+}
+
+fn placeholder(message: &str) -> Element<'_, InspectorMessage> {
+    container(text(message).size(14.0).center())
+        .center_x(Fill)
+        .center_y(Fill)
+        .padding(10.0)
+        .into()
+}
+
+fn single_selection<A>(nodes: &GraphData<Node, A>) -> Option<NodeId>
+where
+    A: crate::graph::Attachment + PartialEq,
+{
+    let mut selection = nodes.selection();
+
+    match (selection.next(), selection.next()) {
+        (Some(id), None) => Some(id),
+        _ => None,
+    }
+}
+
+/// The editor always ends its text with a newline, which shouldn't end up in the notes.
+fn editor_text(content: &text_editor::Content) -> String {
+    let mut text = content.text();
+
+    if text.ends_with('\n') {
+        text.pop();
+    }
+
+    text
+}
@@ -2,28 +2,70 @@ use std::{
     collections::HashMap,
     fs::File,
     io::{BufReader, ErrorKind, Read, Write},
-    os::unix::fs::FileExt,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{Result, anyhow};
+use base64::{Engine, engine::general_purpose::STANDARD};
 use file_type::FileType;
-use iced::widget::image;
+use iced::{
+    futures::{
+        Stream,
+        channel::{mpsc, oneshot},
+    },
+    widget::image,
+};
 use ron::ser::PrettyConfig;
 use thiserror::Error;
 
-use crate::assets::{self, Asset, AssetKind, AssetPath};
+use crate::assets::{self, Asset, AssetKind, AssetPath, Crop, IndexEntry, Media, thumbnail};
 
 pub fn pick_file() -> Result<PathBuf> {
     let file_handle = rfd::FileDialog::new()
-        .set_title("Select an Image")
+        .set_title("Select an Asset")
         .add_filter("Image", &["webp", "png", "jpeg", "jpg"])
+        .add_filter("Audio", &["wav", "mp3", "ogg", "flac", "m4a"])
+        .add_filter("Video", &["mp4", "webm", "mkv", "mov", "avi"])
+        .add_filter("Document", &["pdf"])
+        .pick_file()
+        .ok_or(AssetsError::DialogClosed)?;
+
+    Ok(file_handle)
+}
+
+pub fn pick_gedcom_file() -> Result<PathBuf> {
+    let file_handle = rfd::FileDialog::new()
+        .set_title("Import GEDCOM")
+        .add_filter("GEDCOM", &["ged"])
+        .pick_file()
+        .ok_or(AssetsError::DialogClosed)?;
+
+    Ok(file_handle)
+}
+
+pub fn pick_json_file() -> Result<PathBuf> {
+    let file_handle = rfd::FileDialog::new()
+        .set_title("Import JSON")
+        .add_filter("JSON", &["json"])
         .pick_file()
         .ok_or(AssetsError::DialogClosed)?;
 
     Ok(file_handle)
 }
 
+pub fn pick_save_file(title: &str, filter: &str, extension: &str) -> Result<PathBuf> {
+    let file_handle = rfd::FileDialog::new()
+        .set_title(title)
+        .add_filter(filter, &[extension])
+        .save_file()
+        .ok_or(AssetsError::DialogClosed)?;
+
+    Ok(file_handle.with_extension(extension))
+}
+
 pub fn pick_folder() -> Result<PathBuf> {
     let file_handle = rfd::FileDialog::new()
         .set_title("Open a Folder")
@@ -33,14 +75,142 @@ pub fn pick_folder() -> Result<PathBuf> {
     Ok(file_handle)
 }
 
-pub fn save(path: PathBuf, data: String) -> Result<()> {
-    let mut file = File::create(path)?;
+/// Number of backups kept in a project's `.backups` folder, older ones get deleted.
+const KEPT_BACKUPS: usize = 10;
+
+/// Writes `data` to a temporary file next to `path` and renames it over `path` once it's on
+/// disk, so a crash or a full disk leaves either the old or the new file, never half of one.
+pub fn save(path: PathBuf, data: impl AsRef<[u8]>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{path:?} is not a file"))?
+        .to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.tmp"));
+
+    let mut file = File::create(&temp_path)?;
+
+    let written = file
+        .write_all(data.as_ref())
+        .and_then(|()| file.sync_all())
+        .and_then(|()| std::fs::rename(&temp_path, &path));
+
+    if let Err(err) = written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(anyhow!(err));
+    }
+
+    // the rename itself only survives a crash once the directory is synced too
+    if let Some(dir) = path.parent().and_then(|dir| File::open(dir).ok()) {
+        dir.sync_all()?;
+    }
+
+    Ok(())
+}
+
+/// A copy of a project's data.ron and .index.ron, taken right before they got overwritten.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub created: SystemTime,
+}
+
+impl Backup {
+    /// How long ago the backup was taken, like "5 minutes ago".
+    pub fn age(&self) -> String {
+        let seconds = self.created.elapsed().unwrap_or_default().as_secs();
+
+        let (amount, unit) = match seconds {
+            0..60 => return "Just now".to_string(),
+            60..3600 => (seconds / 60, "minute"),
+            3600..86400 => (seconds / 3600, "hour"),
+            _ => (seconds / 86400, "day"),
+        };
+
+        format!("{amount} {unit}{} ago", if amount == 1 { "" } else { "s" })
+    }
+}
+
+/// Copies the current data.ron and .index.ron of the project in `folder` into a new folder in
+/// `.backups` and deletes the oldest backups past [`KEPT_BACKUPS`].
+pub fn backup(folder: &Path) -> Result<()> {
+    if !folder.join("data.ron").exists() {
+        return Ok(());
+    }
+
+    let created = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let backup_folder = folder.join(".backups").join(created.to_string());
+
+    std::fs::create_dir_all(&backup_folder)?;
+
+    for file_name in ["data.ron", ".index.ron"] {
+        if folder.join(file_name).exists() {
+            std::fs::copy(folder.join(file_name), backup_folder.join(file_name))?;
+        }
+    }
 
-    file.write_all(data.as_bytes())?;
+    for old in backups(folder)?.into_iter().skip(KEPT_BACKUPS) {
+        std::fs::remove_dir_all(old.path)?;
+    }
 
     Ok(())
 }
 
+/// Backups of the project in `folder`, newest first.
+pub fn backups(folder: &Path) -> Result<Vec<Backup>> {
+    let Ok(entries) = std::fs::read_dir(folder.join(".backups")) else {
+        return Ok(Vec::new());
+    };
+
+    let mut backups: Vec<_> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let millis: u64 = path.file_name()?.to_str()?.parse().ok()?;
+
+            Some(Backup {
+                created: UNIX_EPOCH + Duration::from_millis(millis),
+                path,
+            })
+        })
+        .filter(|backup| backup.path.join("data.ron").exists())
+        .collect();
+
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created));
+
+    Ok(backups)
+}
+
+/// Puts the files of `backup` back into the project in `folder`, after backing up the current
+/// ones so restoring can be undone by restoring again.
+pub fn restore_backup(folder: &Path, backup: &Path) -> Result<()> {
+    let data = std::fs::read(backup.join("data.ron"))?;
+    let index = std::fs::read(backup.join(".index.ron")).ok();
+
+    self::backup(folder)?;
+
+    save(folder.join("data.ron"), data)?;
+
+    if let Some(index) = index {
+        save(folder.join(".index.ron"), index)?;
+    }
+
+    Ok(())
+}
+
+/// Lays out a new project in the empty `folder`: a subfolder for every kind of asset, an empty
+/// index and `data`, the initial data.ron.
+pub fn create_project(folder: &Path, data: String) -> Result<()> {
+    if std::fs::read_dir(folder)?.next().is_some() {
+        return Err(anyhow!(AssetsError::FolderNotEmpty));
+    }
+
+    for kind in AssetKind::all() {
+        std::fs::create_dir(folder.join(kind.folder()))?;
+    }
+
+    save(folder.join(".index.ron"), "")?;
+    save(folder.join("data.ron"), data)
+}
+
 pub fn load(path: PathBuf) -> Result<String> {
     let mut file = File::open(path.join("data.ron"))?;
 
@@ -56,7 +226,10 @@ fn new_key<V>(map: &HashMap<u32, V>) -> u32 {
     (0..).into_iter().find(|id| !keys.contains(&id)).unwrap()
 }
 
-pub fn load_dir(path: PathBuf) -> Result<HashMap<u32, (AssetPath, Asset)>> {
+/// Reads the index of the project in `path`, adding any files in the asset folders that aren't
+/// in it yet. A new file with the contents of an indexed one that's gone keeps its id, it was
+/// renamed while the project wasn't open.
+pub fn load_index(path: PathBuf) -> Result<HashMap<u32, IndexEntry>> {
     let index_path = path.join(".index.ron");
 
     let mut index_file = match File::options()
@@ -68,7 +241,7 @@ pub fn load_dir(path: PathBuf) -> Result<HashMap<u32, (AssetPath, Asset)>> {
     {
         Ok(index_file) => index_file,
         Err(err) => match err.kind() {
-            ErrorKind::NotFound => File::create_new(index_path)?,
+            ErrorKind::NotFound => File::create_new(&index_path)?,
             _ => return Err(anyhow!(err)),
         },
     };
@@ -77,89 +250,154 @@ pub fn load_dir(path: PathBuf) -> Result<HashMap<u32, (AssetPath, Asset)>> {
 
     index_file.read_to_string(&mut index_file_contents)?;
 
-    let mut index: HashMap<u32, AssetPath> = if index_file_contents.trim().is_empty() {
+    let mut index: HashMap<u32, IndexEntry> = if index_file_contents.trim().is_empty() {
         HashMap::default()
     } else {
-        ron::de::from_str(&index_file_contents)?
+        ron::de::from_str(&index_file_contents).or_else(|err| {
+            // indexes from before content hashes only have the paths
+            ron::de::from_str::<HashMap<u32, AssetPath>>(&index_file_contents)
+                .map(|paths| {
+                    paths
+                        .into_iter()
+                        .map(|(id, path)| (id, IndexEntry::new(path, "")))
+                        .collect()
+                })
+                .map_err(|_| err)
+        })?
     };
 
+    let mut changed = false;
+
     for kind in AssetKind::all() {
-        let dir: Vec<_> = std::fs::read_dir(path.join(kind.folder()))?
-            .filter_map(|entry| entry.ok())
-            .collect();
-
-        let current_index_size = index
-            .iter()
-            .filter(|(_, path)| path.kind() == *kind)
-            .count();
-
-        if current_index_size < dir.len() {
-            for entry in dir {
-                let file_name = entry.file_name().to_string_lossy().to_string();
-
-                let asset_path = AssetPath::new(*kind, file_name);
-
-                if !index.values().any(|path| path == &asset_path)
-                    && let Ok(asset_path) = AssetPath::try_from(asset_path.to_string().as_str())
-                {
-                    let id = new_key(&index);
-                    index.insert(id, asset_path);
-                }
-            }
+        // projects from before a kind existed don't have its folder
+        std::fs::create_dir_all(path.join(kind.folder()))?;
 
-            if let Ok(index_str) = ron::ser::to_string_pretty(&index, PrettyConfig::new()) {
-                if let Err(err) = index_file.set_len(0) {
-                    eprintln!("{err}");
-                };
-                match index_file.write_all_at(index_str.as_bytes(), 0) {
-                    Ok(_) => (),
-                    Err(err) => {
-                        eprintln!("{err}");
-                    }
-                }
+        for asset_path in asset_files(&path.join(kind.folder()), *kind, &[])? {
+            if index.values().any(|entry| entry.path == asset_path) {
+                continue;
             }
+
+            let hash = std::fs::read(&path + asset_path.clone())
+                .map(|bytes| thumbnail::content_hash(&bytes))
+                .unwrap_or_default();
+
+            let id = index
+                .iter()
+                .find(|(_, entry)| {
+                    !hash.is_empty() && entry.hash == hash && !(&path + entry.path.clone()).exists()
+                })
+                .map(|(id, _)| *id)
+                .unwrap_or_else(|| new_key(&index));
+
+            // a moved file keeps its tags and when it was added
+            let entry = match index.remove(&id) {
+                Some(moved) => IndexEntry {
+                    path: asset_path,
+                    hash,
+                    ..moved
+                },
+                None => IndexEntry::new(asset_path, hash),
+            };
+
+            index.insert(id, entry);
+            changed = true;
         }
     }
 
-    let assets = index
-        .into_iter()
-        .filter_map(|(id, entry)| {
-            let path = path.join(entry.to_string());
+    if changed
+        && let Ok(index_str) = ron::ser::to_string_pretty(&index, PrettyConfig::new())
+        && let Err(err) = save(index_path.clone(), index_str)
+    {
+        eprintln!("{err}");
+    }
 
-            let mut file = File::open(path.clone()).ok()?;
+    Ok(index)
+}
 
-            let mut buffer = Vec::new();
+/// The files in `dir`, which is the sub-folder `folders` of the folder of `kind`, and in every
+/// folder below it. Hidden files and folders are skipped.
+pub fn asset_files(dir: &Path, kind: AssetKind, folders: &[String]) -> Result<Vec<AssetPath>> {
+    let mut files = Vec::new();
 
-            file.read_to_end(&mut buffer).ok()?;
+    for entry in std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
 
-            let reader = BufReader::new(buffer.as_slice());
+        if name.starts_with('.') {
+            continue;
+        }
 
-            let file_type = FileType::try_from_reader(reader).expect("File type not found!");
+        let mut relative = folders.to_vec();
+        relative.push(name);
 
-            let media_types = file_type.media_types();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            files.extend(asset_files(&entry.path(), kind, &relative)?);
+        } else if let Ok(path) = AssetPath::from_relative(kind, &relative.join("/")) {
+            files.push(path);
+        }
+    }
 
-            let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+    Ok(files)
+}
 
-            if media_types.iter().any(|t| t.contains("image")) {
-                Some((
-                    id,
-                    (
-                        AssetPath::new(assets::AssetKind::Image, file_name.clone()),
-                        Asset::Image(assets::Image {
-                            handle: image::Handle::from_bytes(buffer),
-                        }),
-                    ),
-                ))
-            } else {
-                None
+/// Reads every asset in `index` on a few threads and streams them back as they're done, `None`
+/// for files that are missing or aren't assets. Reading stops when the stream is dropped.
+pub fn load_assets(
+    folder: PathBuf,
+    index: HashMap<u32, IndexEntry>,
+) -> impl Stream<Item = (u32, AssetPath, Option<Asset>)> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    std::thread::spawn(move || {
+        let queue = Mutex::new(index.into_iter());
+
+        let workers = std::thread::available_parallelism()
+            .map(NonZeroUsize::get)
+            .unwrap_or(4);
+
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| {
+                    loop {
+                        let Some((id, IndexEntry { path, crop, .. })) =
+                            queue.lock().unwrap().next()
+                        else {
+                            return;
+                        };
+
+                        let file = &folder + path.clone();
+                        let asset = load_file(&file, &folder).ok().map(|asset| match crop {
+                            Some(crop) => with_crop(asset, &file, &folder, crop),
+                            None => asset,
+                        });
+
+                        if sender.unbounded_send((id, path, asset)).is_err() {
+                            return;
+                        }
+                    }
+                });
             }
-        })
-        .collect();
+        });
+    });
 
-    Ok(assets)
+    receiver
 }
 
-pub fn load_file(path: &Path) -> Result<Asset> {
+/// `asset` read from `path`, showing only the part `crop` on characters if it's an image. An
+/// image the crop can't be made for shows whole.
+pub fn with_crop(asset: Asset, path: &Path, project: &Path, crop: Crop) -> Asset {
+    match asset {
+        Asset::Image(img) => Asset::Image(assets::Image {
+            cropped: thumbnail::cropped(project, &img.hash, path, crop).ok(),
+            ..img
+        }),
+        asset => asset,
+    }
+}
+
+/// Reads the asset at `path`, its kind going by the file's contents. Images stay on disk until
+/// something draws them, only previews get made, or taken from the cache of the project in
+/// `project`.
+pub fn load_file(path: &Path, project: &Path) -> Result<Asset> {
     let mut file = File::open(path)?;
 
     let mut buffer = Vec::new();
@@ -170,28 +408,70 @@ pub fn load_file(path: &Path) -> Result<Asset> {
 
     let file_type = FileType::try_from_reader(reader).expect("File type not found!");
 
-    if file_type
+    // some signatures, like the RIFF ones of WAV and AVI files, are ambiguous without the
+    // extension. Images still have to be recognised by their contents, they get decoded.
+    let by_extension = path
+        .extension()
+        .map(|extension| FileType::from_extension(extension.to_string_lossy()))
+        .unwrap_or_default()
+        .iter()
+        .flat_map(|file_type| file_type.media_types())
+        .filter_map(|media| AssetKind::from_media_type(media))
+        .find(|kind| *kind != AssetKind::Image);
+
+    let Some(kind) = file_type
         .media_types()
         .iter()
-        .any(|media| media.starts_with("image"))
-    {
-        Ok(Asset::Image(assets::Image {
-            handle: image::Handle::from_bytes(buffer),
-        }))
-    } else {
-        Err(anyhow!(AssetsError::InvalidAsset))
-    }
+        .find_map(|media| AssetKind::from_media_type(media))
+        .or(by_extension)
+    else {
+        return Err(anyhow!(AssetsError::InvalidAsset));
+    };
+
+    let hash = thumbnail::content_hash(&buffer);
+    let size = buffer.len() as u64;
+
+    // only images need their preview, for the rest a missing tool shouldn't stop importing
+    Ok(match kind {
+        AssetKind::Image => Asset::Image(assets::Image {
+            handle: image::Handle::from_path(path),
+            thumbnail: thumbnail::thumbnail(project, &hash, &buffer)
+                .unwrap_or_else(|_| image::Handle::from_path(path)),
+            cropped: None,
+            hash,
+            size,
+        }),
+        AssetKind::Audio => Asset::Audio(Media {
+            preview: thumbnail::waveform(project, &hash, &buffer).ok(),
+            hash,
+            size,
+        }),
+        AssetKind::Video => Asset::Video(Media {
+            preview: thumbnail::video_frame(project, &hash, path).ok(),
+            hash,
+            size,
+        }),
+        AssetKind::Document => Asset::Document(Media {
+            preview: thumbnail::document_page(project, &hash, path).ok(),
+            hash,
+            size,
+        }),
+    })
 }
 
+/// Copies the asset at `path` into the project, returning where it ended up. A file with the
+/// same name in the project is overwritten if `replace` is set, otherwise the copy gets a free
+/// name like `name (2).png`.
 pub fn copy_to_assets_dir(
     assets_folder: Option<PathBuf>,
     path: PathBuf,
+    replace: bool,
 ) -> Result<(PathBuf, Asset)> {
     let Some(folder) = assets_folder.clone() else {
         return Err(anyhow!(AssetsError::NoFolderLoaded));
     };
 
-    match load_file(&path) {
+    match load_file(&path, &folder) {
         Ok(asset) => {
             let file_name = path
                 .clone()
@@ -200,36 +480,199 @@ pub fn copy_to_assets_dir(
                 .to_string_lossy()
                 .to_string();
 
-            let new_path = folder.join(asset.folder()).join(file_name);
+            let dir = folder.join(asset.folder());
+
+            let new_path = if replace {
+                dir.join(file_name)
+            } else {
+                dir.join(free_file_name(&dir, &file_name))
+            };
 
-            std::fs::copy(path.clone(), new_path)?;
+            std::fs::copy(path.clone(), new_path.clone())?;
 
-            Ok((path, asset))
+            Ok((new_path.clone(), asset.moved_to(new_path)))
         }
         Err(err) => Err(err),
     }
 }
 
-pub fn write_index(index: &HashMap<u32, AssetPath>, assets_folder: Option<PathBuf>) -> Result<()> {
-    let Some(assets_folder) = assets_folder else {
-        return Err(anyhow!(AssetsError::NoFolderLoaded));
+/// Where files made to be imported, like pasted images, wait until they're copied into the
+/// project.
+fn import_dir() -> PathBuf {
+    std::env::temp_dir().join(env!("CARGO_PKG_NAME"))
+}
+
+/// Deletes `path` if it's one of the files written to be imported, once it was copied.
+pub fn remove_imported(path: &Path) {
+    if path.starts_with(import_dir()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// [`save_pasted_image`] on a thread of its own, as the clipboard tools can take a while.
+pub async fn paste_image(project: PathBuf) -> Result<PathBuf> {
+    let (sender, receiver) = oneshot::channel();
+
+    std::thread::spawn(move || sender.send(save_pasted_image(&project)));
+
+    receiver.await?
+}
+
+/// Writes the image on the clipboard to a PNG in the temp folder, to be imported like any other
+/// file. It's named `Pasted Image.png`, numbered if the project already has one.
+fn save_pasted_image(project: &Path) -> Result<PathBuf> {
+    let bytes = clipboard_image()?;
+
+    // tools hand over whatever claims to be a PNG, decoding it makes sure it is one
+    let image = ::image::load_from_memory(&bytes)
+        .map_err(|err| anyhow!(err).context(AssetsError::InvalidAsset))?;
+
+    let file_name = free_file_name(&project.join(AssetKind::Image.folder()), "Pasted Image.png");
+    std::fs::create_dir_all(import_dir())?;
+    let path = import_dir().join(file_name);
+
+    image.save_with_format(&path, ::image::ImageFormat::Png)?;
+
+    Ok(path)
+}
+
+/// Writes an edited version of the image called `name` to a PNG in the temp folder, to be
+/// imported next to it. It's named like `name (edited).png`, numbered if that's taken.
+pub fn save_edited_image(
+    project: &Path,
+    name: &str,
+    image: &::image::DynamicImage,
+) -> Result<PathBuf> {
+    let stem = Path::new(name)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    let file_name = free_file_name(
+        &project.join(AssetKind::Image.folder()),
+        &format!("{stem} (edited).png"),
+    );
+    std::fs::create_dir_all(import_dir())?;
+    let path = import_dir().join(file_name);
+
+    image.save_with_format(&path, ::image::ImageFormat::Png)?;
+
+    Ok(path)
+}
+
+/// The image on the clipboard as PNG. iced's clipboard only does text, so it's read with the
+/// platform's tools: wl-paste or xclip on Linux, osascript on macOS and PowerShell on Windows.
+fn clipboard_image() -> Result<Vec<u8>> {
+    let (tool, mut command) = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("powershell");
+        command.args([
+            "-NoProfile",
+            "-Command",
+            "Add-Type -AssemblyName System.Windows.Forms; \
+            $image = [Windows.Forms.Clipboard]::GetImage(); \
+            if ($image) { \
+                $stream = New-Object IO.MemoryStream; \
+                $image.Save($stream, [Drawing.Imaging.ImageFormat]::Png); \
+                [Convert]::ToBase64String($stream.ToArray()) \
+            }",
+        ]);
+        ("PowerShell", command)
+    } else if cfg!(target_os = "macos") {
+        let mut command = std::process::Command::new("osascript");
+        command.args(["-e", "the clipboard as «class PNGf»"]);
+        ("osascript", command)
+    } else if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        let mut command = std::process::Command::new("wl-paste");
+        command.args(["--no-newline", "--type", "image/png"]);
+        ("wl-paste", command)
+    } else {
+        let mut command = std::process::Command::new("xclip");
+        command.args(["-selection", "clipboard", "-target", "image/png", "-out"]);
+        ("xclip", command)
     };
 
-    let parsed_data = ron::ser::to_string_pretty(index, PrettyConfig::new())?;
+    let output = command
+        .output()
+        .map_err(|err| anyhow!(err).context(AssetsError::ClipboardToolFailed(tool)))?;
 
-    let index_path = assets_folder.join(".index.ron");
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow!(AssetsError::NoClipboardImage));
+    }
 
-    let mut index_file = File::options()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(index_path)?;
+    let stdout = output.stdout;
+
+    let png = if cfg!(target_os = "windows") {
+        String::from_utf8(stdout)
+            .ok()
+            .and_then(|base64| STANDARD.decode(base64.trim()).ok())
+    } else if cfg!(target_os = "macos") {
+        // printed as `«data PNGf89504E47...»`
+        String::from_utf8(stdout).ok().and_then(|data| {
+            let hex = data.trim().strip_prefix("«data PNGf")?.strip_suffix('»')?;
+
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect()
+        })
+    } else {
+        Some(stdout)
+    };
+
+    png.ok_or(anyhow!(AssetsError::NoClipboardImage))
+}
+
+/// `file_name`, or if that's taken in `dir` the first of `name (2).ext`, `name (3).ext`, ...
+/// that isn't.
+pub fn free_file_name(dir: &Path, file_name: &str) -> String {
+    let path = Path::new(file_name);
+
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+
+    std::iter::once(file_name.to_string())
+        .chain((2..).map(|n| format!("{stem} ({n}){extension}")))
+        .find(|name| !dir.join(name).exists())
+        .unwrap()
+}
+
+/// Opens `path` with the app the system uses for its type.
+pub fn open_externally(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+
+    let mut child = command.arg(path).spawn()?;
 
-    index_file.write_all(parsed_data.as_bytes())?;
+    // reaps the process once the opener exits
+    std::thread::spawn(move || child.wait());
 
     Ok(())
 }
 
+pub fn write_index(index: &HashMap<u32, IndexEntry>, assets_folder: Option<PathBuf>) -> Result<()> {
+    let Some(assets_folder) = assets_folder else {
+        return Err(anyhow!(AssetsError::NoFolderLoaded));
+    };
+
+    let parsed_data = ron::ser::to_string_pretty(index, PrettyConfig::new())?;
+
+    save(assets_folder.join(".index.ron"), parsed_data)
+}
+
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum AssetsError {
     #[error("The dialog was closed.")]
@@ -238,4 +681,18 @@ pub enum AssetsError {
     InvalidAsset,
     #[error("Can't complete operation without any folder being loaded.")]
     NoFolderLoaded,
+    #[error("The asset doesn't exist.")]
+    AssetNotFound,
+    #[error("New projects have to be created in an empty folder.")]
+    FolderNotEmpty,
+    #[error("Another file is already in the way.")]
+    AlreadyExists,
+    #[error("Names can't be empty, and folders in them can't be empty, `.` or `..`.")]
+    InvalidName,
+    #[error("Only images can become characters.")]
+    NotAnImage,
+    #[error("The clipboard doesn't contain an image.")]
+    NoClipboardImage,
+    #[error("Reading the clipboard with {0} failed, it might not be installed.")]
+    ClipboardToolFailed(&'static str),
 }
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use iced::Point;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    Character, Node,
+    assets::{AssetHandle, AssetPath, AssetsData},
+    graph::{
+        ConnectionKind, GraphData, LinePattern, NodeId, RelativeAttachment, connections::Edge,
+        line_styles::AxisAligned,
+    },
+    relationships::{RelationshipKind, Relationships},
+};
+
+type FamilyTree = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+// The exported document looks like this:
+//
+// {
+//   "version": 1,
+//   "relationships": [
+//     { "id": 1, "label": "Adopted", "color": "#3fa36b", "pattern": "dashed" }
+//   ],
+//   "nodes": [
+//     {
+//       "id": 0,
+//       "type": "character",
+//       "x": 0.0,
+//       "y": 0.0,
+//       "name": "Anna",
+//       "birth": "1900",
+//       "death": "",
+//       "notes": "",
+//       "tags": ["main"],
+//       "image": "characters/anna.png",
+//       "attachments": ["audio/anna.wav"]
+//     },
+//     { "id": 1, "type": "family", "x": 80.0, "y": 200.0 }
+//   ],
+//   "connections": [
+//     {
+//       "id": 0,
+//       "a": { "node": 0, "attachment": { "edge": "right", "align": 0.5 } },
+//       "b": { "node": 1, "attachment": "center" },
+//       "kind": null
+//     }
+//   ]
+// }
+//
+// Ids only have to be unique within the document. `image` is a path inside the project's
+// assets folder or null, `attachments` a list of such paths. `kind` is the id of one of the
+// `relationships`, or null for family connections. `pattern` is one of "solid", "dashed" and
+// "dotted". Fields that aren't listed are ignored.
+
+/// Version of the document written by [`export`]. Documents with a higher version are rejected.
+pub const VERSION: u32 = 1;
+
+/// Colour of imported relationship kinds that don't have a valid one.
+const DEFAULT_COLOR: [u8; 3] = [0x8a, 0x8a, 0x8a];
+
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error("The document isn't valid: {0}")]
+    Invalid(serde_json::Error),
+    #[error("The document has version {0}, only versions up to {VERSION} are supported.")]
+    UnsupportedVersion(u32),
+    #[error("A connection refers to node {0}, which doesn't exist.")]
+    UnknownNode(u32),
+}
+
+/// Just the version, read before the rest so newer documents get a clear error.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    #[serde(default)]
+    relationships: Vec<Relationship>,
+    nodes: Vec<JsonNode>,
+    connections: Vec<Connection>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Relationship {
+    id: u32,
+    label: String,
+    #[serde(default)]
+    color: String,
+    #[serde(default, with = "PatternDef")]
+    pattern: LinePattern,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "LinePattern", rename_all = "lowercase")]
+enum PatternDef {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonNode {
+    id: u32,
+    x: f32,
+    y: f32,
+    #[serde(flatten)]
+    data: NodeData,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum NodeData {
+    Character {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        birth: String,
+        #[serde(default)]
+        death: String,
+        #[serde(default)]
+        notes: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        #[serde(default)]
+        image: Option<String>,
+        #[serde(default)]
+        attachments: Vec<String>,
+    },
+    Family,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Connection {
+    id: u32,
+    a: End,
+    b: End,
+    #[serde(default)]
+    kind: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct End {
+    node: u32,
+    attachment: Attachment,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum Attachment {
+    Center(Center),
+    Edge {
+        #[serde(with = "EdgeDef")]
+        edge: Edge,
+        #[serde(default = "centered")]
+        align: f32,
+    },
+}
+
+/// Written as the string `"center"`.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Center {
+    Center,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Edge", rename_all = "lowercase")]
+enum EdgeDef {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+fn centered() -> f32 {
+    0.5
+}
+
+impl From<&RelativeAttachment<AxisAligned>> for Attachment {
+    fn from(attachment: &RelativeAttachment<AxisAligned>) -> Self {
+        match attachment {
+            RelativeAttachment::Center => Self::Center(Center::Center),
+            RelativeAttachment::Edge { edge, align, .. } => Self::Edge {
+                edge: edge.clone(),
+                align: *align,
+            },
+        }
+    }
+}
+
+impl From<Attachment> for RelativeAttachment<AxisAligned> {
+    fn from(attachment: Attachment) -> Self {
+        match attachment {
+            Attachment::Center(_) => Self::Center,
+            Attachment::Edge { edge, align } => Self::edge(edge, align),
+        }
+    }
+}
+
+/// Writes the graph and the relationship kinds its connections use as a JSON document, see the
+/// top of this file for its layout.
+pub fn export(graph: &FamilyTree, assets: &AssetsData, relationships: &Relationships) -> String {
+    let relationships = relationships
+        .iter()
+        .filter(|(kind, _)| graph.connections().any(|conn| conn.kind == Some(*kind)))
+        .map(|(kind, relationship)| {
+            let [r, g, b] = relationship.color;
+
+            Relationship {
+                id: kind.0,
+                label: relationship.label.clone(),
+                color: format!("#{r:02x}{g:02x}{b:02x}"),
+                pattern: relationship.pattern,
+            }
+        })
+        .collect();
+
+    let nodes = graph
+        .iter()
+        .map(|node| {
+            let position = node.position();
+
+            let data = match node.data() {
+                Node::Character(chara) => NodeData::Character {
+                    name: chara.name.clone(),
+                    birth: chara.birth.clone(),
+                    death: chara.death.clone(),
+                    notes: chara.notes.clone(),
+                    tags: chara.tags.clone(),
+                    image: assets.path(chara.img).map(|path| path.to_string()),
+                    attachments: chara
+                        .attachments
+                        .iter()
+                        .filter_map(|handle| assets.path(*handle))
+                        .map(|path| path.to_string())
+                        .collect(),
+                },
+                Node::Family => NodeData::Family,
+            };
+
+            JsonNode {
+                id: node.id().into(),
+                x: position.x,
+                y: position.y,
+                data,
+            }
+        })
+        .collect();
+
+    let end = |(node, attachment): &(NodeId, RelativeAttachment<AxisAligned>)| End {
+        node: (*node).into(),
+        attachment: attachment.into(),
+    };
+
+    let connections = graph
+        .connections()
+        .map(|conn| Connection {
+            id: conn.id.into(),
+            a: end(&conn.a),
+            b: end(&conn.b),
+            kind: conn.kind.map(|kind| kind.0),
+        })
+        .collect();
+
+    let document = Document {
+        version: VERSION,
+        relationships,
+        nodes,
+        connections,
+    };
+
+    // the document only holds strings, numbers and lists, which can always be written
+    let mut out = serde_json::to_string_pretty(&document).unwrap_or_default();
+    out.push('\n');
+
+    out
+}
+
+/// Reads a document written by [`export`]. Relationship kinds are matched to the project's kinds
+/// by their label. Kinds the project doesn't have yet are added to a copy of `relationships`,
+/// which is returned with the graph so nothing changes unless the whole document could be read.
+pub fn import(
+    source: &str,
+    assets: &AssetsData,
+    relationships: &Relationships,
+) -> Result<(FamilyTree, Relationships)> {
+    let source = source.trim_start_matches('\u{feff}');
+
+    let header: Header = serde_json::from_str(source).map_err(JsonError::Invalid)?;
+    if header.version > VERSION {
+        return Err(JsonError::UnsupportedVersion(header.version).into());
+    }
+
+    let document: Document = serde_json::from_str(source).map_err(JsonError::Invalid)?;
+
+    let mut relationships = relationships.clone();
+    let mut kinds: HashMap<u32, ConnectionKind> = HashMap::new();
+
+    for relationship in document.relationships {
+        let existing = relationships
+            .iter()
+            .find_map(|(kind, existing)| (existing.label == relationship.label).then_some(kind));
+
+        let kind = existing.unwrap_or_else(|| {
+            let color = u32::from_str_radix(relationship.color.trim_start_matches('#'), 16)
+                .ok()
+                .map(|color| {
+                    let [_, r, g, b] = color.to_be_bytes();
+                    [r, g, b]
+                })
+                .unwrap_or(DEFAULT_COLOR);
+
+            relationships.add(RelationshipKind::new(
+                relationship.label,
+                color,
+                relationship.pattern,
+            ))
+        });
+
+        kinds.insert(relationship.id, kind);
+    }
+
+    let handle = |path: &str| {
+        AssetPath::try_from(path)
+            .ok()
+            .and_then(|path| assets.handle(path))
+    };
+
+    let mut graph = FamilyTree::default();
+    let mut ids = HashMap::new();
+
+    for node in document.nodes {
+        let data = match node.data {
+            NodeData::Family => Node::Family,
+            NodeData::Character {
+                name,
+                birth,
+                death,
+                notes,
+                tags,
+                image,
+                attachments,
+            } => {
+                let img = image
+                    .as_deref()
+                    .and_then(handle)
+                    .unwrap_or(AssetHandle::NONE);
+
+                let mut chara = Character::new(name, img);
+                chara.birth = birth;
+                chara.death = death;
+                chara.notes = notes;
+                chara.tags = tags;
+                chara.attachments = attachments.iter().filter_map(|path| handle(path)).collect();
+
+                Node::Character(chara)
+            }
+        };
+
+        ids.insert(node.id, graph.add(data, Point::new(node.x, node.y)));
+    }
+
+    for conn in document.connections {
+        let end = |end: End| -> Result<_, JsonError> {
+            let node = *ids.get(&end.node).ok_or(JsonError::UnknownNode(end.node))?;
+
+            Ok((node, end.attachment.into()))
+        };
+
+        let (a, a_attachment) = end(conn.a)?;
+        let (b, b_attachment) = end(conn.b)?;
+        let kind = conn.kind.and_then(|kind| kinds.get(&kind).copied());
+
+        let _ = graph.connect_with_kind(a, a_attachment, b, b_attachment, kind);
+    }
+
+    Ok((graph, relationships))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two partners with a child, and a rivalry between one partner and the child.
+    fn family() -> (FamilyTree, Relationships) {
+        let relationships = Relationships::default();
+        let rivals = relationships
+            .iter()
+            .find_map(|(kind, relationship)| (relationship.label == "Rivals").then_some(kind));
+
+        let mut anna = Character::new("Anna Berg", AssetHandle::NONE);
+        anna.birth = "1900".to_string();
+        anna.notes = "Wrote \"letters\",\nmostly in ink.".to_string();
+        anna.tags = vec!["main".to_string(), "ä/ö".to_string()];
+
+        let mut graph = FamilyTree::default();
+        let anna = graph.add(Node::Character(anna), Point::new(0.0, 0.0));
+        let ben = graph.add(
+            Node::Character(Character::new("Ben", AssetHandle::NONE)),
+            Point::new(160.5, -0.1),
+        );
+        let family = graph.add(Node::Family, Point::new(80.0, 200.0));
+        let carl = graph.add(
+            Node::Character(Character::new("Carl", AssetHandle::NONE)),
+            Point::new(80.0, 400.0),
+        );
+
+        let _ = graph.connect(
+            anna,
+            RelativeAttachment::bottom(),
+            family,
+            RelativeAttachment::top(),
+        );
+        let _ = graph.connect(
+            ben,
+            RelativeAttachment::bottom(),
+            family,
+            RelativeAttachment::top(),
+        );
+        let _ = graph.connect(
+            family,
+            RelativeAttachment::bottom(),
+            carl,
+            RelativeAttachment::top(),
+        );
+        let _ = graph.connect_with_kind(
+            anna,
+            RelativeAttachment::edge(Edge::Left, 0.25),
+            carl,
+            RelativeAttachment::Center,
+            rivals,
+        );
+
+        (graph, relationships)
+    }
+
+    fn characters(graph: &FamilyTree) -> Vec<&Character> {
+        graph
+            .iter()
+            .filter_map(|node| match node.data() {
+                Node::Character(chara) => Some(chara),
+                Node::Family => None,
+            })
+            .collect()
+    }
+
+    fn import_error(source: &str) -> anyhow::Error {
+        match import(source, &AssetsData::default(), &Relationships::default()) {
+            Ok(_) => panic!("{source:?} was imported"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let (graph, relationships) = family();
+        let assets = AssetsData::default();
+
+        let exported = export(&graph, &assets, &relationships);
+        let (imported, imported_relationships) =
+            import(&exported, &assets, &relationships).unwrap();
+
+        let names = |graph: &FamilyTree| {
+            characters(graph)
+                .into_iter()
+                .map(|chara| chara.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&imported), ["Anna Berg", "Ben", "Carl"]);
+
+        let anna = characters(&imported)[0];
+        assert_eq!(anna.birth, "1900");
+        assert_eq!(anna.notes, "Wrote \"letters\",\nmostly in ink.");
+        assert_eq!(anna.tags, ["main", "ä/ö"]);
+
+        let positions: Vec<_> = imported.iter().map(|node| node.position()).collect();
+        assert_eq!(positions[1], Point::new(160.5, -0.1));
+
+        assert_eq!(imported.connections().count(), 4);
+        assert_eq!(
+            imported_relationships.iter().count(),
+            relationships.iter().count()
+        );
+
+        // exporting the imported graph again gives the same document
+        assert_eq!(
+            export(&imported, &assets, &imported_relationships),
+            exported
+        );
+    }
+
+    #[test]
+    fn only_used_kinds_are_exported() {
+        let (graph, relationships) = family();
+        let exported = export(&graph, &AssetsData::default(), &relationships);
+
+        assert!(exported.contains("\"Rivals\""));
+        assert!(!exported.contains("\"Siblings\""));
+        assert!(exported.contains("\"pattern\": \"dotted\""));
+        assert!(exported.contains("\"attachment\": \"center\""));
+    }
+
+    #[test]
+    fn unknown_kinds_are_added_to_a_copy() {
+        let source = r##"{
+            "version": 1,
+            "relationships": [
+                { "id": 7, "label": "Pen pals", "color": "#102030", "pattern": "dashed" }
+            ],
+            "nodes": [
+                { "id": 3, "type": "character", "x": 0, "y": 0, "name": "Anna" },
+                { "id": 5, "type": "character", "x": 100, "y": 0, "name": "Ben" }
+            ],
+            "connections": [
+                {
+                    "id": 0,
+                    "a": { "node": 3, "attachment": { "edge": "right" } },
+                    "b": { "node": 5, "attachment": "center" },
+                    "kind": 7
+                }
+            ]
+        }"##;
+
+        let relationships = Relationships::default();
+        let (graph, imported) = import(source, &AssetsData::default(), &relationships).unwrap();
+
+        let (kind, pen_pals) = imported
+            .iter()
+            .find(|(_, relationship)| relationship.label == "Pen pals")
+            .unwrap();
+
+        assert_eq!(pen_pals.color, [0x10, 0x20, 0x30]);
+        assert_eq!(pen_pals.pattern, LinePattern::Dashed);
+        assert!(relationships.iter().all(|(_, r)| r.label != "Pen pals"));
+
+        let conn = graph.connections().next().unwrap();
+        assert_eq!(conn.kind, Some(kind));
+        assert_eq!(conn.a.1, RelativeAttachment::edge(Edge::Right, 0.5));
+    }
+
+    #[test]
+    fn missing_assets_are_left_out() {
+        let source = r#"{
+            "version": 1,
+            "nodes": [
+                {
+                    "id": 0,
+                    "type": "character",
+                    "x": 0,
+                    "y": 0,
+                    "name": "Anna",
+                    "image": "images/anna.png",
+                    "attachments": ["audio/anna.wav", "not a path"]
+                }
+            ],
+            "connections": []
+        }"#;
+
+        let (graph, _) = import(source, &AssetsData::default(), &Relationships::default()).unwrap();
+        let anna = characters(&graph)[0];
+
+        assert_eq!(anna.img, AssetHandle::NONE);
+        assert!(anna.attachments.is_empty());
+    }
+
+    #[test]
+    fn malformed_documents_are_rejected() {
+        for source in [
+            "",
+            "{",
+            "[]",
+            r#"{ "version": 1, "nodes": [], "connections": [] } trailing"#,
+            r#"{ "nodes": [], "connections": [] }"#,
+            r#"{ "version": 1, "connections": [] }"#,
+            r#"{ "version": "1", "nodes": [], "connections": [] }"#,
+            r#"{ "version": 1, "nodes": [{ "id": 0, "type": "pet", "x": 0, "y": 0 }],
+                "connections": [] }"#,
+            r#"{ "version": 1, "nodes": [{ "id": 0, "type": "family", "x": "left", "y": 0 }],
+                "connections": [] }"#,
+            r#"{ "version": 1, "nodes": [{ "id": 0, "type": "family", "x": 0, "y": 0 }],
+                "connections": [{ "id": 0, "a": { "node": 0, "attachment": { "edge": "up" } },
+                    "b": { "node": 0, "attachment": "center" } }] }"#,
+        ] {
+            let err = import_error(source);
+            assert!(
+                matches!(err.downcast_ref(), Some(JsonError::Invalid(_))),
+                "{source:?}: {err:#}"
+            );
+        }
+    }
+
+    #[test]
+    fn unknown_nodes_are_rejected() {
+        let err = import_error(
+            r#"{ "version": 1, "nodes": [{ "id": 0, "type": "family", "x": 0, "y": 0 }],
+                "connections": [{ "id": 0, "a": { "node": 0, "attachment": "center" },
+                    "b": { "node": 4, "attachment": "center" } }] }"#,
+        );
+
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(JsonError::UnknownNode(4))
+        ));
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let err = import_error(r#"{ "version": 2, "nodes": "changed", "connections": [] }"#);
+
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(JsonError::UnsupportedVersion(2))
+        ));
+    }
+
+    #[test]
+    fn deep_nesting_does_not_overflow() {
+        let nested = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+
+        // ignored fields are skipped without being read
+        let source =
+            format!(r#"{{ "version": 1, "nodes": [], "connections": [], "extra": {nested} }}"#);
+        assert!(import(&source, &AssetsData::default(), &Relationships::default()).is_ok());
+
+        // the fields of a node are buffered to find its type, which is limited in depth
+        let source = format!(
+            r#"{{ "version": 1, "connections": [],
+                "nodes": [{{ "id": 0, "type": "family", "x": 0, "y": 0, "extra": {nested} }}] }}"#
+        );
+        assert!(matches!(
+            import_error(&source).downcast_ref(),
+            Some(JsonError::Invalid(_))
+        ));
+    }
+}
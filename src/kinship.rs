@@ -0,0 +1,581 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use crate::Node;
+use crate::graph::{GraphData, GraphNode, NodeId, RelativeAttachment, line_styles::LineStyle};
+
+// Kinship follows the same conventions as `positioning_schemes::family_tree`: partners connect
+// to the top of a family node and their children to its bottom. Connections with a relationship
+// kind don't make anyone family.
+
+/// How one character is related to another, as in "a is b's ...".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kinship {
+    Same,
+    Partner,
+    /// `n` generations up, 1 being a parent.
+    Ancestor(u32),
+    /// `n` generations down, 1 being a child.
+    Descendant(u32),
+    Sibling,
+    /// 1 is an aunt or uncle, 2 a great-aunt or great-uncle and so on.
+    AuntOrUncle(u32),
+    /// 1 is a niece or nephew, 2 a great-niece or great-nephew and so on.
+    NieceOrNephew(u32),
+    Cousin {
+        degree: u32,
+        removed: u32,
+    },
+}
+
+impl Display for Kinship {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let greats = |n: u32| "great-".repeat(n as usize);
+
+        match *self {
+            Self::Same => write!(f, "self"),
+            Self::Partner => write!(f, "partner"),
+            Self::Ancestor(1) => write!(f, "parent"),
+            Self::Ancestor(n) => write!(f, "{}grandparent", greats(n - 2)),
+            Self::Descendant(1) => write!(f, "child"),
+            Self::Descendant(n) => write!(f, "{}grandchild", greats(n - 2)),
+            Self::Sibling => write!(f, "sibling"),
+            Self::AuntOrUncle(n) => write!(f, "{0}aunt or {0}uncle", greats(n - 1)),
+            Self::NieceOrNephew(n) => write!(f, "{0}niece or {0}nephew", greats(n - 1)),
+            Self::Cousin { degree, removed } => {
+                write!(f, "{} cousin", ordinal(degree))?;
+
+                match removed {
+                    0 => Ok(()),
+                    1 => write!(f, " once removed"),
+                    2 => write!(f, " twice removed"),
+                    n => write!(f, " {n} times removed"),
+                }
+            }
+        }
+    }
+}
+
+fn ordinal(n: u32) -> String {
+    const WORDS: [&str; 10] = [
+        "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth",
+        "tenth",
+    ];
+
+    match WORDS.get(n as usize - 1) {
+        Some(word) => word.to_string(),
+        None => format!("{n}th"),
+    }
+}
+
+/// Family nodes `id` is part of, along with the attachment on the family's side.
+fn families<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    id: NodeId,
+) -> impl Iterator<Item = (NodeId, &RelativeAttachment<S>)>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    data.connections()
+        .filter(|conn| conn.kind.is_none())
+        .filter_map(move |conn| {
+            let (family, attachment) = if conn.a.0 == id {
+                &conn.b
+            } else if conn.b.0 == id {
+                &conn.a
+            } else {
+                return None;
+            };
+
+            matches!(data.get(*family)?.data(), Node::Family).then_some((*family, attachment))
+        })
+}
+
+/// Characters attached to the top (partners) or bottom (children) of a family node.
+fn members<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    family: NodeId,
+    top: bool,
+) -> impl Iterator<Item = NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    data.connections()
+        .filter(|conn| conn.kind.is_none())
+        .filter_map(move |conn| {
+            let ((_, family_attachment), (member, _)) = if conn.a.0 == family {
+                (&conn.a, &conn.b)
+            } else if conn.b.0 == family {
+                (&conn.b, &conn.a)
+            } else {
+                return None;
+            };
+
+            (family_attachment.is_top() == top
+                && matches!(data.get(*member)?.data(), Node::Character(_)))
+            .then_some(*member)
+        })
+}
+
+/// Relatives reached by going from `id` to the families on one side of it and from there to the
+/// members on one side of those families.
+fn relatives<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    id: NodeId,
+    family_side_top: bool,
+    member_side_top: bool,
+) -> Vec<NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    let mut relatives = Vec::new();
+
+    for (family, attachment) in families(data, id) {
+        if attachment.is_top() != family_side_top {
+            continue;
+        }
+
+        for member in members(data, family, member_side_top) {
+            if member != id && !relatives.contains(&member) {
+                relatives.push(member);
+            }
+        }
+    }
+
+    relatives
+}
+
+pub fn parents<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> Vec<NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    relatives(data, id, false, true)
+}
+
+pub fn children<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> Vec<NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    relatives(data, id, true, false)
+}
+
+pub fn partners<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> Vec<NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    relatives(data, id, true, true)
+}
+
+pub fn siblings<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> Vec<NodeId>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    relatives(data, id, false, false)
+}
+
+/// Walks the tree breadth first and returns everyone found along with the number of generations
+/// between them and `id`, closest first. `id` itself is included with a distance of 0.
+fn walk<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    id: NodeId,
+    next: fn(&GraphData<Node, RelativeAttachment<S>>, NodeId) -> Vec<NodeId>,
+) -> Vec<(NodeId, u32)>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    let mut found = vec![(id, 0)];
+    let mut queue = VecDeque::from([(id, 0)]);
+
+    while let Some((current, distance)) = queue.pop_front() {
+        for relative in next(data, current) {
+            // trees drawn by hand can contain loops, only visit everyone once
+            if found.iter().all(|(id, _)| *id != relative) {
+                found.push((relative, distance + 1));
+                queue.push_back((relative, distance + 1));
+            }
+        }
+    }
+
+    found
+}
+
+/// Everyone and every family `id` descends from, with the number of generations between them.
+/// Families count as ancestors as high up as their partners, so the children of a family without
+/// known parents still have one in common. `id` itself is included with a distance of 0.
+fn lineage<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> HashMap<NodeId, u32>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    let mut found = HashMap::from([(id, 0)]);
+    let mut queue = VecDeque::from([(id, 0)]);
+
+    while let Some((current, distance)) = queue.pop_front() {
+        // a shorter way to it was found after it got queued
+        if found[&current] < distance {
+            continue;
+        }
+
+        if let Some(Node::Family) = data.get(current).map(GraphNode::data) {
+            // partners are no generation further than their family, so they're looked at first
+            for parent in members(data, current, true) {
+                if found.get(&parent).is_none_or(|found| distance < *found) {
+                    found.insert(parent, distance);
+                    queue.push_front((parent, distance));
+                }
+            }
+        } else {
+            for (family, attachment) in families(data, current) {
+                if !attachment.is_top()
+                    && found.get(&family).is_none_or(|found| distance + 1 < *found)
+                {
+                    found.insert(family, distance + 1);
+                    queue.push_back((family, distance + 1));
+                }
+            }
+        }
+    }
+
+    found
+}
+
+/// Everyone `id` descends from with the number of generations between them, closest first.
+pub fn ancestors<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> Vec<(NodeId, u32)>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    walk(data, id, parents).split_off(1)
+}
+
+/// Everyone descending from `id` with the number of generations between them, closest first.
+pub fn descendants<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    id: NodeId,
+) -> Vec<(NodeId, u32)>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    walk(data, id, children).split_off(1)
+}
+
+/// Number of known generations above `id`, 0 if none of their parents are in the tree.
+pub fn generation<S>(data: &GraphData<Node, RelativeAttachment<S>>, id: NodeId) -> u32
+where
+    S: LineStyle + PartialEq + Send,
+{
+    ancestors(data, id)
+        .into_iter()
+        .map(|(_, distance)| distance)
+        .max()
+        .unwrap_or(0)
+}
+
+/// How `a` is related to `b` through their closest common ancestor, or as partners if they
+/// aren't related by blood. `None` if they aren't family at all.
+pub fn relationship_between<S>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    a: NodeId,
+    b: NodeId,
+) -> Option<Kinship>
+where
+    S: LineStyle + PartialEq + Send,
+{
+    let a_ancestors = lineage(data, a);
+
+    let closest = lineage(data, b)
+        .into_iter()
+        .filter_map(|(ancestor, b_distance)| {
+            a_ancestors
+                .get(&ancestor)
+                .map(|a_distance| (*a_distance, b_distance))
+        })
+        .min_by_key(|(a_distance, b_distance)| a_distance + b_distance);
+
+    let Some((up, down)) = closest else {
+        return partners(data, a).contains(&b).then_some(Kinship::Partner);
+    };
+
+    Some(match (up, down) {
+        (0, 0) => Kinship::Same,
+        (0, down) => Kinship::Ancestor(down),
+        (up, 0) => Kinship::Descendant(up),
+        (1, 1) => Kinship::Sibling,
+        (1, down) => Kinship::AuntOrUncle(down - 1),
+        (up, 1) => Kinship::NieceOrNephew(up - 1),
+        (up, down) => Kinship::Cousin {
+            degree: up.min(down) - 1,
+            removed: up.abs_diff(down),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::Point;
+
+    use super::*;
+    use crate::{Character, assets::AssetHandle, graph::line_styles::AxisAligned};
+
+    type Graph = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+    fn character(graph: &mut Graph) -> NodeId {
+        graph.add(
+            Node::Character(Character::new("", AssetHandle::NONE)),
+            Point::ORIGIN,
+        )
+    }
+
+    /// A family node with `parents` on top and `children` below.
+    fn family(graph: &mut Graph, parents: &[NodeId], children: &[NodeId]) -> NodeId {
+        let family = graph.add(Node::Family, Point::ORIGIN);
+
+        for parent in parents {
+            graph
+                .connect(
+                    *parent,
+                    RelativeAttachment::bottom(),
+                    family,
+                    RelativeAttachment::top(),
+                )
+                .unwrap();
+        }
+
+        for child in children {
+            graph
+                .connect(
+                    family,
+                    RelativeAttachment::bottom(),
+                    *child,
+                    RelativeAttachment::top(),
+                )
+                .unwrap();
+        }
+
+        family
+    }
+
+    /// Four generations: a couple, their two children, one grandchild each and a
+    /// great-grandchild below one of those.
+    struct Tree {
+        graph: Graph,
+        grandpa: NodeId,
+        grandma: NodeId,
+        son: NodeId,
+        daughter: NodeId,
+        grandson: NodeId,
+        granddaughter: NodeId,
+        great_grandson: NodeId,
+    }
+
+    fn tree() -> Tree {
+        let mut graph = Graph::default();
+
+        let [
+            grandpa,
+            grandma,
+            son,
+            daughter,
+            grandson,
+            granddaughter,
+            great_grandson,
+        ] = std::array::from_fn(|_| character(&mut graph));
+
+        family(&mut graph, &[grandpa, grandma], &[son, daughter]);
+        family(&mut graph, &[son], &[grandson]);
+        family(&mut graph, &[daughter], &[granddaughter]);
+        family(&mut graph, &[granddaughter], &[great_grandson]);
+
+        Tree {
+            graph,
+            grandpa,
+            grandma,
+            son,
+            daughter,
+            grandson,
+            granddaughter,
+            great_grandson,
+        }
+    }
+
+    #[test]
+    fn ancestors_closest_first() {
+        let tree = tree();
+
+        let ancestors = ancestors(&tree.graph, tree.great_grandson);
+
+        assert_eq!(
+            ancestors[..2],
+            [(tree.granddaughter, 1), (tree.daughter, 2)]
+        );
+        assert_eq!(ancestors.len(), 4);
+        assert!(ancestors.contains(&(tree.grandpa, 3)));
+        assert!(ancestors.contains(&(tree.grandma, 3)));
+        assert!(super::ancestors(&tree.graph, tree.grandpa).is_empty());
+    }
+
+    #[test]
+    fn descendants_closest_first() {
+        let tree = tree();
+
+        let descendants = descendants(&tree.graph, tree.daughter);
+
+        assert_eq!(
+            descendants,
+            [(tree.granddaughter, 1), (tree.great_grandson, 2)]
+        );
+        assert_eq!(super::descendants(&tree.graph, tree.grandpa).len(), 5);
+    }
+
+    #[test]
+    fn siblings_and_partners() {
+        let tree = tree();
+
+        assert_eq!(siblings(&tree.graph, tree.son), [tree.daughter]);
+        assert_eq!(partners(&tree.graph, tree.grandpa), [tree.grandma]);
+        assert!(siblings(&tree.graph, tree.grandson).is_empty());
+    }
+
+    #[test]
+    fn generation_counts_known_ancestors() {
+        let tree = tree();
+
+        assert_eq!(generation(&tree.graph, tree.grandpa), 0);
+        assert_eq!(generation(&tree.graph, tree.son), 1);
+        assert_eq!(generation(&tree.graph, tree.great_grandson), 3);
+    }
+
+    #[test]
+    fn relationships_through_common_ancestors() {
+        let tree = tree();
+        let between = |a, b| relationship_between(&tree.graph, a, b);
+
+        assert_eq!(between(tree.son, tree.son), Some(Kinship::Same));
+        assert_eq!(between(tree.grandpa, tree.grandma), Some(Kinship::Partner));
+        assert_eq!(
+            between(tree.grandpa, tree.grandson),
+            Some(Kinship::Ancestor(2))
+        );
+        assert_eq!(
+            between(tree.great_grandson, tree.grandma),
+            Some(Kinship::Descendant(3))
+        );
+        assert_eq!(between(tree.son, tree.daughter), Some(Kinship::Sibling));
+        assert_eq!(
+            between(tree.son, tree.granddaughter),
+            Some(Kinship::AuntOrUncle(1))
+        );
+        assert_eq!(
+            between(tree.son, tree.great_grandson),
+            Some(Kinship::AuntOrUncle(2))
+        );
+        assert_eq!(
+            between(tree.granddaughter, tree.son),
+            Some(Kinship::NieceOrNephew(1))
+        );
+        assert_eq!(
+            between(tree.grandson, tree.granddaughter),
+            Some(Kinship::Cousin {
+                degree: 1,
+                removed: 0
+            })
+        );
+        assert_eq!(
+            between(tree.grandson, tree.great_grandson),
+            Some(Kinship::Cousin {
+                degree: 1,
+                removed: 1
+            })
+        );
+    }
+
+    #[test]
+    fn second_cousin_once_removed() {
+        let mut tree = tree();
+
+        // a great-grandchild and a great-great-grandchild of the couple, through different
+        // children
+        let [a, b] = std::array::from_fn(|_| character(&mut tree.graph));
+        family(&mut tree.graph, &[tree.grandson], &[a]);
+        family(&mut tree.graph, &[tree.great_grandson], &[b]);
+
+        let kinship = relationship_between(&tree.graph, a, b);
+
+        assert_eq!(
+            kinship,
+            Some(Kinship::Cousin {
+                degree: 2,
+                removed: 1
+            })
+        );
+        assert_eq!(kinship.unwrap().to_string(), "second cousin once removed");
+    }
+
+    #[test]
+    fn half_siblings() {
+        let mut graph = Graph::default();
+
+        let [
+            mother,
+            first_father,
+            second_father,
+            first_child,
+            second_child,
+        ] = std::array::from_fn(|_| character(&mut graph));
+
+        family(&mut graph, &[mother, first_father], &[first_child]);
+        family(&mut graph, &[mother, second_father], &[second_child]);
+
+        assert!(siblings(&graph, first_child).is_empty());
+        assert_eq!(
+            relationship_between(&graph, first_child, second_child),
+            Some(Kinship::Sibling)
+        );
+        assert_eq!(
+            relationship_between(&graph, second_father, first_child),
+            None
+        );
+    }
+
+    #[test]
+    fn children_of_a_family_without_parents() {
+        let mut graph = Graph::default();
+
+        let [a, b, a_child, b_child] = std::array::from_fn(|_| character(&mut graph));
+
+        family(&mut graph, &[], &[a, b]);
+        family(&mut graph, &[a], &[a_child]);
+        family(&mut graph, &[b], &[b_child]);
+
+        assert_eq!(siblings(&graph, a), [b]);
+        assert_eq!(relationship_between(&graph, a, b), Some(Kinship::Sibling));
+        assert_eq!(
+            relationship_between(&graph, a, b_child),
+            Some(Kinship::AuntOrUncle(1))
+        );
+        assert_eq!(
+            relationship_between(&graph, a_child, b_child),
+            Some(Kinship::Cousin {
+                degree: 1,
+                removed: 0
+            })
+        );
+    }
+
+    #[test]
+    fn typed_connections_dont_make_family() {
+        let mut graph = Graph::default();
+
+        let [a, b] = std::array::from_fn(|_| character(&mut graph));
+
+        graph
+            .connect_with_kind(
+                a,
+                RelativeAttachment::right(),
+                b,
+                RelativeAttachment::left(),
+                Some(crate::graph::ConnectionKind(0)),
+            )
+            .unwrap();
+
+        assert_eq!(relationship_between(&graph, a, b), None);
+    }
+}
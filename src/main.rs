@@ -1,24 +1,44 @@
 mod assets;
+mod config;
+mod dot;
+mod export;
+mod gedcom;
+mod history;
+mod inspector;
 mod io;
+mod json;
+mod kinship;
 mod notification;
 mod positioning_schemes;
+mod project;
+mod relationships;
 mod style;
 mod widgets;
 
-use crate::assets::{Asset, AssetHandle, AssetKind};
+use crate::assets::editor::{EditorMessage, ImageEditor};
+use crate::assets::{Asset, AssetHandle, AssetKind, AssetPath, FileEvent};
+use crate::config::Config;
+use crate::export::ImageFormat;
+use crate::history::{Command, History};
+use crate::inspector::{Inspector, InspectorMessage};
 use crate::notification::Notification;
+use crate::relationships::Relationships;
 use crate::widgets::dialog::{Dialog, DialogOption};
-use crate::widgets::dnd::{dnd_indicator, dnd_receiver};
+use crate::widgets::dnd::{dnd_indicator, dnd_receiver, file_drop_receiver};
 use graph::connections::Edge;
-use graph::{GraphEvent, RelativeAttachment, line_styles};
+use graph::{
+    ConnectionId, ConnectionKind, GraphEvent, GraphNode, NodeId, RelativeAttachment, Subgraph,
+    line_styles,
+};
 use iced::Length::Shrink;
 use iced::keyboard::key::Named;
-use ron::ser::PrettyConfig;
 use widgets::*;
 
 use anyhow::anyhow;
 use iced::keyboard::{Key, Modifiers};
-use iced::widget::{horizontal_space, row, scrollable, slider, stack, vertical_space};
+use iced::widget::{
+    horizontal_space, progress_bar, row, scrollable, slider, stack, vertical_space,
+};
 use iced::{
     Alignment, Element, Font,
     Length::Fill,
@@ -37,13 +57,15 @@ use std::time::Duration;
 use crate::{
     assets::{AssetsData, AssetsMessage},
     graph::GraphData,
-    io::{AssetsError, pick_file, pick_folder},
+    io::{AssetsError, pick_file, pick_folder, pick_gedcom_file, pick_json_file, pick_save_file},
 };
 
 fn main() -> iced::Result {
-    iced::application("Hello", update, view)
+    iced::application(title, update, view)
         .window(window::Settings {
             min_size: Some(Size::new(650.0, 500.0)),
+            // closing with unsaved changes asks first, see Message::CloseRequested
+            exit_on_close_request: false,
             ..Default::default()
         })
         .settings(Settings {
@@ -51,27 +73,56 @@ fn main() -> iced::Result {
             ..Default::default()
         })
         .subscription(subscription)
-        .theme(|_| Theme::TokyoNight)
+        .theme(theme)
         .run_with(|| {
+            let (config, notifications) = match Config::load() {
+                Ok(config) => (config, Vec::new()),
+                Err(err) => (
+                    Config::default(),
+                    vec![Notification::error(
+                        "Failed to load settings",
+                        format!("Failed to load settings: {err}"),
+                    )],
+                ),
+            };
+
+            let reopen = match config.last_project() {
+                Some(folder) if folder.is_dir() => Task::done(Message::Load(folder.clone())),
+                _ => Task::none(),
+            };
+
             (
                 State {
                     nodes: GraphData::default(),
+                    history: History::default(),
+                    inspector: Inspector::default(),
+                    relationships: Relationships::default(),
                     assets: AssetsData::default(),
                     panes: pane_grid::State::with_configuration(Configuration::Split {
                         axis: pane_grid::Axis::Vertical,
                         ratio: 0.25,
                         a: Box::new(Configuration::Pane(Pane::Assets)),
-                        b: Box::new(Configuration::Pane(Pane::Graph)),
+                        b: Box::new(Configuration::Split {
+                            axis: pane_grid::Axis::Vertical,
+                            ratio: 0.7,
+                            a: Box::new(Configuration::Pane(Pane::Graph)),
+                            b: Box::new(Configuration::Pane(Pane::Inspector)),
+                        }),
                     }),
                     focus: None,
                     graph_position: Vector::ZERO,
                     graph_zoom: 1.0,
-                    notifications: Vec::new(),
+                    highlighted_path: None,
+                    notifications,
                     dnd_payload: None,
                     dialog: None,
+                    image_editor: None,
                     last_error: None,
+                    dirty: false,
+                    config,
+                    metadata: project::Metadata::default(),
                 },
-                Task::none(),
+                reopen,
             )
         })
 }
@@ -85,6 +136,43 @@ enum Draggable {
 struct Character {
     name: String,
     img: AssetHandle,
+    #[serde(default)]
+    birth: String,
+    #[serde(default)]
+    death: String,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    /// Recordings, letters, clips and other assets that aren't the character's image.
+    #[serde(default)]
+    attachments: Vec<AssetHandle>,
+}
+
+impl Character {
+    fn new(name: impl Into<String>, img: AssetHandle) -> Self {
+        Self {
+            name: name.into(),
+            img,
+            birth: String::new(),
+            death: String::new(),
+            notes: String::new(),
+            tags: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Whether `handle` is the character's image or one of its attachments.
+    fn uses(&self, handle: AssetHandle) -> bool {
+        self.img == handle || self.attachments.contains(&handle)
+    }
+
+    /// The character's image, if it has one, and its attachments.
+    fn assets(&self) -> impl Iterator<Item = AssetHandle> + '_ {
+        std::iter::once(self.img)
+            .filter(|img| *img != AssetHandle::NONE)
+            .chain(self.attachments.iter().copied())
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -93,17 +181,37 @@ enum Node {
     Family,
 }
 
+/// What gets put on the clipboard when copying nodes. Asset handles are only meaningful inside
+/// one project, so the paths they pointed to are copied along to find them again on paste.
+#[derive(Debug, Serialize, Deserialize)]
+struct CopiedNodes {
+    graph: Subgraph<Node, RelativeAttachment<line_styles::AxisAligned>>,
+    assets: Vec<(AssetHandle, AssetPath)>,
+    /// Relationship kinds are per project too, they're found again by their label.
+    #[serde(default)]
+    kinds: Vec<(ConnectionKind, String)>,
+}
+
 struct State {
     nodes: GraphData<Node, RelativeAttachment<line_styles::AxisAligned>>,
+    history: History<Node, RelativeAttachment<line_styles::AxisAligned>>,
+    inspector: Inspector,
+    relationships: Relationships,
     assets: assets::AssetsData,
     panes: pane_grid::State<Pane>,
     focus: Option<pane_grid::Pane>,
     graph_position: Vector,
     graph_zoom: f32,
+    highlighted_path: Option<(NodeId, NodeId)>,
     notifications: Vec<Notification>,
     dnd_payload: Option<Draggable>,
     dialog: Option<Dialog<Message>>,
+    image_editor: Option<ImageEditor>,
     last_error: Option<anyhow::Error>,
+    /// Whether anything changed since the project was last saved or loaded.
+    dirty: bool,
+    config: Config,
+    metadata: project::Metadata,
 }
 
 #[derive(Default, PartialEq)]
@@ -111,6 +219,7 @@ enum Pane {
     #[default]
     Assets,
     Graph,
+    Inspector,
 }
 
 #[allow(clippy::enum_variant_names)]
@@ -119,7 +228,12 @@ enum Message {
     AssetsMessage(AssetsMessage),
     AddCharacter(Character, Point),
     MenuButtonPressed,
+    OpenNewProjectDialog,
+    PickNewProjectFolder,
     OpenLoadFolderDialog,
+    PickLoadFolder,
+    OpenRecentProject(PathBuf),
+    ToggleReopenLastProject,
     ConfirmLoadPath(PathBuf),
     Load(PathBuf),
     LoadData(PathBuf),
@@ -128,23 +242,109 @@ enum Message {
     OpenFolderFailed,
     OpenAddAssetDialog,
     AddExternalAsset(PathBuf),
-    AddAsset(String, Asset),
+    ImportExternalAsset(PathBuf, AssetKind),
+    CopyExternalAsset(PathBuf, NameClash),
+    /// Closes the dialog asking how to import the file, which is deleted if it was only written
+    /// to be imported.
+    CancelImport(PathBuf),
     AddAssetFailed,
+    TrashAsset(AssetHandle),
+    TrashAssetFailed,
     Save,
     Saved,
+    Autosave,
+    OpenAutosaveDialog,
+    SetAutosave(Option<Duration>),
+    OpenRestoreBackupDialog,
+    RestoreBackup(PathBuf),
     SaveFailed,
+    ImportGedcom,
+    ExportGedcom,
+    ImportJson,
+    ExportJson,
+    ExportDot,
+    OpenExportImageDialog,
+    ExportImage(ImageFormat),
+    Exported(PathBuf),
+    ImportFailed,
+    ExportFailed,
     PaneClicked(pane_grid::Pane),
     PaneDragged(pane_grid::DragEvent),
     PaneResized(pane_grid::ResizeEvent),
     GraphEvent(GraphEvent<RelativeAttachment<line_styles::AxisAligned>>),
-    TraverseGraph,
-    CharacterButtonPressed,
+    HighlightPath,
+    ClearHighlight,
+    Inspector(InspectorMessage),
     DismissNotification(usize),
     Tick,
     SetDragPayload(Option<Draggable>),
     DropAssetOnGraph(AssetHandle, Point),
+    /// A file from outside the app was dropped on the graph, at a point relative to the pane.
+    DropFileOnGraph(PathBuf, Point),
+    /// Imports the image on the clipboard, with a character using it at the point on the graph
+    /// if there is one. A `quiet` paste doesn't complain about a clipboard without an image, it's
+    /// tried whenever something is pasted.
+    PasteImage {
+        position: Option<Point>,
+        quiet: bool,
+    },
+    /// The image on the clipboard was saved to the file, to be imported from there.
+    ImagePasted(PathBuf, Option<Point>),
+    PasteImageFailed(String),
+    /// An asset was dropped on the inspector.
+    AttachAsset(AssetHandle),
+    ImageEditor(EditorMessage),
+    EditImageFailed,
+    ConnectAs {
+        a: NodeId,
+        a_attachment: RelativeAttachment<line_styles::AxisAligned>,
+        b: NodeId,
+        b_attachment: RelativeAttachment<line_styles::AxisAligned>,
+        kind: Option<ConnectionKind>,
+    },
     CloseDialog,
+    DiscardChanges(Box<Message>),
+    SaveAndContinue(Box<Message>),
+    CloseRequested(window::Id),
+    CloseWindow(window::Id),
     EscapePressed,
+    Undo,
+    Redo,
+    Paste,
+}
+
+/// What to do when an imported file has the name of one that's already in the project.
+#[derive(Debug, Clone, Copy)]
+enum NameClash {
+    /// Overwrite the file, characters using it show the new one.
+    Replace,
+    /// Import under a free name like `name (2).png`.
+    KeepBoth,
+    /// Like `KeepBoth`, then start renaming the import.
+    Rename,
+}
+
+fn title(state: &State) -> String {
+    let folder_name = state
+        .assets
+        .folder()
+        .and_then(|folder| folder.file_name())
+        .map(|name| name.to_string_lossy().to_string());
+
+    let project = Some(state.metadata.title.clone())
+        .filter(|title| !title.is_empty())
+        .or(folder_name)
+        .unwrap_or_else(|| "Untitled".to_string());
+
+    if state.dirty {
+        format!("{project} (unsaved) - Hello")
+    } else {
+        format!("{project} - Hello")
+    }
+}
+
+fn theme(_state: &State) -> Theme {
+    Theme::TokyoNight
 }
 
 fn view(state: &State) -> Element<'_, Message> {
@@ -153,9 +353,32 @@ fn view(state: &State) -> Element<'_, Message> {
         (
             menu_button("File", Message::MenuButtonPressed),
             menu!(
+                (menu_item_button("New Project", Some("CTRL+N")).on_press(Message::OpenNewProjectDialog))
                 (menu_item_button("Open Folder", Some("CTRL+O")).on_press(Message::OpenLoadFolderDialog))
-                (menu_item_button("Add Image", None).on_press(Message::OpenAddAssetDialog))
+                (menu_item_button("Open Recent", Some(">")).on_press(Message::MenuButtonPressed), recent_projects_menu(state))
+                (menu_item_button("Add Asset", None).on_press(Message::OpenAddAssetDialog))
                 (menu_item_button("Save", Some("CTRL+S")).on_press(Message::Save))
+                (menu_item_button("Autosave", None).on_press(Message::OpenAutosaveDialog))
+                (menu_item_button("Restore Backup", None).on_press(Message::OpenRestoreBackupDialog))
+                (menu_item_button("Import GEDCOM", None).on_press(Message::ImportGedcom))
+                (menu_item_button("Export GEDCOM", None).on_press(Message::ExportGedcom))
+                (menu_item_button("Import JSON", None).on_press(Message::ImportJson))
+                (menu_item_button("Export JSON", None).on_press(Message::ExportJson))
+                (menu_item_button("Export DOT", None).on_press(Message::ExportDot))
+                (menu_item_button("Export Image", None).on_press(Message::OpenExportImageDialog))
+            )
+            .width(200.0)
+            .spacing(2.0)
+        )
+        (
+            menu_button("Edit", Message::MenuButtonPressed),
+            menu!(
+                (menu_item_button("Undo", Some("CTRL+Z")).on_press_maybe(state.history.can_undo().then_some(Message::Undo)))
+                (menu_item_button("Redo", Some("CTRL+SHIFT+Z")).on_press_maybe(state.history.can_redo().then_some(Message::Redo)))
+                (menu_item_button("Cut", Some("CTRL+X")).on_press(Message::GraphEvent(GraphEvent::Cut)))
+                (menu_item_button("Copy", Some("CTRL+C")).on_press(Message::GraphEvent(GraphEvent::Copy)))
+                (menu_item_button("Paste", Some("CTRL+V")).on_press(Message::Paste))
+                (menu_item_button("Paste Image", None).on_press(Message::PasteImage { position: None, quiet: false }))
             )
             .width(200.0)
             .spacing(2.0)
@@ -164,6 +387,8 @@ fn view(state: &State) -> Element<'_, Message> {
             menu_button("Graph", Message::MenuButtonPressed),
             menu!(
                 (menu_item_button("Select All", Some("CTRL+A")).on_press(Message::GraphEvent(GraphEvent::SelectAll)))
+                (menu_item_button("Highlight Path", Some("CTRL+H")).on_press(Message::HighlightPath))
+                (menu_item_button("Clear Highlight", Some("ESC")).on_press_maybe(state.highlighted_path.is_some().then_some(Message::ClearHighlight)))
             )
             .width(200.0)
             .spacing(2.0)
@@ -176,23 +401,44 @@ fn view(state: &State) -> Element<'_, Message> {
         let mut title_bar_font = Font::DEFAULT;
         title_bar_font.weight = Weight::Semibold;
 
-        let title_bar = pane_grid::TitleBar::new(
-            container(
-                text(match pane {
-                    Pane::Assets => "Assets",
-                    Pane::Graph => "Graph",
-                })
-                .size(14.0)
-                .font(title_bar_font),
+        let loading = match pane {
+            Pane::Assets => state.assets.loading_progress(),
+            _ => None,
+        };
+
+        let title_bar =
+            pane_grid::TitleBar::new(
+                container(
+                    row![
+                        text(match pane {
+                            Pane::Assets => "Assets",
+                            Pane::Graph => "Graph",
+                            Pane::Inspector => "Inspector",
+                        })
+                        .size(14.0)
+                        .font(title_bar_font),
+                        horizontal_space(),
+                    ]
+                    .push_maybe(loading.map(|(loaded, total)| {
+                        text(format!("Loading {loaded}/{total}")).size(12.0)
+                    }))
+                    .push_maybe(loading.map(|(loaded, total)| {
+                        progress_bar(0.0..=total as f32, loaded as f32)
+                            .width(80.0)
+                            .height(6.0)
+                            .style(style::loading_progress_bar)
+                    }))
+                    .spacing(6.0)
+                    .align_y(Alignment::Center),
+                )
+                .padding(Padding::new(2.0).left(8.0).right(8.0))
+                .align_y(Alignment::Center),
             )
-            .padding(Padding::new(2.0).left(8.0).right(8.0))
-            .align_y(Alignment::Center),
-        )
-        .style(if is_focused {
-            style::title_bar_focused
-        } else {
-            style::title_bar_active
-        });
+            .style(if is_focused {
+                style::title_bar_focused
+            } else {
+                style::title_bar_active
+            });
 
         let mut content = pane_grid::Content::new(match pane {
             Pane::Graph => {
@@ -200,6 +446,8 @@ fn view(state: &State) -> Element<'_, Message> {
                     .position(state.graph_position)
                     .zoom(state.graph_zoom)
                     .on_event(Message::GraphEvent)
+                    .connection_style(|connection| state.relationships.style(connection.kind))
+                    .highlight_path(state.highlighted_path)
                     .position_nodes(positioning_schemes::family_tree)
                     .per_node_attachments(|node| {
                         match node {
@@ -230,6 +478,8 @@ fn view(state: &State) -> Element<'_, Message> {
                             ))
                             .size(14.0),
                             horizontal_space(),
+                            text(path_chain(state).unwrap_or_default()).size(14.0),
+                            horizontal_space(),
                             slider(0.5..=2.0, state.graph_zoom, |new_zoom| {
                                 Message::GraphEvent(GraphEvent::Zoom(new_zoom))
                             })
@@ -253,12 +503,16 @@ fn view(state: &State) -> Element<'_, Message> {
                 .style(style::info_bar_border)
                 .padding(Padding::new(2.0).bottom(1.0));
 
-                Element::from(dnd_receiver(
+                let graph_pane = dnd_receiver(
                     |payload, relative_cursor_pos| match payload {
-                        Draggable::Asset(handle) => match state.assets[handle] {
+                        // the asset might have been removed from disk while it was dragged
+                        Draggable::Asset(handle) => match state.assets.get(handle)? {
                             Asset::Image(_) => {
                                 Some(Message::DropAssetOnGraph(handle, relative_cursor_pos))
                             }
+                            // only images make characters, the rest gets attached in the
+                            // inspector
+                            Asset::Audio(_) | Asset::Video(_) | Asset::Document(_) => None,
                         },
                     },
                     state.dnd_payload.clone(),
@@ -266,11 +520,33 @@ fn view(state: &State) -> Element<'_, Message> {
                         container(graph).padding(2.0).center_x(Fill).center_y(Fill),
                         column![vertical_space(), info_bar,].padding(4.0)
                     ],
-                ))
+                );
+
+                file_drop_receiver(
+                    |path, relative_cursor_pos| {
+                        Some(Message::DropFileOnGraph(path, relative_cursor_pos))
+                    },
+                    graph_pane,
+                )
             }
-            Pane::Assets => container(assets::view(&state.assets).map(Message::AssetsMessage))
-                .padding(Padding::new(5.0))
-                .into(),
+            Pane::Assets => file_drop_receiver(
+                |path, _| Some(Message::AddExternalAsset(path)),
+                container(
+                    assets::view(&state.assets, &asset_usage(state)).map(Message::AssetsMessage),
+                )
+                .padding(Padding::new(5.0)),
+            ),
+            Pane::Inspector => dnd_receiver(
+                |payload, _| match payload {
+                    Draggable::Asset(handle) => state
+                        .inspector
+                        .accepts(&state.nodes)
+                        .then_some(Message::AttachAsset(handle)),
+                },
+                state.dnd_payload.clone(),
+                container(inspector::view(state).map(Message::Inspector))
+                    .padding(Padding::new(5.0)),
+            ),
         })
         .style(match pane {
             Pane::Graph => |_: &Theme| container::Style::default(),
@@ -318,6 +594,16 @@ fn view(state: &State) -> Element<'_, Message> {
         )
     ];
 
+    // the image editor covers the panes, under the notifications which can be about it
+    let overlays = stack![]
+        .push_maybe(
+            state
+                .image_editor
+                .as_ref()
+                .map(|editor| editor.view().map(Message::ImageEditor)),
+        )
+        .push(notifications);
+
     widgets::dialog(
         &state.dialog,
         column![
@@ -325,22 +611,74 @@ fn view(state: &State) -> Element<'_, Message> {
             stack![
                 dnd_indicator(
                     state.dnd_payload.clone().map(|draggable| match draggable {
-                        Draggable::Asset(handle) => match &state.assets[handle] {
-                            Asset::Image(img) =>
-                                container(image(img.handle.clone()).width(50.0).opacity(0.5))
-                                    .width(50.0)
-                                    .into(),
-                        },
+                        Draggable::Asset(handle) =>
+                            match (state.assets.get(handle), state.assets.path(handle)) {
+                                (Some(Asset::Image(img)), _) =>
+                                    container(image(img.thumbnail.clone()).width(50.0).opacity(0.5))
+                                        .width(50.0)
+                                        .into(),
+                                (Some(asset), Some(path)) =>
+                                    container(assets::preview(asset, path, 50.0))
+                                        .width(50.0)
+                                        .into(),
+                                _ => horizontal_space().into(),
+                            },
                     }),
                     container(grid)
                 ),
-                notifications
+                overlays
             ]
         ],
     )
 }
 
+fn recent_projects_menu(state: &State) -> menu::Menu<'_, Message, Theme, iced::Renderer> {
+    let recent = state.config.recent_projects.iter().map(|folder| {
+        Item::new(
+            menu_item_button(folder.to_string_lossy(), None)
+                .on_press(Message::OpenRecentProject(folder.clone())),
+        )
+    });
+
+    let placeholder = state
+        .config
+        .recent_projects
+        .is_empty()
+        .then(|| Item::new(menu_item_button("No recent projects", None)));
+
+    let reopen = Item::new(
+        menu_item_button(
+            "Reopen Last Project on Start",
+            Some(if state.config.reopen_last_project {
+                "On"
+            } else {
+                "Off"
+            }),
+        )
+        .on_press(Message::ToggleReopenLastProject),
+    );
+
+    menu::Menu::new(recent.chain(placeholder).chain([reopen]).collect())
+        .width(350.0)
+        .spacing(2.0)
+        .offset(5.0)
+}
+
 fn update(state: &mut State, message: Message) -> Task<Message> {
+    let revision = state.history.revision();
+
+    let task = handle_message(state, message);
+
+    if state.history.revision() != revision {
+        state.dirty = true;
+    }
+
+    state.inspector.sync(&state.nodes);
+
+    task
+}
+
+fn handle_message(state: &mut State, message: Message) -> Task<Message> {
     match message {
         Message::AssetsMessage(assets_message) => match assets_message {
             AssetsMessage::OpenAsset(handle) => {
@@ -349,8 +687,8 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                 };
                 match asset {
                     Asset::Image(_) => Task::done(Message::AddCharacter(
-                        Character {
-                            name: state
+                        Character::new(
+                            state
                                 .assets
                                 .path(handle)
                                 .unwrap()
@@ -359,10 +697,25 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                                 .next()
                                 .unwrap()
                                 .to_string(),
-                            img: handle,
-                        },
+                            handle,
+                        ),
                         Point::ORIGIN + state.graph_position,
                     )),
+                    Asset::Audio(_) | Asset::Video(_) | Asset::Document(_) => {
+                        let opened =
+                            state.assets.folder().zip(state.assets.path(handle)).map(
+                                |(folder, path)| io::open_externally(&(folder + path.clone())),
+                            );
+
+                        if let Some(Err(err)) = opened {
+                            state.notifications.push(Notification::error(
+                                "Failed to open asset",
+                                format!("Failed to open asset: {err:#}"),
+                            ));
+                        }
+
+                        Task::none()
+                    }
                 }
             }
             AssetsMessage::LoadAssets(path) => {
@@ -370,31 +723,227 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                 assets::update(&mut state.assets, AssetsMessage::LoadAssets(path.clone()))
                     .map(Message::AssetsMessage)
             }
-            AssetsMessage::LoadCompleted(path, data) => assets::update(
-                &mut state.assets,
-                AssetsMessage::LoadCompleted(path.clone(), data),
-            )
-            .map(Message::AssetsMessage)
-            .chain(Task::done(Message::LoadData(path))),
+            // the graph only needs the index, the images fill in as they arrive
+            AssetsMessage::LoadStarted(path) => Task::done(Message::LoadData(path)),
             AssetsMessage::SetPayload(payload) => Task::done(Message::SetDragPayload(payload)),
-            AssetsMessage::RenameAssetFailed(_) => {
-                if let Some(err) = state.assets.last_error() {
+            AssetsMessage::AssetRenamed(handle, from, to) => {
+                state
+                    .history
+                    .record(Command::RenameAsset { handle, from, to });
+
+                Task::none()
+            }
+            AssetsMessage::FileChanged(FileEvent::Removed(path)) => {
+                let handle = state.assets.handle(path.clone());
+
+                let task = assets::update(
+                    &mut state.assets,
+                    AssetsMessage::FileChanged(FileEvent::Removed(path.clone())),
+                )
+                .map(Message::AssetsMessage);
+
+                let users = state
+                    .nodes
+                    .iter()
+                    .filter(|node| match node.data() {
+                        Node::Character(chara) => handle.is_some_and(|handle| chara.uses(handle)),
+                        Node::Family => false,
+                    })
+                    .count();
+
+                if users > 0 {
                     state.notifications.push(Notification::error(
-                        "Failed to rename asset",
-                        format!("{err:#}"),
+                        "Image missing",
+                        format!(
+                            "{path} was deleted or moved, {users} character(s) are missing it. \
+                            Putting the file back restores it."
+                        ),
                     ));
                 }
 
+                task
+            }
+            AssetsMessage::DeleteAsset(handle) => {
+                let Some(path) = state.assets.path(handle) else {
+                    return Task::none();
+                };
+
+                let users: Vec<_> = state
+                    .nodes
+                    .iter()
+                    .filter_map(|node| match node.data() {
+                        Node::Character(chara) if chara.uses(handle) => Some(chara.name.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+
+                let used_by = if users.is_empty() {
+                    "No character uses it.".to_string()
+                } else {
+                    format!("It's used by {}, who'll lose it.", users.join(", "))
+                };
+
+                state.dialog = Some(Dialog::new(
+                    format!("Delete {}?", path.name()),
+                    format!(
+                        "{used_by} The file is moved to the project's .trash folder, Undo puts \
+                        it back."
+                    ),
+                    Message::CloseDialog,
+                    vec![DialogOption::new(
+                        dialog::Severity::Destructive,
+                        "Move to Trash",
+                        Message::TrashAsset(handle),
+                    )],
+                ));
+
+                Task::none()
+            }
+            AssetsMessage::EditImage(handle) => {
+                let opened = state
+                    .assets
+                    .folder()
+                    .zip(state.assets.path(handle))
+                    .ok_or(anyhow!(AssetsError::AssetNotFound))
+                    .and_then(|(folder, path)| {
+                        ImageEditor::open(
+                            handle,
+                            &(folder + path.clone()),
+                            state.assets.crop(handle),
+                        )
+                    });
+
+                match opened {
+                    Ok(editor) => {
+                        state.image_editor = Some(editor);
+                        Task::none()
+                    }
+                    Err(err) => {
+                        state.last_error = Some(err);
+                        Task::done(Message::EditImageFailed)
+                    }
+                }
+            }
+            AssetsMessage::RenameAssetFailed(handle) => {
+                if let Some(err) = state.assets.last_error() {
+                    let title = match state.assets.path(handle) {
+                        Some(path) => format!("Failed to rename {}", path.name()),
+                        None => "Failed to rename asset".to_string(),
+                    };
+
+                    state
+                        .notifications
+                        .push(Notification::error(title, format!("{err:#}")));
+                }
+
+                Task::none()
+            }
+            AssetsMessage::TagAssetFailed(handle) => {
+                if let Some(err) = state.assets.last_error() {
+                    let title = match state.assets.path(handle) {
+                        Some(path) => format!("Failed to tag {}", path.name()),
+                        None => "Failed to tag asset".to_string(),
+                    };
+
+                    state
+                        .notifications
+                        .push(Notification::error(title, format!("{err:#}")));
+                }
+
+                Task::none()
+            }
+            AssetsMessage::WatchFailed(err) => {
+                state.notifications.push(Notification::error(
+                    "Failed to watch the asset folders",
+                    format!(
+                        "{err}. Changes made outside the app might not show up until the project \
+                        is opened again."
+                    ),
+                ));
+
                 Task::none()
             }
             _ => assets::update(&mut state.assets, assets_message).map(Message::AssetsMessage),
         },
         Message::AddCharacter(chara, pos) => {
-            state.nodes.add(Node::Character(chara), pos);
+            let id = state.nodes.add(Node::Character(chara), pos);
+
+            if let Some(added) = state.nodes.snapshot(id) {
+                state.history.record(Command::AddNode(added));
+            }
+
             Task::none()
         }
         Message::MenuButtonPressed => Task::none(),
-        Message::OpenLoadFolderDialog => match pick_folder() {
+        Message::OpenNewProjectDialog => {
+            if state.dirty {
+                state.dialog = Some(discard_dialog(state, Message::PickNewProjectFolder));
+                Task::none()
+            } else {
+                Task::done(Message::PickNewProjectFolder)
+            }
+        }
+        Message::PickNewProjectFolder => {
+            let created = pick_folder().and_then(|folder| {
+                let title = folder
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let author = std::env::var("USER")
+                    .or_else(|_| std::env::var("USERNAME"))
+                    .unwrap_or_default();
+
+                let data = project::save(
+                    &GraphData::default(),
+                    &Relationships::default(),
+                    &project::Metadata::new(title, author),
+                )?;
+
+                io::create_project(&folder, data)?;
+
+                Ok(folder)
+            });
+
+            match created {
+                Ok(folder) => Task::done(Message::Load(folder)),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::OpenFolderFailed)
+                }
+            }
+        }
+        Message::OpenLoadFolderDialog => {
+            if state.dirty {
+                state.dialog = Some(discard_dialog(state, Message::PickLoadFolder));
+                Task::none()
+            } else {
+                Task::done(Message::PickLoadFolder)
+            }
+        }
+        Message::OpenRecentProject(folder) => {
+            if !folder.is_dir() {
+                state.notifications.push(Notification::error(
+                    "Project not found",
+                    format!("{folder:?} doesn't exist anymore, it was removed from the list."),
+                ));
+                state.config.remove_recent_project(&folder);
+
+                return save_config(state);
+            }
+
+            if state.dirty {
+                state.dialog = Some(discard_dialog(state, Message::Load(folder)));
+                Task::none()
+            } else {
+                Task::done(Message::Load(folder))
+            }
+        }
+        Message::ToggleReopenLastProject => {
+            state.config.reopen_last_project = !state.config.reopen_last_project;
+
+            save_config(state)
+        }
+        Message::PickLoadFolder => match pick_folder() {
             Ok(path) if path.exists() && path.is_dir() => {
                 Task::done(Message::ConfirmLoadPath(path))
             }
@@ -455,6 +1004,7 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
         }
         Message::Load(path) => {
             state.dialog = None;
+            state.image_editor = None;
 
             Task::done(Message::AssetsMessage(AssetsMessage::LoadAssets(path)))
         }
@@ -466,28 +1016,37 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                 Message::LoadDataFailed
             }
         }),
-        Message::ParseData(raw_data, path) => {
-            let data = ron::from_str(&raw_data);
-
-            match data {
-                Ok(data) => {
-                    state.nodes = data;
+        Message::ParseData(raw_data, path) => match project::load(&raw_data) {
+            Ok((data, version)) => {
+                state.nodes = data.nodes;
+                state.relationships = data.relationships;
+                state.metadata = data.metadata;
+                state.history.clear();
+                state.highlighted_path = None;
+                state.dirty = false;
 
-                    state.notifications.push(Notification::info(
-                        "Successfully loaded data!",
-                        format!("Successfully loaded data from {:?}", path.clone()),
-                    ));
+                state.notifications.push(Notification::info(
+                    "Successfully loaded data!",
+                    if version < project::FORMAT_VERSION {
+                        format!(
+                            "Successfully loaded data from {path:?}. It was saved in an older \
+                            format and will be upgraded the next time it's saved."
+                        )
+                    } else {
+                        format!("Successfully loaded data from {:?}", path.clone())
+                    },
+                ));
 
-                    state.assets.set_folder(path.clone());
+                state.assets.set_folder(path.clone());
+                state.config.add_recent_project(path);
 
-                    Task::none()
-                }
-                Err(err) => {
-                    state.last_error = Some(anyhow!(err));
-                    Task::done(Message::LoadDataFailed)
-                }
+                save_config(state)
             }
-        }
+            Err(err) => {
+                state.last_error = Some(err);
+                Task::done(Message::LoadDataFailed)
+            }
+        },
         Message::LoadDataFailed => {
             if let Some(err) = &state.last_error
                 && let Some(assets_err) = err.downcast_ref::<AssetsError>()
@@ -525,84 +1084,552 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             }
         },
         Message::AddExternalAsset(path) => {
-            let res = io::copy_to_assets_dir(state.assets.folder().cloned(), path.clone());
+            let Some(folder) = state.assets.folder() else {
+                state.last_error = Some(anyhow!(AssetsError::NoFolderLoaded));
+                return Task::done(Message::AddAssetFailed);
+            };
 
-            Task::done(match res {
-                Ok((path, asset)) => Message::AddAsset(
-                    path.file_name().unwrap().to_string_lossy().to_string(),
-                    asset,
-                ),
+            let asset = match io::load_file(&path, folder) {
+                Ok(asset) => asset,
                 Err(err) => {
                     state.last_error = Some(err);
-                    Message::AddAssetFailed
+                    return Task::done(Message::AddAssetFailed);
                 }
-            })
+            };
+
+            let Some((_, existing)) = state.assets.find_duplicate(asset.hash()) else {
+                return Task::done(Message::ImportExternalAsset(path, asset.kind()));
+            };
+
+            state.dialog = Some(Dialog::new(
+                "Already imported",
+                format!(
+                    "{:?} has the same contents as {existing}, which is already in the project.",
+                    path.file_name().unwrap_or_default(),
+                ),
+                Message::CancelImport(path.clone()),
+                vec![DialogOption::new(
+                    dialog::Severity::Neutral,
+                    "Import Anyway",
+                    Message::ImportExternalAsset(path, asset.kind()),
+                )],
+            ));
+
+            Task::none()
         }
-        Message::AddAsset(file_name, asset) => match state.assets.add(file_name, asset) {
-            Ok(_) => Task::none(),
-            Err(err) => {
-                state.last_error = Some(anyhow!(err));
-                Task::done(Message::AddAssetFailed)
-            }
-        },
-        Message::AddAssetFailed => {
-            if let Some(err) = &state.last_error {
-                state.notifications.push(Notification::error(
-                    "Failed to add asset",
-                    format!("Failed to add asset: {err:?}",),
-                ));
+        Message::ImportExternalAsset(path, kind) => {
+            state.dialog = None;
+
+            let Some(folder) = state.assets.folder() else {
+                return Task::none();
+            };
+
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+
+            if !folder.join(kind.folder()).join(&*file_name).exists() {
+                return Task::done(Message::CopyExternalAsset(path, NameClash::KeepBoth));
             }
 
+            state.dialog = Some(Dialog::new(
+                "Name already taken",
+                format!(
+                    "The project already has a file called {file_name:?}. Replacing it changes \
+                    every character using it."
+                ),
+                Message::CancelImport(path.clone()),
+                vec![
+                    DialogOption::new(
+                        dialog::Severity::Destructive,
+                        "Replace",
+                        Message::CopyExternalAsset(path.clone(), NameClash::Replace),
+                    ),
+                    DialogOption::new(
+                        dialog::Severity::Neutral,
+                        "Keep Both",
+                        Message::CopyExternalAsset(path.clone(), NameClash::KeepBoth),
+                    ),
+                    DialogOption::new(
+                        dialog::Severity::Neutral,
+                        "Rename",
+                        Message::CopyExternalAsset(path, NameClash::Rename),
+                    ),
+                ],
+            ));
+
             Task::none()
         }
-        Message::Save => {
-            let parsed = ron::ser::to_string_pretty(&state.nodes, PrettyConfig::new()).unwrap();
+        Message::CopyExternalAsset(path, clash) => {
+            state.dialog = None;
 
-            if let Some(folder) = &state.assets.folder() {
-                match io::save(folder.join("data.ron"), parsed) {
-                    Ok(()) => Task::done(Message::Saved),
-                    Err(err) => {
-                        state.last_error = Some(err);
-                        Task::done(Message::SaveFailed)
-                    }
+            let added = io::copy_to_assets_dir(
+                state.assets.folder().cloned(),
+                path.clone(),
+                matches!(clash, NameClash::Replace),
+            )
+            .and_then(|(path, asset)| {
+                let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+
+                Ok((state.assets.add(file_name.clone(), asset)?, file_name))
+            });
+
+            io::remove_imported(&path);
+
+            match added {
+                Ok((handle, file_name)) if matches!(clash, NameClash::Rename) => {
+                    Task::done(Message::AssetsMessage(AssetsMessage::SetRenameInput(Some(
+                        (handle, file_name),
+                    ))))
+                }
+                Ok(_) => Task::none(),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::AddAssetFailed)
                 }
-            } else {
-                Task::none()
             }
         }
-        Message::Saved => {
-            state.notifications.push(Notification::info(
-                "Saved successfully!",
-                format!(
-                    "successfully saved to {}",
-                    state.assets.folder().unwrap().to_string_lossy()
-                ),
-            ));
+        Message::CancelImport(path) => {
+            state.dialog = None;
+            io::remove_imported(&path);
+
             Task::none()
         }
-        Message::SaveFailed => {
+        Message::AddAssetFailed => {
             if let Some(err) = &state.last_error {
                 state.notifications.push(Notification::error(
-                    "Failed to save data",
-                    format!(
-                        "Failed to save to {}: {err}",
-                        state.assets.folder().unwrap().to_string_lossy()
-                    ),
+                    "Failed to add asset",
+                    format!("Failed to add asset: {err:?}",),
                 ));
             }
 
             Task::none()
         }
-        Message::PaneClicked(pane) => {
-            state.focus = Some(pane);
-            Task::none()
-        }
-        Message::PaneDragged(pane_grid::DragEvent::Dropped { pane, target }) => {
-            state.panes.drop(pane, target);
-            Task::none()
-        }
-        Message::PaneDragged(_) => Task::none(),
-        Message::PaneResized(pane_grid::ResizeEvent { split, ratio }) => {
+        Message::TrashAsset(handle) => {
+            state.dialog = None;
+
+            let trashed = match state.assets.trash(handle) {
+                Ok(trashed) => trashed,
+                Err(err) => {
+                    state.last_error = Some(err);
+                    return Task::done(Message::TrashAssetFailed);
+                }
+            };
+
+            let users: Vec<_> = state
+                .nodes
+                .iter()
+                .filter_map(|node| match node.data() {
+                    Node::Character(chara) if chara.uses(handle) => {
+                        Some((node.id(), chara.clone()))
+                    }
+                    _ => None,
+                })
+                .collect();
+
+            // one undo brings back the file and every character using it
+            state.history.begin();
+            state.history.record(Command::TrashAsset(trashed));
+
+            for (id, old) in users {
+                let mut chara = old.clone();
+
+                if chara.img == handle {
+                    chara.img = AssetHandle::NONE;
+                }
+
+                chara.attachments.retain(|attachment| *attachment != handle);
+
+                if let Some(node) = state.nodes.get_mut(id) {
+                    *node.data_mut() = Node::Character(chara.clone());
+                }
+
+                state.history.record(Command::EditNode {
+                    id,
+                    from: Node::Character(old),
+                    to: Node::Character(chara),
+                });
+            }
+
+            state.history.commit();
+
+            Task::none()
+        }
+        Message::TrashAssetFailed => {
+            if let Some(err) = &state.last_error {
+                state.notifications.push(Notification::error(
+                    "Failed to delete asset",
+                    format!("Failed to delete asset: {err:#}"),
+                ));
+            }
+
+            Task::none()
+        }
+        Message::Save => {
+            if state.assets.folder().is_none() {
+                return Task::none();
+            }
+
+            match save(state) {
+                Ok(()) => Task::done(Message::Saved),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::SaveFailed)
+                }
+            }
+        }
+        Message::Autosave => {
+            if !state.dirty || state.assets.folder().is_none() {
+                return Task::none();
+            }
+
+            // saving quietly, only failures are worth interrupting for
+            match save(state) {
+                Ok(()) => Task::none(),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::SaveFailed)
+                }
+            }
+        }
+        Message::OpenAutosaveDialog => {
+            let options = [
+                ("Off", None),
+                ("After 1 minute", Some(Duration::from_secs(60))),
+                ("After 5 minutes", Some(Duration::from_secs(5 * 60))),
+                ("After 15 minutes", Some(Duration::from_secs(15 * 60))),
+            ]
+            .into_iter()
+            .map(|(label, interval)| {
+                DialogOption::new(
+                    if interval == state.config.autosave {
+                        dialog::Severity::Success
+                    } else {
+                        dialog::Severity::Neutral
+                    },
+                    label,
+                    Message::SetAutosave(interval),
+                )
+            })
+            .collect();
+
+            state.dialog = Some(Dialog::new(
+                "Autosave",
+                "How long after a change should the project get saved automatically?",
+                Message::CloseDialog,
+                options,
+            ));
+
+            Task::none()
+        }
+        Message::SetAutosave(interval) => {
+            state.dialog = None;
+            state.config.autosave = interval;
+
+            save_config(state)
+        }
+        Message::OpenRestoreBackupDialog => {
+            let Some(folder) = state.assets.folder() else {
+                return Task::none();
+            };
+
+            match io::backups(folder) {
+                Ok(backups) if backups.is_empty() => {
+                    state.notifications.push(Notification::info(
+                        "No backups",
+                        "Backups are created every time the project gets saved.",
+                    ));
+                }
+                Ok(backups) => {
+                    let options = backups
+                        .into_iter()
+                        .map(|backup| {
+                            DialogOption::new(
+                                dialog::Severity::Neutral,
+                                backup.age(),
+                                Message::RestoreBackup(backup.path),
+                            )
+                        })
+                        .collect();
+
+                    state.dialog = Some(Dialog::new(
+                        "Restore Backup",
+                        "Which save should the project go back to? Unsaved changes are lost, the \
+                        current save gets backed up first.",
+                        Message::CloseDialog,
+                        options,
+                    ));
+                }
+                Err(err) => {
+                    state.notifications.push(Notification::error(
+                        "Failed to list backups",
+                        format!("Failed to list backups: {err}"),
+                    ));
+                }
+            }
+
+            Task::none()
+        }
+        Message::RestoreBackup(backup) => {
+            state.dialog = None;
+
+            let Some(folder) = state.assets.folder().cloned() else {
+                return Task::none();
+            };
+
+            match io::restore_backup(&folder, &backup) {
+                Ok(()) => Task::done(Message::Load(folder)),
+                Err(err) => {
+                    state.notifications.push(Notification::error(
+                        "Failed to restore backup",
+                        format!("Failed to restore backup: {err}"),
+                    ));
+
+                    Task::none()
+                }
+            }
+        }
+        Message::Saved => {
+            state.notifications.push(Notification::info(
+                "Saved successfully!",
+                format!(
+                    "successfully saved to {}",
+                    state.assets.folder().unwrap().to_string_lossy()
+                ),
+            ));
+            Task::none()
+        }
+        Message::SaveFailed => {
+            if let Some(err) = &state.last_error {
+                state.notifications.push(Notification::error(
+                    "Failed to save data",
+                    format!(
+                        "Failed to save to {}: {err}",
+                        state.assets.folder().unwrap().to_string_lossy()
+                    ),
+                ));
+            }
+
+            Task::none()
+        }
+        Message::ImportGedcom => {
+            let imported = pick_gedcom_file()
+                .and_then(|path| Ok(std::fs::read_to_string(path)?))
+                .and_then(|source| gedcom::import(&source, &state.assets));
+
+            match imported {
+                Ok(imported) => {
+                    let ids: Vec<_> = imported.iter().map(GraphNode::id).collect();
+
+                    insert_nodes(
+                        state,
+                        imported.subgraph(&ids),
+                        Point::ORIGIN + state.graph_position,
+                    );
+
+                    state.notifications.push(Notification::info(
+                        "Imported GEDCOM",
+                        format!("Imported {} nodes.", ids.len()),
+                    ));
+
+                    Task::none()
+                }
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::ImportFailed)
+                }
+            }
+        }
+        Message::ExportGedcom => {
+            let exported = pick_save_file("Export GEDCOM", "GEDCOM", "ged").and_then(|path| {
+                io::save(path.clone(), gedcom::export(&state.nodes, &state.assets)?)?;
+                Ok(path)
+            });
+
+            Task::done(match exported {
+                Ok(path) => Message::Exported(path),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Message::ExportFailed
+                }
+            })
+        }
+        Message::ImportJson => {
+            let imported = pick_json_file()
+                .and_then(|path| Ok(std::fs::read_to_string(path)?))
+                .and_then(|source| json::import(&source, &state.assets, &state.relationships));
+
+            match imported {
+                Ok((imported, relationships)) => {
+                    // undoing the import also takes the relationship kinds it added away
+                    state.history.begin();
+
+                    if relationships != state.relationships {
+                        let from = std::mem::replace(&mut state.relationships, relationships);
+
+                        state.history.record(Command::SetRelationships {
+                            from,
+                            to: state.relationships.clone(),
+                        });
+                    }
+
+                    let ids: Vec<_> = imported.iter().map(GraphNode::id).collect();
+
+                    insert_nodes(
+                        state,
+                        imported.subgraph(&ids),
+                        Point::ORIGIN + state.graph_position,
+                    );
+                    state.history.commit();
+
+                    state.notifications.push(Notification::info(
+                        "Imported JSON",
+                        format!("Imported {} nodes.", ids.len()),
+                    ));
+
+                    Task::none()
+                }
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::ImportFailed)
+                }
+            }
+        }
+        Message::ExportJson => {
+            let exported = pick_save_file("Export JSON", "JSON", "json").and_then(|path| {
+                io::save(
+                    path.clone(),
+                    json::export(&state.nodes, &state.assets, &state.relationships),
+                )?;
+                Ok(path)
+            });
+
+            Task::done(match exported {
+                Ok(path) => Message::Exported(path),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Message::ExportFailed
+                }
+            })
+        }
+        Message::ExportDot => {
+            let exported = pick_save_file("Export DOT", "Graphviz", "dot").and_then(|path| {
+                io::save(
+                    path.clone(),
+                    dot::export(&state.nodes, &state.relationships),
+                )?;
+                Ok(path)
+            });
+
+            Task::done(match exported {
+                Ok(path) => Message::Exported(path),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Message::ExportFailed
+                }
+            })
+        }
+        Message::OpenExportImageDialog => {
+            let options = [
+                ("SVG", ImageFormat::Svg),
+                ("PNG", ImageFormat::Png(1.0)),
+                ("PNG (2x)", ImageFormat::Png(2.0)),
+                ("PNG (4x)", ImageFormat::Png(4.0)),
+            ]
+            .into_iter()
+            .map(|(label, format)| {
+                DialogOption::new(
+                    dialog::Severity::Neutral,
+                    label,
+                    Message::ExportImage(format),
+                )
+            })
+            .collect();
+
+            state.dialog = Some(Dialog::new(
+                "Export Image",
+                "Which format should the graph be exported as?",
+                Message::CloseDialog,
+                options,
+            ));
+
+            Task::none()
+        }
+        Message::ExportImage(format) => {
+            state.dialog = None;
+
+            let exported = pick_save_file("Export Image", format.name(), format.extension())
+                .and_then(|path| {
+                    let theme = theme(state);
+
+                    match format {
+                        ImageFormat::Svg => io::save(
+                            path.clone(),
+                            export::svg(&state.nodes, &state.assets, &state.relationships, &theme)?,
+                        )?,
+                        ImageFormat::Png(scale) => io::save(
+                            path.clone(),
+                            export::png(
+                                &state.nodes,
+                                &state.assets,
+                                &state.relationships,
+                                &theme,
+                                scale,
+                            )?,
+                        )?,
+                    }
+
+                    Ok(path)
+                });
+
+            Task::done(match exported {
+                Ok(path) => Message::Exported(path),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Message::ExportFailed
+                }
+            })
+        }
+        Message::Exported(path) => {
+            state.notifications.push(Notification::info(
+                "Exported successfully!",
+                format!("Exported to {}", path.to_string_lossy()),
+            ));
+
+            Task::none()
+        }
+        Message::ImportFailed => {
+            if let Some(err) = &state.last_error
+                && err.downcast_ref::<AssetsError>() != Some(&AssetsError::DialogClosed)
+            {
+                state.notifications.push(Notification::error(
+                    "Failed to import",
+                    format!("Failed to import: {err}"),
+                ));
+            }
+
+            Task::none()
+        }
+        Message::ExportFailed => {
+            if let Some(err) = &state.last_error
+                && err.downcast_ref::<AssetsError>() != Some(&AssetsError::DialogClosed)
+            {
+                state.notifications.push(Notification::error(
+                    "Failed to export",
+                    format!("Failed to export: {err}"),
+                ));
+            }
+
+            Task::none()
+        }
+        Message::PaneClicked(pane) => {
+            state.focus = Some(pane);
+            Task::none()
+        }
+        Message::PaneDragged(pane_grid::DragEvent::Dropped { pane, target }) => {
+            state.panes.drop(pane, target);
+            Task::none()
+        }
+        Message::PaneDragged(_) => Task::none(),
+        Message::PaneResized(pane_grid::ResizeEvent { split, ratio }) => {
             state.panes.resize(split, ratio);
             Task::none()
         }
@@ -618,13 +1645,29 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             GraphEvent::MoveNode {
                 id,
                 new_position,
-                was_dragged: _,
+                was_dragged,
             } => {
                 if let Some(img) = state.nodes.get_mut(id) {
+                    let from = img.position();
                     img.move_to(new_position);
+
+                    // positioning schemes move nodes around all the time, only user drags
+                    // are worth undoing
+                    if was_dragged {
+                        state.history.begin();
+                        state.history.record(Command::MoveNode {
+                            id,
+                            from,
+                            to: new_position,
+                        });
+                    }
                 }
                 Task::none()
             }
+            GraphEvent::DragEnded => {
+                state.history.commit();
+                Task::none()
+            }
             GraphEvent::Connect {
                 a,
                 a_attachment,
@@ -652,60 +1695,79 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
                     matches!(a_node, Node::Family) || matches!(b_node, Node::Family);
 
                 if !family_present {
-                    let a_point = state.nodes.get(a).unwrap().position();
-                    let b_point = state.nodes.get(b).unwrap().position();
-
-                    let halfway_point = a_point + (b_point - a_point) * 0.5;
-
-                    match (a_edge, b_edge) {
-                        (Edge::Left, Edge::Right) => {
-                            let _ = state.nodes.attach_new(
-                                Node::Family,
-                                halfway_point,
-                                RelativeAttachment::top(),
-                                a,
-                                RelativeAttachment::left(),
-                            );
+                    let partners = matches!(
+                        (a_edge, b_edge),
+                        (Edge::Left, Edge::Right) | (Edge::Right, Edge::Left)
+                    );
 
-                            let _ = state.nodes.connect(
-                                b,
-                                b_attachment,
-                                state.nodes.num_nodes() - 1,
-                                RelativeAttachment::top(),
-                            );
-                        }
-                        (Edge::Right, Edge::Left) => {
-                            let _ = state.nodes.attach_new(
-                                Node::Family,
-                                halfway_point,
-                                RelativeAttachment::top(),
-                                a,
-                                RelativeAttachment::right(),
-                            );
+                    let connect_as = |kind| Message::ConnectAs {
+                        a,
+                        a_attachment: a_attachment.clone(),
+                        b,
+                        b_attachment: b_attachment.clone(),
+                        kind,
+                    };
 
-                            let _ = state.nodes.connect(
-                                b,
-                                b_attachment,
-                                state.nodes.num_nodes() - 1,
-                                RelativeAttachment::top(),
-                            );
-                        }
-                        _ => (),
+                    if state.relationships.is_empty() {
+                        return if partners {
+                            Task::done(connect_as(None))
+                        } else {
+                            Task::none()
+                        };
+                    }
+
+                    let mut options = Vec::new();
+
+                    if partners {
+                        options.push(DialogOption::new(
+                            dialog::Severity::Neutral,
+                            "Partners",
+                            connect_as(None),
+                        ));
                     }
 
+                    options.extend(state.relationships.iter().map(|(kind, relationship)| {
+                        DialogOption::new(
+                            dialog::Severity::Neutral,
+                            relationship.label.clone(),
+                            connect_as(Some(kind)),
+                        )
+                    }));
+
+                    let name = |id| match state.nodes.get(id).map(GraphNode::data) {
+                        Some(Node::Character(chara)) => chara.name.clone(),
+                        _ => String::new(),
+                    };
+
+                    state.dialog = Some(Dialog::new(
+                        "Add relationship",
+                        format!("How are {} and {} related?", name(a), name(b)),
+                        Message::CloseDialog,
+                        options,
+                    ));
+
                     return Task::none();
                 }
 
-                let _ = state.nodes.connect(a, a_attachment, b, b_attachment);
+                let connection = state.nodes.connect(a, a_attachment, b, b_attachment);
+
+                record_connection(state, connection.ok().flatten());
 
                 Task::none()
             }
             GraphEvent::Disconnect { connection_id } => {
-                state.nodes.remove_connection(connection_id);
+                if let Some(connection) = state.nodes.remove_connection(connection_id) {
+                    state.history.record(Command::Disconnect(connection));
+                }
                 Task::none()
             }
             GraphEvent::Delete { id } => {
-                state.nodes.remove(id);
+                if let Some(removed) = state.nodes.remove(id) {
+                    // deleting a selection publishes one event per node followed by
+                    // ClearSelection, which commits them as one entry
+                    state.history.begin();
+                    state.history.record(Command::RemoveNode(removed));
+                }
                 Task::none()
             }
             GraphEvent::ConnectionDropped { id, attachment } => {
@@ -722,24 +1784,110 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             }
             GraphEvent::ClearSelection => {
                 state.nodes.clear_selection();
+                state.history.commit();
                 Task::none()
             }
             GraphEvent::SelectAll => {
                 state.nodes.select_all();
                 Task::none()
             }
+            GraphEvent::Copy => copy_selection(state),
+            GraphEvent::Cut => {
+                let task = copy_selection(state);
+
+                let selection: Vec<_> = state.nodes.selection().collect();
+
+                state.history.begin();
+                for id in selection {
+                    if let Some(removed) = state.nodes.remove(id) {
+                        state.history.record(Command::RemoveNode(removed));
+                    }
+                }
+                state.history.commit();
+
+                task
+            }
+            GraphEvent::Paste { contents, position } => {
+                // the clipboard can contain anything, if it's not a subgraph it might be an image
+                let Ok(CopiedNodes {
+                    graph: mut subgraph,
+                    assets,
+                    kinds,
+                }) = ron::from_str(&contents)
+                else {
+                    return Task::done(Message::PasteImage {
+                        position: Some(position),
+                        quiet: true,
+                    });
+                };
+
+                if subgraph.is_empty() {
+                    return Task::none();
+                }
+
+                let find = |copied: AssetHandle| {
+                    assets
+                        .iter()
+                        .find(|(handle, _)| *handle == copied)
+                        .and_then(|(_, path)| state.assets.handle(path.clone()))
+                };
+
+                for node in subgraph.iter_mut() {
+                    if let Node::Character(chara) = node.data_mut() {
+                        // the same handle in this project could be any other image
+                        chara.img = find(chara.img).unwrap_or(AssetHandle::NONE);
+                        chara.attachments =
+                            chara.attachments.iter().filter_map(|h| find(*h)).collect();
+                    }
+                }
+
+                // kinds this project doesn't have are drawn like family connections
+                for connection in subgraph.connections_mut() {
+                    connection.kind = connection.kind.and_then(|copied| {
+                        let (_, label) = kinds.iter().find(|(kind, _)| *kind == copied)?;
+
+                        state
+                            .relationships
+                            .iter()
+                            .find(|(_, kind)| kind.label == *label)
+                            .map(|(kind, _)| kind)
+                    });
+                }
+
+                insert_nodes(state, subgraph, position);
+
+                Task::none()
+            }
         },
-        Message::TraverseGraph => {
-            state
-                .nodes
-                .iter_bfs(state.nodes.selection().next().unwrap_or(0))
-                .for_each(|(i, node)| println!("for_each {i}: {:?}", node.data()));
+        Message::HighlightPath => {
+            let mut selection = state.nodes.selection();
+
+            let (Some(a), Some(b), None) = (selection.next(), selection.next(), selection.next())
+            else {
+                state.notifications.push(Notification::info(
+                    "Can't highlight path",
+                    "Select exactly two nodes to highlight the path between them.",
+                ));
+                return Task::none();
+            };
+
+            if state.nodes.shortest_path(a, b).is_none() {
+                state.notifications.push(Notification::info(
+                    "Can't highlight path",
+                    "The selected nodes aren't connected.",
+                ));
+                return Task::none();
+            }
+
+            state.highlighted_path = Some((a, b));
+
             Task::none()
         }
-        Message::CharacterButtonPressed => {
-            println!("pressd");
+        Message::ClearHighlight => {
+            state.highlighted_path = None;
             Task::none()
         }
+        Message::Inspector(message) => inspector::update(state, message),
         Message::DismissNotification(i) => {
             state.notifications.remove(i);
 
@@ -762,36 +1910,228 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
             }
             Task::none()
         }
+        Message::AttachAsset(handle) => {
+            state.dnd_payload = None;
+
+            inspector::update(state, InspectorMessage::Attach(handle))
+        }
         Message::DropAssetOnGraph(handle, relative_cursor_pos) => {
             state.dnd_payload = None;
-            match &state.assets[handle] {
-                Asset::Image(_) => Task::done(Message::AddCharacter(
-                    Character {
-                        name: state
-                            .assets
-                            .path(handle)
-                            .unwrap()
-                            .name()
-                            .split('.')
-                            .next()
-                            .unwrap_or("name")
-                            .to_string(),
-                        img: handle,
-                    },
+            match state.assets.get(handle) {
+                Some(Asset::Image(_)) => Task::done(Message::AddCharacter(
+                    image_character(state, handle),
                     relative_cursor_pos * Transformation::scale(1.0 / state.graph_zoom)
                         + state.graph_position,
                 )),
+                Some(Asset::Audio(_) | Asset::Video(_) | Asset::Document(_)) | None => Task::none(),
             }
         }
+        Message::DropFileOnGraph(path, relative_cursor_pos) => match import_image(state, &path) {
+            Ok(handle) => Task::done(Message::DropAssetOnGraph(handle, relative_cursor_pos)),
+            Err(err) => {
+                state.last_error = Some(err);
+                Task::done(Message::AddAssetFailed)
+            }
+        },
+        Message::PasteImage { position, quiet } => {
+            let Some(folder) = state.assets.folder().cloned() else {
+                state.last_error = Some(anyhow!(AssetsError::NoFolderLoaded));
+                return Task::done(Message::AddAssetFailed);
+            };
+
+            Task::future(io::paste_image(folder)).then(move |pasted| match pasted {
+                Ok(path) => Task::done(Message::ImagePasted(path, position)),
+                Err(err)
+                    if quiet
+                        && matches!(err.downcast_ref(), Some(AssetsError::NoClipboardImage)) =>
+                {
+                    Task::none()
+                }
+                Err(err) => Task::done(Message::PasteImageFailed(format!("{err:#}"))),
+            })
+        }
+        Message::ImagePasted(path, None) => Task::done(Message::AddExternalAsset(path)),
+        Message::ImagePasted(path, Some(position)) => {
+            let imported = import_image(state, &path);
+            io::remove_imported(&path);
+
+            match imported {
+                Ok(handle) => Task::done(Message::AddCharacter(
+                    image_character(state, handle),
+                    position,
+                )),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::AddAssetFailed)
+                }
+            }
+        }
+        Message::PasteImageFailed(err) => {
+            state.last_error = Some(anyhow!(err));
+            Task::done(Message::AddAssetFailed)
+        }
+        Message::ImageEditor(message) => {
+            let Some(editor) = &mut state.image_editor else {
+                return Task::none();
+            };
+
+            match message {
+                EditorMessage::SaveCrop => {
+                    let (handle, to) = (editor.handle, editor.crop());
+                    state.image_editor = None;
+
+                    match state.assets.set_crop(handle, to) {
+                        Ok(from) => {
+                            state
+                                .history
+                                .record(Command::CropAsset { handle, from, to });
+
+                            Task::none()
+                        }
+                        Err(err) => {
+                            state.last_error = Some(err);
+                            Task::done(Message::EditImageFailed)
+                        }
+                    }
+                }
+                EditorMessage::SaveVersion => {
+                    let saved = state
+                        .assets
+                        .folder()
+                        .ok_or(anyhow!(AssetsError::NoFolderLoaded))
+                        .and_then(|folder| {
+                            io::save_edited_image(folder, editor.name(), &editor.result())
+                        });
+                    state.image_editor = None;
+
+                    match saved {
+                        Ok(path) => {
+                            Task::done(Message::CopyExternalAsset(path, NameClash::KeepBoth))
+                        }
+                        Err(err) => {
+                            state.last_error = Some(err);
+                            Task::done(Message::EditImageFailed)
+                        }
+                    }
+                }
+                EditorMessage::Cancel => {
+                    state.image_editor = None;
+
+                    Task::none()
+                }
+                message => {
+                    editor.update(message);
+
+                    Task::none()
+                }
+            }
+        }
+        Message::EditImageFailed => {
+            if let Some(err) = &state.last_error {
+                state.notifications.push(Notification::error(
+                    "Failed to edit image",
+                    format!("{err:#}"),
+                ));
+            }
+
+            Task::none()
+        }
+        Message::ConnectAs {
+            a,
+            a_attachment,
+            b,
+            b_attachment,
+            kind,
+        } => {
+            state.dialog = None;
+
+            match kind {
+                None => connect_partners(state, a, a_attachment, b, b_attachment),
+                Some(kind) => {
+                    let connection =
+                        state
+                            .nodes
+                            .connect_with_kind(a, a_attachment, b, b_attachment, Some(kind));
+
+                    record_connection(state, connection.ok().flatten());
+                }
+            }
+
+            Task::none()
+        }
         Message::CloseDialog => {
             state.dialog = None;
             Task::none()
         }
+        Message::DiscardChanges(then) => {
+            state.dialog = None;
+            Task::done(*then)
+        }
+        Message::SaveAndContinue(then) => {
+            state.dialog = None;
+
+            match save(state) {
+                Ok(()) => Task::done(Message::Saved).chain(Task::done(*then)),
+                Err(err) => {
+                    state.last_error = Some(err);
+                    Task::done(Message::SaveFailed)
+                }
+            }
+        }
+        Message::CloseRequested(id) => {
+            if state.dirty {
+                state.dialog = Some(discard_dialog(state, Message::CloseWindow(id)));
+                Task::none()
+            } else {
+                Task::done(Message::CloseWindow(id))
+            }
+        }
+        Message::CloseWindow(id) => window::close(id),
+        Message::Undo => {
+            if let Err(err) = state.history.undo(
+                &mut state.nodes,
+                &mut state.assets,
+                &mut state.relationships,
+            ) {
+                state
+                    .notifications
+                    .push(Notification::error("Failed to undo", format!("{err:#}")));
+            }
+
+            Task::none()
+        }
+        Message::Redo => {
+            if let Err(err) = state.history.redo(
+                &mut state.nodes,
+                &mut state.assets,
+                &mut state.relationships,
+            ) {
+                state
+                    .notifications
+                    .push(Notification::error("Failed to redo", format!("{err:#}")));
+            }
+
+            Task::none()
+        }
+        Message::Paste => {
+            let position = Point::ORIGIN + state.graph_position + Vector::new(50.0, 50.0);
+
+            iced::clipboard::read().map(move |contents| {
+                Message::GraphEvent(GraphEvent::Paste {
+                    contents: contents.unwrap_or_default(),
+                    position,
+                })
+            })
+        }
         Message::EscapePressed => {
-            if state.dialog.is_some() {
-                Task::done(Message::CloseDialog)
+            if let Some(dialog) = &state.dialog {
+                Task::done(dialog.on_cancel().clone())
+            } else if state.image_editor.is_some() {
+                Task::done(Message::ImageEditor(EditorMessage::Cancel))
             } else if state.assets.query_present() {
                 Task::done(Message::AssetsMessage(AssetsMessage::QueryChanged(None)))
+            } else if state.highlighted_path.is_some() {
+                Task::done(Message::ClearHighlight)
             } else {
                 Task::none()
             }
@@ -799,14 +2139,312 @@ fn update(state: &mut State, message: Message) -> Task<Message> {
     }
 }
 
-fn subscription(_state: &State) -> Subscription<Message> {
+/// Backs up the last save and writes the project to its folder.
+fn save(state: &mut State) -> anyhow::Result<()> {
+    let folder = state.assets.folder().ok_or(AssetsError::NoFolderLoaded)?;
+
+    io::backup(folder)?;
+    io::save(
+        folder.join("data.ron"),
+        project::save(&state.nodes, &state.relationships, &state.metadata)?,
+    )?;
+
+    state.dirty = false;
+
+    Ok(())
+}
+
+fn save_config(state: &mut State) -> Task<Message> {
+    if let Err(err) = state.config.save() {
+        state.notifications.push(Notification::error(
+            "Failed to save settings",
+            format!("Failed to save settings: {err}"),
+        ));
+    }
+
+    Task::none()
+}
+
+/// Asks whether unsaved changes can be thrown away before doing `then`.
+fn discard_dialog(state: &State, then: Message) -> Dialog<Message> {
+    let mut options = vec![DialogOption::new(
+        dialog::Severity::Warn,
+        "Discard",
+        Message::DiscardChanges(Box::new(then.clone())),
+    )];
+
+    // without a folder there's nowhere to save to
+    if state.assets.folder().is_some() {
+        options.push(DialogOption::new(
+            dialog::Severity::Success,
+            "Save",
+            Message::SaveAndContinue(Box::new(then)),
+        ));
+    }
+
+    Dialog::new(
+        "Unsaved changes",
+        "The project has changes that haven't been saved yet. Do you want to save them first?",
+        Message::CloseDialog,
+        options,
+    )
+}
+
+/// A new character with the image `handle` and named after its file.
+fn image_character(state: &State, handle: AssetHandle) -> Character {
+    let name = state
+        .assets
+        .path(handle)
+        .and_then(|path| path.name().split('.').next())
+        .unwrap_or("name");
+
+    Character::new(name, handle)
+}
+
+/// Imports the image at `path` for a character, without asking anything: an asset with the same
+/// contents is used instead, and a taken name gets numbered.
+fn import_image(state: &mut State, path: &std::path::Path) -> anyhow::Result<AssetHandle> {
+    let folder = state
+        .assets
+        .folder()
+        .cloned()
+        .ok_or(AssetsError::NoFolderLoaded)?;
+
+    let asset = io::load_file(path, &folder)?;
+
+    if asset.kind() != AssetKind::Image {
+        return Err(anyhow!(AssetsError::NotAnImage));
+    }
+
+    if let Some((handle, _)) = state.assets.find_duplicate(asset.hash()) {
+        return Ok(handle);
+    }
+
+    let (new_path, asset) = io::copy_to_assets_dir(Some(folder), path.to_path_buf(), false)?;
+    let file_name = new_path.file_name().unwrap().to_string_lossy().to_string();
+
+    state.assets.add(file_name, asset)
+}
+
+/// Which assets the characters use, for sorting and filtering the assets pane.
+fn asset_usage(state: &State) -> assets::Usage {
+    let mut usage = assets::Usage::default();
+
+    for node in state.nodes.iter() {
+        if let Node::Character(chara) = node.data() {
+            for handle in chara.assets() {
+                *usage.counts.entry(handle).or_default() += 1;
+            }
+        }
+    }
+
+    for id in state.nodes.selection() {
+        if let Some(Node::Character(chara)) = state.nodes.get(id).map(|node| node.data()) {
+            usage.selected.extend(chara.assets());
+        }
+    }
+
+    usage
+}
+
+fn copy_selection(state: &mut State) -> Task<Message> {
+    let selection: Vec<_> = state.nodes.selection().collect();
+
+    if selection.is_empty() {
+        return Task::none();
+    }
+
+    let graph = state.nodes.subgraph(&selection);
+
+    let assets = graph
+        .iter()
+        .filter_map(|node| match node.data() {
+            Node::Character(chara) => Some(
+                std::iter::once(chara.img)
+                    .chain(chara.attachments.iter().copied())
+                    .filter_map(|handle| {
+                        state.assets.path(handle).map(|path| (handle, path.clone()))
+                    }),
+            ),
+            Node::Family => None,
+        })
+        .flatten()
+        .collect();
+
+    let kinds = graph
+        .connections()
+        .filter_map(|connection| connection.kind)
+        .filter_map(|kind| {
+            state
+                .relationships
+                .get(kind)
+                .map(|relationship| (kind, relationship.label.clone()))
+        })
+        .collect();
+
+    match ron::ser::to_string(&CopiedNodes {
+        graph,
+        assets,
+        kinds,
+    }) {
+        Ok(contents) => iced::clipboard::write(contents),
+        Err(err) => {
+            state.notifications.push(Notification::error(
+                "Failed to copy",
+                format!("Failed to copy selection: {err}"),
+            ));
+
+            Task::none()
+        }
+    }
+}
+
+/// Connects two characters as partners through a new family node between them.
+fn connect_partners(
+    state: &mut State,
+    a: NodeId,
+    a_attachment: RelativeAttachment<line_styles::AxisAligned>,
+    b: NodeId,
+    b_attachment: RelativeAttachment<line_styles::AxisAligned>,
+) {
+    let (Some(a_node), Some(b_node)) = (state.nodes.get(a), state.nodes.get(b)) else {
+        return;
+    };
+
+    let halfway_point = a_node.position() + (b_node.position() - a_node.position()) * 0.5;
+
+    if let Ok(family) = state.nodes.attach_new(
+        Node::Family,
+        halfway_point,
+        RelativeAttachment::top(),
+        a,
+        a_attachment,
+    ) {
+        let connection = state
+            .nodes
+            .connect(b, b_attachment, family, RelativeAttachment::top());
+
+        state.history.begin();
+        if let Some(added) = state.nodes.snapshot(family) {
+            state.history.record(Command::AddNode(added));
+        }
+        record_connection(state, connection.ok().flatten());
+        state.history.commit();
+    }
+}
+
+/// Describes the highlighted path as a chain of characters and how each is related to the
+/// next, like "A → parent of → B → sibling of → C".
+fn path_chain(state: &State) -> Option<String> {
+    let (a, b) = state.highlighted_path?;
+    let path = state.nodes.shortest_path(a, b)?;
+
+    let name = |id| match state.nodes.get(id).map(GraphNode::data) {
+        Some(Node::Character(chara)) => Some(chara.name.as_str()),
+        _ => None,
+    };
+
+    let characters: Vec<_> = path
+        .nodes
+        .iter()
+        .filter_map(|id| name(*id).map(|name| (*id, name)))
+        .collect();
+
+    let mut chain = characters.first()?.1.to_string();
+
+    for pair in characters.windows(2) {
+        let [(x, _), (y, y_name)] = pair else {
+            continue;
+        };
+
+        let kind_label = path
+            .connections
+            .iter()
+            .filter_map(|id| state.nodes.get_connection(*id))
+            .find(|conn| (conn.a.0 == *x && conn.b.0 == *y) || (conn.a.0 == *y && conn.b.0 == *x))
+            .and_then(|conn| conn.kind)
+            .and_then(|kind| state.relationships.get(kind))
+            .map(|kind| kind.label.clone());
+
+        let relation = kind_label
+            .or_else(|| {
+                kinship::relationship_between(&state.nodes, *x, *y).map(|kin| format!("{kin} of"))
+            })
+            .unwrap_or_else(|| "connected to".to_string());
+
+        chain.push_str(&format!(" → {relation} → {y_name}"));
+    }
+
+    Some(chain)
+}
+
+/// Inserts nodes as one undoable step and selects them.
+fn insert_nodes(
+    state: &mut State,
+    subgraph: Subgraph<Node, RelativeAttachment<line_styles::AxisAligned>>,
+    position: Point,
+) {
+    let ids = state.nodes.insert_subgraph(subgraph, position);
+
+    state.nodes.clear_selection();
+
+    state.history.begin();
+    for id in ids {
+        state.nodes.select(id);
+
+        if let Some(added) = state.nodes.snapshot(id) {
+            state.history.record(Command::AddNode(added));
+        }
+    }
+    state.history.commit();
+}
+
+fn record_connection(state: &mut State, connection_id: Option<ConnectionId>) {
+    if let Some(connection) = connection_id.and_then(|id| state.nodes.get_connection(id)) {
+        state.history.record(Command::Connect(connection.clone()));
+    }
+}
+
+fn subscription(state: &State) -> Subscription<Message> {
+    let watcher = match state.assets.folder() {
+        Some(folder) => assets::watch(folder.clone()).map(|event| {
+            Message::AssetsMessage(match event {
+                Ok(event) => AssetsMessage::FileChanged(event),
+                Err(err) => AssetsMessage::WatchFailed(err),
+            })
+        }),
+        None => Subscription::none(),
+    };
+
+    // restarts whenever the project becomes dirty, so the interval counts from the first change
+    let autosave = match state.config.autosave {
+        Some(interval) if state.dirty && state.assets.folder().is_some() => {
+            iced::time::every(interval).map(|_| Message::Autosave)
+        }
+        _ => Subscription::none(),
+    };
+
     Subscription::batch([
         keyboard::on_key_press(|key, modifiers| match (modifiers, key) {
             (Modifiers::CTRL, Key::Character(char)) if char.eq("s") => Some(Message::Save),
+            (Modifiers::CTRL, Key::Character(char)) if char.eq("n") => {
+                Some(Message::OpenNewProjectDialog)
+            }
             (Modifiers::CTRL, Key::Character(char)) if char.eq("o") => {
                 Some(Message::OpenLoadFolderDialog)
             }
-            (Modifiers::CTRL, Key::Character(char)) if char.eq("t") => Some(Message::TraverseGraph),
+            (Modifiers::CTRL, Key::Character(char)) if char.eq("z") => Some(Message::Undo),
+            (Modifiers::CTRL, Key::Character(char)) if char.eq("h") => Some(Message::HighlightPath),
+            (mods, Key::Character(char))
+                if mods == Modifiers::CTRL | Modifiers::SHIFT && char.eq_ignore_ascii_case("z") =>
+            {
+                Some(Message::Redo)
+            }
+            // pasting over the graph is handled by the graph
+            (Modifiers::CTRL, Key::Character(char)) if char.eq("v") => Some(Message::PasteImage {
+                position: None,
+                quiet: true,
+            }),
             (Modifiers::CTRL, Key::Character(char)) if char.eq("a") => {
                 Some(Message::GraphEvent(GraphEvent::SelectAll))
             }
@@ -817,5 +2455,8 @@ fn subscription(_state: &State) -> Subscription<Message> {
             _ => None,
         }),
         iced::time::every(Duration::from_millis(20)).map(|_| Message::Tick),
+        autosave,
+        watcher,
+        window::close_requests().map(Message::CloseRequested),
     ])
 }
@@ -1,6 +1,6 @@
 use crate::graph::connections::Edge;
 use crate::graph::line_styles::AxisAligned;
-use crate::graph::{GraphNode, RelativeAttachment, line_styles};
+use crate::graph::{GraphNode, NodeId, RelativeAttachment, line_styles};
 use crate::{Node, widgets::*};
 
 use iced::{Rectangle, Size, Vector};
@@ -10,27 +10,26 @@ use crate::graph::GraphData;
 #[allow(clippy::type_complexity)]
 pub fn family_tree(
     prev: Option<(
-        usize,
+        NodeId,
         &GraphNode<Node>,
         &RelativeAttachment<AxisAligned>,
         &RelativeAttachment<AxisAligned>,
         Size<f32>,
     )>,
-    id: usize,
+    id: NodeId,
     node: &GraphNode<Node>,
     size: iced::Size,
     data: &GraphData<Node, RelativeAttachment<line_styles::AxisAligned>>,
     layout: &iced::advanced::Layout<'_>,
-    visited: Vec<usize>,
+    visited: Vec<NodeId>,
 ) -> Vector {
     let Some((prev_id, prev, attachment, prev_attachment, prev_size)) = prev else {
         let total_covered_space = visited
             .iter()
             .filter_map(|other_id| {
                 (other_id != &id).then_some(()).and_then(|_| {
-                    layout
-                        .children()
-                        .nth(*other_id)
+                    data.index_of(*other_id)
+                        .and_then(|i| layout.children().nth(i))
                         .map(|layout| layout.bounds())
                 })
             })
@@ -61,7 +60,7 @@ pub fn family_tree(
                             )
                             .then_some((
                                 conn.1,
-                                layout.children().nth(conn.1).unwrap().bounds().size(),
+                                node_size(data, layout, conn.1),
                                 None,
                             ))
                         })
@@ -138,7 +137,7 @@ pub fn family_tree(
                                 )
                                 .then_some((
                                     conn.1,
-                                    layout.children().nth(conn.1).unwrap().bounds().size(),
+                                    node_size(data, layout, conn.1),
                                     false,
                                 ))
                             })
@@ -160,8 +159,7 @@ pub fn family_tree(
                                         continue;
                                     };
 
-                                    let partner_size =
-                                        layout.children().nth(partner_id).unwrap().bounds().size();
+                                    let partner_size = node_size(data, layout, partner_id);
 
                                     let elem = (partner_id, partner_size, true);
 
@@ -197,10 +195,10 @@ pub fn family_tree(
 }
 
 fn add_partners_to_children<S: graph::line_styles::LineStyle + PartialEq + Send>(
-    children: &mut Vec<(usize, Size, Option<usize>)>,
+    children: &mut Vec<(NodeId, Size, Option<NodeId>)>,
     data: &GraphData<Node, RelativeAttachment<S>>,
     layout: &iced::advanced::Layout<'_>,
-    visited: &[usize],
+    visited: &[NodeId],
 ) -> usize {
     let mut num_visited_partners = 0;
 
@@ -218,7 +216,7 @@ fn add_partners_to_children<S: graph::line_styles::LineStyle + PartialEq + Send>
                     continue;
                 };
 
-                let partner_size = layout.children().nth(partner_id).unwrap().bounds().size();
+                let partner_size = node_size(data, layout, partner_id);
 
                 if !children.iter().any(|(id, _, _)| *id == partner_id) {
                     if att.is_right() && i < children.len() {
@@ -239,3 +237,15 @@ fn add_partners_to_children<S: graph::line_styles::LineStyle + PartialEq + Send>
 
     num_visited_partners
 }
+
+fn node_size<S: graph::line_styles::LineStyle + PartialEq + Send>(
+    data: &GraphData<Node, RelativeAttachment<S>>,
+    layout: &iced::advanced::Layout<'_>,
+    id: NodeId,
+) -> Size {
+    data.index_of(id)
+        .and_then(|i| layout.children().nth(i))
+        .unwrap()
+        .bounds()
+        .size()
+}
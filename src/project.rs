@@ -0,0 +1,343 @@
+use std::time::SystemTime;
+
+use anyhow::{Result, anyhow};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    Node,
+    graph::{GraphData, RelativeAttachment, line_styles::AxisAligned},
+    relationships::Relationships,
+};
+
+type FamilyTree = GraphData<Node, RelativeAttachment<AxisAligned>>;
+
+/// Version of the data.ron documents written by this build.
+///
+/// 0. Just the graph.
+/// 1. The graph and relationship kinds, still without a version.
+/// 2. Adds `format_version`.
+/// 3. Adds `metadata`.
+pub const FORMAT_VERSION: u32 = 3;
+
+/// Upgrades a document by one version, the step at index `n` reads version `n` and writes
+/// version `n + 1`. When the graph types change, the step from the version before the change has
+/// to keep a copy of the old types around to read it.
+const MIGRATIONS: [fn(&str) -> Result<String>; FORMAT_VERSION as usize] =
+    [v0_to_v1, v1_to_v2, v2_to_v3];
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ProjectError {
+    #[error(
+        "The project was saved by a newer version of the app (format version {0}), this version \
+        can only open projects up to format version {FORMAT_VERSION}."
+    )]
+    NewerVersion(u32),
+    #[error("The file isn't a project of any known format version.")]
+    UnknownFormat,
+}
+
+/// Describes the project as a whole, filled in when it's created.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Metadata {
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    /// `None` for projects from before metadata existed.
+    #[serde(default)]
+    pub created: Option<SystemTime>,
+}
+
+impl Metadata {
+    pub fn new(title: impl Into<String>, author: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            author: author.into(),
+            created: Some(SystemTime::now()),
+        }
+    }
+}
+
+/// Contents of a project's data.ron.
+#[derive(Debug, Deserialize)]
+pub struct ProjectData {
+    pub nodes: FamilyTree,
+    #[serde(default)]
+    pub relationships: Relationships,
+    #[serde(default)]
+    pub metadata: Metadata,
+}
+
+/// Borrowed version of [`ProjectData`] for saving without cloning the graph.
+#[derive(Serialize)]
+struct ProjectDataRef<'a> {
+    format_version: u32,
+    metadata: &'a Metadata,
+    nodes: &'a FamilyTree,
+    relationships: &'a Relationships,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    /// 0 if the field is missing, documents that have it start at version 2.
+    #[serde(default)]
+    format_version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct V1 {
+    nodes: FamilyTree,
+    relationships: Relationships,
+}
+
+#[derive(Serialize, Deserialize)]
+struct V2 {
+    format_version: u32,
+    nodes: FamilyTree,
+    relationships: Relationships,
+}
+
+fn v0_to_v1(raw_data: &str) -> Result<String> {
+    let nodes: FamilyTree = ron::from_str(raw_data)?;
+
+    Ok(ron::to_string(&V1 {
+        nodes,
+        relationships: Relationships::default(),
+    })?)
+}
+
+fn v1_to_v2(raw_data: &str) -> Result<String> {
+    let V1 {
+        nodes,
+        relationships,
+    } = ron::from_str(raw_data)?;
+
+    Ok(ron::to_string(&V2 {
+        format_version: 2,
+        nodes,
+        relationships,
+    })?)
+}
+
+fn v2_to_v3(raw_data: &str) -> Result<String> {
+    let V2 {
+        nodes,
+        relationships,
+        ..
+    } = ron::from_str(raw_data)?;
+
+    Ok(ron::to_string(&ProjectDataRef {
+        format_version: 3,
+        metadata: &Metadata::default(),
+        nodes: &nodes,
+        relationships: &relationships,
+    })?)
+}
+
+/// Version of a document, guessing between the versions from before the field existed. When
+/// no version fits, the error of the one that got furthest into the document is kept, as it's
+/// most likely the version the document was meant to be.
+fn format_version(raw_data: &str) -> Result<u32> {
+    let header = match ron::from_str::<Header>(raw_data) {
+        Ok(Header { format_version }) if format_version > 0 => return Ok(format_version),
+        Ok(_) => None,
+        Err(err) => Some(err),
+    };
+
+    let v1 = match ron::from_str::<V1>(raw_data) {
+        Ok(_) => return Ok(1),
+        Err(err) => err,
+    };
+
+    let v0 = match ron::from_str::<FamilyTree>(raw_data) {
+        Ok(_) => return Ok(0),
+        Err(err) => err,
+    };
+
+    let err = header
+        .into_iter()
+        .chain([v1, v0])
+        .max_by_key(|err| (err.position.line, err.position.col))
+        .ok_or(ProjectError::UnknownFormat)?;
+
+    Err(anyhow!(err).context(ProjectError::UnknownFormat))
+}
+
+/// Reads a data.ron of any version up to [`FORMAT_VERSION`], migrating it on the way. Also
+/// returns the version the document was in.
+pub fn load(raw_data: &str) -> Result<(ProjectData, u32)> {
+    let version = format_version(raw_data)?;
+
+    if version > FORMAT_VERSION {
+        return Err(anyhow!(ProjectError::NewerVersion(version)));
+    }
+
+    let migrated = MIGRATIONS[version as usize..]
+        .iter()
+        .try_fold(raw_data.to_string(), |data, migrate| migrate(&data))?;
+
+    Ok((ron::from_str(&migrated)?, version))
+}
+
+pub fn save(
+    nodes: &FamilyTree,
+    relationships: &Relationships,
+    metadata: &Metadata,
+) -> Result<String> {
+    let data = ProjectDataRef {
+        format_version: FORMAT_VERSION,
+        metadata,
+        nodes,
+        relationships,
+    };
+
+    Ok(ron::ser::to_string_pretty(&data, PrettyConfig::new())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Character, assets::AssetHandle};
+
+    const V0: &str = include_str!("../tests/fixtures/project/v0/data.ron");
+    const V1: &str = include_str!("../tests/fixtures/project/v1/data.ron");
+    const V2: &str = include_str!("../tests/fixtures/project/v2/data.ron");
+
+    fn names(data: &ProjectData) -> Vec<&str> {
+        data.nodes
+            .iter()
+            .filter_map(|node| match node.data() {
+                Node::Character(chara) => Some(chara.name.as_str()),
+                Node::Family => None,
+            })
+            .collect()
+    }
+
+    fn label(data: &ProjectData, label: &str) -> bool {
+        data.relationships
+            .iter()
+            .any(|(_, relationship)| relationship.label == label)
+    }
+
+    /// Loads the document, checks it was read as `version` and that saving and loading it again
+    /// doesn't need a migration.
+    fn migrate(raw_data: &str, version: u32) -> ProjectData {
+        let (data, read_version) = load(raw_data).unwrap();
+        assert_eq!(read_version, version);
+
+        let saved = save(&data.nodes, &data.relationships, &data.metadata).unwrap();
+        let (resaved, saved_version) = load(&saved).unwrap();
+        assert_eq!(saved_version, FORMAT_VERSION);
+        assert_eq!(names(&resaved), names(&data));
+
+        data
+    }
+
+    #[test]
+    fn migrates_v0() {
+        let data = migrate(V0, 0);
+
+        assert_eq!(names(&data), ["Anna", "Ben", "Carl"]);
+        assert_eq!(data.nodes.connections().count(), 3);
+        assert!(label(&data, "Siblings"));
+        assert!(data.metadata.created.is_none());
+    }
+
+    #[test]
+    fn migrates_v1() {
+        let data = migrate(V1, 1);
+
+        assert_eq!(names(&data), ["Anna", "Ben", "Carl"]);
+        assert!(label(&data, "Pen pals"));
+
+        let kinds: Vec<_> = data
+            .nodes
+            .connections()
+            .filter_map(|conn| conn.kind)
+            .collect();
+        assert_eq!(kinds.len(), 1);
+        assert_eq!(
+            data.relationships
+                .get(kinds[0])
+                .map(|kind| kind.label.as_str()),
+            Some("Pen pals")
+        );
+    }
+
+    #[test]
+    fn migrates_v2() {
+        let data = migrate(V2, 2);
+
+        assert_eq!(names(&data), ["Anna", "Ben", "Carl"]);
+        assert!(label(&data, "Pen pals"));
+        assert_eq!(data.nodes.connections().count(), 4);
+    }
+
+    #[test]
+    fn reads_the_current_version() {
+        let mut nodes = FamilyTree::default();
+        nodes.add(
+            Node::Character(Character::new("Anna", AssetHandle::NONE)),
+            iced::Point::ORIGIN,
+        );
+        let metadata = Metadata::new("The Bergs", "Anna");
+
+        let saved = save(&nodes, &Relationships::default(), &metadata).unwrap();
+        let (data, version) = load(&saved).unwrap();
+
+        assert_eq!(version, FORMAT_VERSION);
+        assert_eq!(names(&data), ["Anna"]);
+        assert_eq!(data.metadata.title, "The Bergs");
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let raw_data = V2.replace("format_version: 2", "format_version: 99");
+        let err = load(&raw_data).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<ProjectError>(),
+            Some(&ProjectError::NewerVersion(99))
+        );
+    }
+
+    #[test]
+    fn unknown_formats_keep_the_ron_error() {
+        // a typo in the last relationship kind, which only the v1 layout gets to
+        let raw_data = V1.replace(
+            "pattern: Dashed,\n            ),\n        },",
+            "pattern: Wavy,\n            ),\n        },",
+        );
+        let err = load(&raw_data).unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<ProjectError>(),
+            Some(&ProjectError::UnknownFormat)
+        );
+
+        let ron_err = err
+            .chain()
+            .find_map(|err| err.downcast_ref::<ron::error::SpannedError>())
+            .unwrap();
+        let line = V1
+            .lines()
+            .position(|line| line.contains("Dashed,"))
+            .unwrap();
+
+        assert!(ron_err.position.line > line, "{err:#}");
+        assert!(format!("{err:#}").contains("Wavy"), "{err:#}");
+    }
+
+    #[test]
+    fn syntax_errors_keep_the_ron_error() {
+        let err = load("(nodes: [").unwrap_err();
+
+        assert_eq!(
+            err.downcast_ref::<ProjectError>(),
+            Some(&ProjectError::UnknownFormat)
+        );
+        assert!(err.root_cause().is::<ron::error::SpannedError>());
+    }
+}
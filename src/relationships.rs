@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+
+use iced::{
+    Alignment, Color, Element,
+    Length::Fill,
+    widget::{button, column, container, pick_list, row, text, text_input},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graph::{ConnectionKind, ConnectionStyle, LinePattern},
+    style,
+    widgets::icons,
+};
+
+/// Colours offered when editing a relationship kind.
+const COLORS: [[u8; 3]; 8] = [
+    [0x4c, 0x8e, 0xda],
+    [0x3f, 0xa3, 0x6b],
+    [0x2a, 0xa1, 0x98],
+    [0xd9, 0x4f, 0x4f],
+    [0xe8, 0x8a, 0x2e],
+    [0x9b, 0x5d, 0xc8],
+    [0xc9, 0x4f, 0x9b],
+    [0x8a, 0x8a, 0x8a],
+];
+
+/// A user defined type of relationship between two characters, like siblings or rivals.
+/// Partners and children are expressed through family nodes instead.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationshipKind {
+    pub label: String,
+    pub color: [u8; 3],
+    pub pattern: LinePattern,
+}
+
+impl RelationshipKind {
+    pub fn new(label: impl Into<String>, color: [u8; 3], pattern: LinePattern) -> Self {
+        Self {
+            label: label.into(),
+            color,
+            pattern,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::from_rgb8(r, g, b)
+    }
+
+    pub fn style(&self) -> ConnectionStyle {
+        ConnectionStyle {
+            color: Some(self.color()),
+            pattern: self.pattern,
+            label: Some(self.label.clone()),
+        }
+    }
+}
+
+/// The relationship kinds of a project. Connections refer to them through their
+/// [`ConnectionKind`], connections without one are the implicit family connections.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relationships {
+    kinds: BTreeMap<ConnectionKind, RelationshipKind>,
+    next_id: u32,
+}
+
+impl Default for Relationships {
+    fn default() -> Self {
+        let kinds: BTreeMap<_, _> = [
+            RelationshipKind::new("Siblings", COLORS[0], LinePattern::Solid),
+            RelationshipKind::new("Adopted", COLORS[1], LinePattern::Dashed),
+            RelationshipKind::new("Guardian", COLORS[2], LinePattern::Dotted),
+            RelationshipKind::new("Divorced", COLORS[3], LinePattern::Dashed),
+            RelationshipKind::new("Rivals", COLORS[4], LinePattern::Dotted),
+            RelationshipKind::new("Mentor", COLORS[5], LinePattern::Solid),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, kind)| (ConnectionKind(i as u32), kind))
+        .collect();
+
+        Self {
+            next_id: kinds.len() as u32,
+            kinds,
+        }
+    }
+}
+
+impl Relationships {
+    pub fn get(&self, kind: ConnectionKind) -> Option<&RelationshipKind> {
+        self.kinds.get(&kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ConnectionKind, &RelationshipKind)> {
+        self.kinds.iter().map(|(id, kind)| (*id, kind))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    pub fn add(&mut self, kind: RelationshipKind) -> ConnectionKind {
+        let id = ConnectionKind(self.next_id);
+        self.next_id += 1;
+
+        self.kinds.insert(id, kind);
+
+        id
+    }
+
+    /// Connections that still use the removed kind are drawn like family connections.
+    pub fn remove(&mut self, kind: ConnectionKind) -> Option<RelationshipKind> {
+        self.kinds.remove(&kind)
+    }
+
+    pub fn style(&self, kind: Option<ConnectionKind>) -> ConnectionStyle {
+        kind.and_then(|kind| self.get(kind))
+            .map(RelationshipKind::style)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RelationshipsMessage {
+    Add,
+    Remove(ConnectionKind),
+    LabelChanged(ConnectionKind, String),
+    ColorSelected(ConnectionKind, [u8; 3]),
+    PatternSelected(ConnectionKind, LinePattern),
+}
+
+pub fn update(relationships: &mut Relationships, message: RelationshipsMessage) {
+    match message {
+        RelationshipsMessage::Add => {
+            let color = COLORS[relationships.kinds.len() % COLORS.len()];
+
+            relationships.add(RelationshipKind::new(
+                "New relationship",
+                color,
+                LinePattern::Solid,
+            ));
+        }
+        RelationshipsMessage::Remove(kind) => {
+            relationships.remove(kind);
+        }
+        RelationshipsMessage::LabelChanged(kind, label) => {
+            if let Some(kind) = relationships.kinds.get_mut(&kind) {
+                kind.label = label;
+            }
+        }
+        RelationshipsMessage::ColorSelected(kind, color) => {
+            if let Some(kind) = relationships.kinds.get_mut(&kind) {
+                kind.color = color;
+            }
+        }
+        RelationshipsMessage::PatternSelected(kind, pattern) => {
+            if let Some(kind) = relationships.kinds.get_mut(&kind) {
+                kind.pattern = pattern;
+            }
+        }
+    }
+}
+
+pub fn view(relationships: &Relationships) -> Element<'_, RelationshipsMessage> {
+    column(relationships.iter().map(|(id, kind)| {
+        column![
+            row![
+                text_input("Label", &kind.label)
+                    .on_input(move |label| RelationshipsMessage::LabelChanged(id, label))
+                    .style(style::text_input),
+                pick_list(LinePattern::ALL, Some(kind.pattern), move |pattern| {
+                    RelationshipsMessage::PatternSelected(id, pattern)
+                })
+                .width(90.0),
+                button(icons::close().size(12.0).center())
+                    .on_press(RelationshipsMessage::Remove(id))
+                    .padding(4.0)
+                    .style(style::menu_button),
+            ]
+            .spacing(4.0)
+            .align_y(Alignment::Center),
+            row(COLORS.into_iter().map(|color| {
+                let [r, g, b] = color;
+
+                button(
+                    container("")
+                        .width(16.0)
+                        .height(16.0)
+                        .style(style::color_swatch(Color::from_rgb8(r, g, b))),
+                )
+                .on_press(RelationshipsMessage::ColorSelected(id, color))
+                .padding(2.0)
+                .style(style::color_swatch_button(color == kind.color))
+                .into()
+            }))
+            .spacing(2.0),
+        ]
+        .spacing(4.0)
+        .into()
+    }))
+    .push(
+        button(text("Add relationship kind").size(13.0).center())
+            .on_press(RelationshipsMessage::Add)
+            .width(Fill)
+            .style(style::secondary_button),
+    )
+    .spacing(10.0)
+    .into()
+}
@@ -1,8 +1,8 @@
 use iced::border::Radius;
 use iced::gradient::{ColorStop, Linear};
-use iced::widget::{self, button, container, rule, slider};
+use iced::widget::{self, button, container, progress_bar, rule, slider};
 use iced::{Border, Color, Gradient, Radians, Shadow, Theme, Vector};
-use iced_aw::style::menu_bar;
+use iced_aw::style::{menu_bar, tab_bar};
 use palette::convert::FromColorUnclamped;
 use palette::rgb::Rgba;
 use palette::{IntoColor, LinSrgba, Mix, Oklab};
@@ -179,6 +179,53 @@ pub fn text_input(theme: &Theme, status: widget::text_input::Status) -> widget::
     }
 }
 
+pub fn text_editor(
+    theme: &Theme,
+    status: widget::text_editor::Status,
+) -> widget::text_editor::Style {
+    use widget::text_editor::Status;
+
+    let input = text_input(
+        theme,
+        match status {
+            Status::Active => widget::text_input::Status::Active,
+            Status::Hovered => widget::text_input::Status::Hovered,
+            Status::Focused => widget::text_input::Status::Focused,
+            Status::Disabled => widget::text_input::Status::Disabled,
+        },
+    );
+
+    widget::text_editor::Style {
+        background: input.background,
+        border: input.border,
+        icon: input.icon,
+        placeholder: input.placeholder,
+        value: input.value,
+        selection: input.selection,
+    }
+}
+
+pub fn tag(theme: &Theme) -> container::Style {
+    let palette = theme.extended_palette();
+
+    container::Style {
+        background: Some(
+            mix_colors(
+                palette.background.base.color,
+                palette.primary.base.color,
+                0.2,
+            )
+            .into(),
+        ),
+        border: Border {
+            color: palette.primary.weak.color,
+            width: 1.0,
+            radius: Radius::new(10.0),
+        },
+        ..Default::default()
+    }
+}
+
 pub fn menu_bar(theme: &Theme, _status: iced_aw::style::Status) -> menu_bar::Style {
     let palette = theme.extended_palette();
 
@@ -231,6 +278,46 @@ pub fn menu_bar(theme: &Theme, _status: iced_aw::style::Status) -> menu_bar::Sty
     }
 }
 
+/// The selected tab is `Active`, the others are `Disabled`.
+pub fn tab_bar(theme: &Theme, status: iced_aw::style::Status) -> tab_bar::Style {
+    let palette = theme.extended_palette();
+
+    let (background, text_color) = match status {
+        iced_aw::style::Status::Active => (
+            mix_colors(
+                palette.background.base.color,
+                palette.primary.base.color,
+                0.3,
+            ),
+            palette.background.base.text,
+        ),
+        iced_aw::style::Status::Hovered => (
+            mix_colors(
+                palette.background.base.color,
+                palette.primary.base.color,
+                0.15,
+            ),
+            palette.background.base.text,
+        ),
+        _ => (
+            palette.background.base.color,
+            palette.background.base.text.scale_alpha(0.8),
+        ),
+    };
+
+    tab_bar::Style {
+        background: None,
+        border_color: None,
+        border_width: 0.0,
+        tab_label_background: background.into(),
+        tab_label_border_color: palette.background.weak.color,
+        tab_label_border_width: 1.0,
+        icon_color: text_color,
+        text_color,
+        ..Default::default()
+    }
+}
+
 pub fn menu_button(theme: &Theme, status: button::Status) -> button::Style {
     let palette = theme.extended_palette();
 
@@ -250,6 +337,32 @@ pub fn menu_button(theme: &Theme, status: button::Status) -> button::Style {
     }
 }
 
+pub fn color_swatch<'a>(color: Color) -> container::StyleFn<'a, Theme> {
+    Box::new(move |_theme: &Theme| container::Style {
+        background: Some(color.into()),
+        border: Border::default().rounded(8.0),
+        ..Default::default()
+    })
+}
+
+pub fn color_swatch_button<'a>(selected: bool) -> button::StyleFn<'a, Theme> {
+    Box::new(move |theme: &Theme, status| {
+        let style = menu_button(theme, status);
+
+        if !selected {
+            return style;
+        }
+
+        button::Style {
+            border: Border::default()
+                .rounded(10.0)
+                .width(2.0)
+                .color(theme.extended_palette().primary.strong.color),
+            ..style
+        }
+    })
+}
+
 pub fn dropdown(theme: &Theme) -> container::Style {
     let palette = theme.extended_palette();
 
@@ -445,6 +558,15 @@ pub fn node<'a>(selected: bool) -> container::StyleFn<'a, Theme> {
     })
 }
 
+pub fn family_node(theme: &Theme) -> container::Style {
+    container::Style {
+        text_color: None,
+        background: Some(theme.palette().success.into()),
+        border: Border::default().rounded(10.0),
+        ..Default::default()
+    }
+}
+
 /// (bg_gradient_start, bg_gradient_end, text_color)
 fn notification_bg_colors(theme: &Theme, severity: Severity) -> (Color, Color, Color) {
     let palette = theme.extended_palette();
@@ -711,6 +833,30 @@ pub fn info_bar_border(theme: &Theme) -> container::Style {
     }
 }
 
+pub fn loading_progress_bar(theme: &Theme) -> progress_bar::Style {
+    let palette = theme.extended_palette();
+
+    progress_bar::Style {
+        background: palette.secondary.base.color.scale_alpha(0.5).into(),
+        bar: Gradient::Linear(Linear::new(Radians::PI / 2.0).add_stops([
+            ColorStop {
+                offset: 0.0,
+                color: palette.primary.strong.color,
+            },
+            ColorStop {
+                offset: 1.0,
+                color: mix_colors(
+                    palette.primary.weak.color,
+                    palette.primary.strong.color,
+                    0.3,
+                ),
+            },
+        ]))
+        .into(),
+        border: Border::default().rounded(3.0),
+    }
+}
+
 pub fn info_bar_zoom_slider(theme: &Theme, status: slider::Status) -> slider::Style {
     let palette = theme.extended_palette();
 
@@ -6,20 +6,20 @@ pub mod icons;
 
 pub use dialog::dialog;
 use iced::{
-    Alignment, Border, Element, Font,
+    Alignment, Element, Font,
     Length::Fill,
-    Padding, Theme,
+    Padding, Size,
     advanced::widget::Text,
     font::Weight,
     widget::{
         Container, button, column, container, horizontal_rule, horizontal_space, image, mouse_area,
-        opaque, row, scrollable, text,
+        opaque, row, scrollable, stack, text,
     },
 };
 use iced_aw::DropDown;
 
 use crate::{
-    Message, Node,
+    Character, Message, Node,
     assets::{AssetsData, Image, image::default_image},
     notification::Notification,
     style,
@@ -32,7 +32,10 @@ pub fn base_button<'a, Message>(
     button(content).padding([4, 8]).style(style::primary_button)
 }
 
-pub fn menu_button<'a, Message>(label: &'a str, message: Message) -> button::Button<'a, Message> {
+pub fn menu_button<'a, Message>(
+    label: impl text::IntoFragment<'a>,
+    message: Message,
+) -> button::Button<'a, Message> {
     let mut font = Font::DEFAULT;
     font.weight = Weight::Medium;
 
@@ -42,7 +45,7 @@ pub fn menu_button<'a, Message>(label: &'a str, message: Message) -> button::But
 }
 
 pub fn menu_item_button<'a, Message: 'a>(
-    label: &'a str,
+    label: impl text::IntoFragment<'a>,
     flavor_text: Option<&'a str>,
 ) -> button::Button<'a, Message> {
     let mut font = Font::DEFAULT;
@@ -70,10 +73,6 @@ pub fn notification(i: usize, notification: &Notification) -> Container<'_, Mess
 
     title_font.weight = Weight::Bold;
 
-    let mut x_font = Font::DEFAULT;
-
-    x_font.weight = Weight::Black;
-
     let header = container(
         row![
             text(&notification.title)
@@ -176,6 +175,12 @@ pub fn dropdown<'a, Message: Clone + 'a>(
         .on_dismiss(show_hide_dropdown)
 }
 
+pub const CHARACTER_SIZE: Size = Size::new(150.0, 150.0);
+pub const FAMILY_SIZE: Size = Size::new(10.0, 10.0);
+/// Space between a character node's border and its content, also used between the image and
+/// the text below it.
+pub const NODE_PADDING: f32 = 5.0;
+
 #[allow(clippy::type_complexity)]
 pub fn node<'a>(
     assets: &'a AssetsData,
@@ -183,41 +188,50 @@ pub fn node<'a>(
     Box::new(|node| match node.data() {
         Node::Character(chara) => container(
             column![
-                image(
-                    assets
-                        .get(chara.img)
-                        .and_then(|asset| Image::try_from(asset).ok())
-                        .map(|img| img.handle)
-                        .unwrap_or(default_image())
-                )
-                .width(Fill)
-                .height(Fill)
-                .filter_method(image::FilterMethod::Nearest),
+                stack![
+                    image(
+                        assets
+                            .get(chara.img)
+                            .and_then(|asset| Image::try_from(asset).ok())
+                            .map(|img| img.cropped.unwrap_or(img.handle))
+                            .unwrap_or(default_image())
+                    )
+                    .width(Fill)
+                    .height(Fill)
+                    .filter_method(image::FilterMethod::Nearest),
+                ]
+                .push_maybe(assets.is_missing(chara.img).then(|| {
+                    container(text("Image missing").size(12.0).style(text::danger)).center(Fill)
+                })),
                 opaque(
                     column![
                         text(&chara.name).center().width(Fill),
-                        base_button("ahkdlfjs").on_press(Message::CharacterButtonPressed)
+                        text(lifespan(chara)).size(12.0).center().width(Fill)
                     ]
                     .width(Fill)
-                    .spacing(5.0)
+                    .spacing(NODE_PADDING)
                 ),
             ]
-            .spacing(5.0),
+            .spacing(NODE_PADDING),
         )
-        .width(150.0)
-        .height(150.0)
-        .padding(5.0)
+        .width(CHARACTER_SIZE.width)
+        .height(CHARACTER_SIZE.height)
+        .padding(NODE_PADDING)
         .style(style::node(node.selected()))
         .into(),
         Node::Family => container("")
-            .width(10.0)
-            .height(10.0)
-            .style(|theme: &Theme| container::Style {
-                text_color: None,
-                background: Some(theme.palette().success.into()),
-                border: Border::default().rounded(10.0),
-                ..Default::default()
-            })
+            .width(FAMILY_SIZE.width)
+            .height(FAMILY_SIZE.height)
+            .style(style::family_node)
             .into(),
     })
 }
+
+pub fn lifespan(chara: &Character) -> String {
+    match (chara.birth.trim(), chara.death.trim()) {
+        ("", "") => String::new(),
+        (birth, "") => format!("* {birth}"),
+        ("", death) => format!("† {death}"),
+        (birth, death) => format!("{birth} – {death}"),
+    }
+}
@@ -4,8 +4,8 @@ use iced::{
     Theme,
     font::Weight,
     widget::{
-        button, column, container, horizontal_space, mouse_area, opaque, row, stack, text,
-        vertical_space,
+        button, column, container, horizontal_space, mouse_area, opaque, row, scrollable, stack,
+        text, vertical_space,
     },
 };
 
@@ -59,6 +59,11 @@ impl<Message> Dialog<Message> {
             options,
         }
     }
+
+    /// What closing the dialog without picking an option sends.
+    pub fn on_cancel(&self) -> &Message {
+        &self.on_cancel
+    }
 }
 
 pub fn dialog<'a, Message: Clone>(
@@ -68,6 +73,11 @@ pub fn dialog<'a, Message: Clone>(
     let mut title_font = Font::DEFAULT;
     title_font.weight = Weight::Bold;
 
+    // more options than fit next to each other get listed above the cancel button
+    let list_options = dialog
+        .as_ref()
+        .is_some_and(|dialog| dialog.options.len() > 2);
+
     stack!(content.into())
         .push_maybe(dialog.clone().map(|dialog| {
             opaque(
@@ -81,7 +91,23 @@ pub fn dialog<'a, Message: Clone>(
                                         color: Some(theme.palette().text),
                                     }
                                 }),
-                                vertical_space(),
+                            ]
+                            .push_maybe((!list_options).then(vertical_space))
+                            .push_maybe(list_options.then(|| {
+                                scrollable(
+                                    column(
+                                        dialog
+                                            .options
+                                            .iter()
+                                            .cloned()
+                                            .map(|opt| option_button(opt).width(Fill).into()),
+                                    )
+                                    .spacing(6.0),
+                                )
+                                .style(style::scrollable)
+                                .height(Fill)
+                            }))
+                            .push(
                                 row![
                                     button(text("Cancel").center())
                                         .on_press(dialog.on_cancel.clone())
@@ -89,21 +115,17 @@ pub fn dialog<'a, Message: Clone>(
                                         .style(style::secondary_button),
                                     horizontal_space(),
                                 ]
-                                .extend(dialog.options.iter().cloned().map(|opt| {
-                                    button(text(opt.text).center())
-                                        .on_press(opt.on_press)
-                                        .height(40.0)
-                                        .style(match opt.severity {
-                                            Severity::Success => style::success_button,
-                                            Severity::Neutral => style::primary_button,
-                                            Severity::Warn => style::danger_button,
-                                            Severity::Destructive => style::danger_button,
-                                        })
-                                        .into()
-                                }))
+                                .extend(
+                                    dialog
+                                        .options
+                                        .iter()
+                                        .filter(|_| !list_options)
+                                        .cloned()
+                                        .map(|opt| option_button(opt).into()),
+                                )
                                 .spacing(10.0)
-                                .width(Fill)
-                            ]
+                                .width(Fill),
+                            )
                             .height(Fill)
                             .spacing(4.0),
                         )
@@ -129,3 +151,17 @@ pub fn dialog<'a, Message: Clone>(
         }))
         .into()
 }
+
+fn option_button<'a, Message: Clone + 'a>(
+    option: DialogOption<Message>,
+) -> button::Button<'a, Message> {
+    button(text(option.text).center())
+        .on_press(option.on_press)
+        .height(40.0)
+        .style(match option.severity {
+            Severity::Success => style::success_button,
+            Severity::Neutral => style::primary_button,
+            Severity::Warn => style::danger_button,
+            Severity::Destructive => style::danger_button,
+        })
+}
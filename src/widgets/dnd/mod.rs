@@ -1,11 +1,15 @@
+mod file_receiver;
 mod indicator;
 mod provider;
 mod receiver;
 
+use std::path::PathBuf;
+
 use iced::{Element, Point};
 
 use crate::widgets::dnd::{
-    indicator::DragAndDropIndicator, provider::DragAndDropProvider, receiver::DragAndDropReceiver,
+    file_receiver::FileDropReceiver, indicator::DragAndDropIndicator,
+    provider::DragAndDropProvider, receiver::DragAndDropReceiver,
 };
 
 pub fn dnd_indicator<'a, Message, Theme, Renderer>(
@@ -61,3 +65,20 @@ where
     }
     .into()
 }
+
+pub fn file_drop_receiver<'a, Message, Theme, Renderer, F>(
+    receive: F,
+    content: impl Into<Element<'a, Message, Theme, Renderer>>,
+) -> Element<'a, Message, Theme, Renderer>
+where
+    F: Fn(PathBuf, Point) -> Option<Message> + 'a,
+    Message: 'a,
+    Renderer: iced::advanced::Renderer + 'a,
+    Theme: 'a,
+{
+    FileDropReceiver {
+        receive: Box::new(receive),
+        content: content.into(),
+    }
+    .into()
+}
@@ -1,25 +1,78 @@
 use std::marker::PhantomData;
 
-use iced::{Point, Size, Vector, widget::canvas::Path};
+use iced::{Color, Point, Size, Vector, widget::canvas::Path};
 use serde::{Deserialize, Serialize};
 
-use crate::graph::line_styles;
+use crate::graph::{ConnectionId, ConnectionKind, NodeId, line_styles};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
-pub(super) struct Connection<A: Attachment = RelativeAttachment> {
-    pub a: (usize, A),
-    pub b: (usize, A),
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connection<A: Attachment = RelativeAttachment> {
+    pub id: ConnectionId,
+    pub a: (NodeId, A),
+    pub b: (NodeId, A),
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<ConnectionKind>,
 }
 
 impl<A: Attachment> Connection<A> {
-    pub(super) fn new(a: usize, a_attachment: A, b: usize, b_attachment: A) -> Self {
+    pub(super) fn new(
+        id: ConnectionId,
+        a: NodeId,
+        a_attachment: A,
+        b: NodeId,
+        b_attachment: A,
+        kind: Option<ConnectionKind>,
+    ) -> Self {
         Self {
+            id,
             a: (a, a_attachment),
             b: (b, b_attachment),
+            kind,
         }
     }
 }
 
+/// Dash pattern a connection is drawn with.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinePattern {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LinePattern {
+    pub const ALL: [Self; 3] = [Self::Solid, Self::Dashed, Self::Dotted];
+
+    /// Dash segments for a line of the given width, see [`iced::widget::canvas::LineDash`].
+    pub fn segments(&self, width: f32) -> Option<[f32; 2]> {
+        match self {
+            Self::Solid => None,
+            Self::Dashed => Some([width * 3.0, width * 2.0]),
+            Self::Dotted => Some([width * 0.1, width * 2.0]),
+        }
+    }
+}
+
+impl std::fmt::Display for LinePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Solid => "Solid",
+            Self::Dashed => "Dashed",
+            Self::Dotted => "Dotted",
+        })
+    }
+}
+
+/// How a single connection gets drawn. `None` fields fall back to the theme.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ConnectionStyle {
+    pub color: Option<Color>,
+    pub pattern: LinePattern,
+    /// Shown next to the cursor while hovering the connection.
+    pub label: Option<String>,
+}
+
 pub trait Attachment: std::fmt::Debug + Clone + Send {
     fn connection_point(&self) -> Vector;
 
@@ -40,6 +93,21 @@ pub enum Edge {
     Left,
 }
 
+impl Edge {
+    pub const ALL: [Self; 4] = [Self::Top, Self::Right, Self::Bottom, Self::Left];
+}
+
+impl std::fmt::Display for Edge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Top => "top",
+            Self::Right => "right",
+            Self::Bottom => "bottom",
+            Self::Left => "left",
+        })
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum RelativeAttachment<Style = line_styles::Direct>
 where
@@ -96,6 +164,14 @@ where
         ]
     }
 
+    pub fn edge(edge: Edge, align: f32) -> Self {
+        Self::Edge {
+            edge,
+            align,
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn top() -> Self {
         Self::Edge {
             edge: Edge::Top,
@@ -1,6 +1,7 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
 
-use iced::Point;
+use iced::{Point, Size, Vector, widget::canvas::Path};
 use serde::{Deserialize, Serialize};
 
 use crate::graph::{
@@ -15,8 +16,55 @@ struct Position {
     y: f32,
 }
 
+/// Stable identifier of a node in a [`GraphData`].
+///
+/// Ids are handed out in increasing order and never reused, so they stay valid (or point to
+/// nothing) after other nodes get removed. The next id is saved with the graph, so that also
+/// holds across saving and loading it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NodeId(u32);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<NodeId> for u32 {
+    fn from(id: NodeId) -> Self {
+        id.0
+    }
+}
+
+/// Stable identifier of a connection in a [`GraphData`]. Same rules as [`NodeId`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConnectionId(u32);
+
+impl Display for ConnectionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<ConnectionId> for u32 {
+    fn from(id: ConnectionId) -> Self {
+        id.0
+    }
+}
+
+/// User defined kind of a connection. The graph only stores it, what a kind means and how it's
+/// drawn is decided by the user of the graph, see [`Graph::connection_style`].
+///
+/// [`Graph::connection_style`]: crate::graph::Graph::connection_style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ConnectionKind(pub u32);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GraphNode<D: std::fmt::Debug> {
+    pub(super) id: NodeId,
     #[serde(with = "Position")]
     pub(super) position: Point,
     pub(super) data: D,
@@ -25,14 +73,19 @@ pub struct GraphNode<D: std::fmt::Debug> {
 }
 
 impl<Data: std::fmt::Debug> GraphNode<Data> {
-    pub fn new(position: Point, data: Data) -> Self {
+    pub(super) fn new(id: NodeId, position: Point, data: Data) -> Self {
         Self {
+            id,
             position,
             data,
             selected: false,
         }
     }
 
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
     pub fn position(&self) -> Point {
         self.position
     }
@@ -55,6 +108,8 @@ impl<Data: std::fmt::Debug> GraphNode<Data> {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "SerializedGraphData<Data, Attachment>")]
+#[serde(bound(deserialize = "Data: Deserialize<'de>, Attachment: Deserialize<'de>"))]
 pub struct GraphData<Data, Attachment = RelativeAttachment>
 where
     Data: std::fmt::Debug,
@@ -62,6 +117,8 @@ where
 {
     pub(super) nodes: Vec<GraphNode<Data>>,
     pub(super) connections: Vec<Connection<Attachment>>,
+    next_node_id: u32,
+    next_connection_id: u32,
 }
 
 impl<Data: std::fmt::Debug, Attachment: connections::Attachment + std::cmp::PartialEq> Default
@@ -71,6 +128,91 @@ impl<Data: std::fmt::Debug, Attachment: connections::Attachment + std::cmp::Part
         Self {
             nodes: Vec::new(),
             connections: Vec::new(),
+            next_node_id: 0,
+            next_connection_id: 0,
+        }
+    }
+}
+
+fn some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+struct SerializedNode<D> {
+    #[serde(default, deserialize_with = "some")]
+    id: Option<NodeId>,
+    #[serde(with = "Position")]
+    position: Point,
+    data: D,
+}
+
+#[derive(Deserialize)]
+struct SerializedConnection<A> {
+    #[serde(default, deserialize_with = "some")]
+    id: Option<ConnectionId>,
+    a: (NodeId, A),
+    b: (NodeId, A),
+    #[serde(default)]
+    kind: Option<ConnectionKind>,
+}
+
+/// On-disk shape of [`GraphData`], which also accepts files written before nodes and
+/// connections had ids. Back then connections referred to nodes by their index, so the index
+/// of a node becomes its id. Files without the next ids continue after the highest one used.
+#[derive(Deserialize)]
+struct SerializedGraphData<D, A> {
+    nodes: Vec<SerializedNode<D>>,
+    connections: Vec<SerializedConnection<A>>,
+    #[serde(default)]
+    next_node_id: u32,
+    #[serde(default)]
+    next_connection_id: u32,
+}
+
+impl<Data, Attachment> From<SerializedGraphData<Data, Attachment>> for GraphData<Data, Attachment>
+where
+    Data: std::fmt::Debug,
+    Attachment: connections::Attachment + std::cmp::PartialEq,
+{
+    fn from(data: SerializedGraphData<Data, Attachment>) -> Self {
+        let nodes: Vec<_> = data
+            .nodes
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let id = node.id.unwrap_or(NodeId(i as u32));
+                GraphNode::new(id, node.position, node.data)
+            })
+            .collect();
+
+        let connections: Vec<_> = data
+            .connections
+            .into_iter()
+            .enumerate()
+            .map(|(i, conn)| Connection {
+                id: conn.id.unwrap_or(ConnectionId(i as u32)),
+                a: conn.a,
+                b: conn.b,
+                kind: conn.kind,
+            })
+            .collect();
+
+        Self {
+            next_node_id: nodes
+                .iter()
+                .map(|node| node.id.0 + 1)
+                .fold(data.next_node_id, u32::max),
+            next_connection_id: connections
+                .iter()
+                .map(|conn| conn.id.0 + 1)
+                .fold(data.next_connection_id, u32::max),
+            nodes,
+            connections,
         }
     }
 }
@@ -80,16 +222,31 @@ where
     Data: std::fmt::Debug,
     Attachment: self::Attachment + std::cmp::PartialEq,
 {
-    pub fn get(&self, id: usize) -> Option<&GraphNode<Data>> {
-        self.nodes.get(id)
+    pub fn get(&self, id: NodeId) -> Option<&GraphNode<Data>> {
+        self.nodes.iter().find(|node| node.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut GraphNode<Data>> {
+        self.nodes.iter_mut().find(|node| node.id == id)
+    }
+
+    /// Returns the position of the node in draw order, which is also the index of its layout
+    /// node inside the [`Graph`](super::Graph) widget.
+    pub fn index_of(&self, id: NodeId) -> Option<usize> {
+        self.nodes.iter().position(|node| node.id == id)
     }
 
-    pub fn get_mut(&mut self, id: usize) -> Option<&mut GraphNode<Data>> {
-        self.nodes.get_mut(id)
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.iter().any(|node| node.id == id)
     }
 
-    pub fn add(&mut self, node: Data, position: Point) {
-        self.nodes.push(GraphNode::new(position, node));
+    pub fn add(&mut self, node: Data, position: Point) -> NodeId {
+        let id = NodeId(self.next_node_id);
+        self.next_node_id += 1;
+
+        self.nodes.push(GraphNode::new(id, position, node));
+
+        id
     }
 
     pub fn attach_new(
@@ -97,42 +254,30 @@ where
         node: Data,
         position: Point,
         attachment: Attachment,
-        connection: usize,
+        connection: NodeId,
         other_attachment: Attachment,
-    ) -> Result<(), GraphError> {
-        if self.nodes.len() < connection {
+    ) -> Result<NodeId, GraphError> {
+        if !self.contains(connection) {
             return Result::Err(GraphError::NodeNotFound(connection));
         }
 
-        self.add(node, position);
+        let id = self.add(node, position);
 
-        self.connect(
-            self.nodes.len() - 1,
-            attachment,
-            connection,
-            other_attachment,
-        )
+        self.connect(id, attachment, connection, other_attachment)?;
+
+        Ok(id)
     }
 
-    pub fn get_connected_nodes(&self, id: usize) -> Vec<&GraphNode<Data>> {
-        self.connections
-            .iter()
-            .filter_map(|Connection { a, b, .. }| {
-                if a.0 == id {
-                    Some(self.get(b.0).unwrap())
-                } else if b.0 == id {
-                    Some(self.get(a.0).unwrap())
-                } else {
-                    None
-                }
-            })
+    pub fn get_connected_nodes(&self, id: NodeId) -> Vec<&GraphNode<Data>> {
+        self.get_connections(id)
+            .filter_map(|(_, other, _)| self.get(other))
             .collect()
     }
 
     pub fn get_connections(
         &self,
-        id: usize,
-    ) -> impl Iterator<Item = (&Attachment, usize, &Attachment)> {
+        id: NodeId,
+    ) -> impl Iterator<Item = (&Attachment, NodeId, &Attachment)> {
         self.connections.iter().filter_map(move |conn| {
             (conn.a.0 == id)
                 .then_some((&conn.a.1, conn.b.0, &conn.b.1))
@@ -142,86 +287,222 @@ where
 
     pub fn get_connections_indexed(
         &self,
-        id: usize,
-    ) -> impl Iterator<Item = (usize, &Attachment, usize, &Attachment)> {
-        self.connections
-            .iter()
-            .enumerate()
-            .filter_map(move |(i, conn)| {
-                (conn.a.0 == id)
-                    .then_some((i, &conn.a.1, conn.b.0, &conn.b.1))
-                    .or_else(|| (conn.b.0 == id).then_some((i, &conn.b.1, conn.a.0, &conn.a.1)))
-            })
+        id: NodeId,
+    ) -> impl Iterator<Item = (ConnectionId, &Attachment, NodeId, &Attachment)> {
+        self.connections.iter().filter_map(move |conn| {
+            (conn.a.0 == id)
+                .then_some((conn.id, &conn.a.1, conn.b.0, &conn.b.1))
+                .or_else(|| (conn.b.0 == id).then_some((conn.id, &conn.b.1, conn.a.0, &conn.a.1)))
+        })
     }
 
     pub fn connect(
         &mut self,
-        a: usize,
+        a: NodeId,
         a_attachment: Attachment,
-        b: usize,
+        b: NodeId,
         b_attachment: Attachment,
-    ) -> Result<(), GraphError> {
-        if self.nodes.len() < a {
+    ) -> Result<Option<ConnectionId>, GraphError> {
+        self.connect_with_kind(a, a_attachment, b, b_attachment, None)
+    }
+
+    pub fn connect_with_kind(
+        &mut self,
+        a: NodeId,
+        a_attachment: Attachment,
+        b: NodeId,
+        b_attachment: Attachment,
+        kind: Option<ConnectionKind>,
+    ) -> Result<Option<ConnectionId>, GraphError> {
+        if !self.contains(a) {
             return Result::Err(GraphError::NodeNotFound(a));
         }
 
-        if self.nodes.len() < b {
+        if !self.contains(b) {
             return Result::Err(GraphError::NodeNotFound(b));
         }
 
         if a == b && a_attachment == b_attachment {
-            return Ok(());
+            return Ok(None);
         }
 
+        let id = ConnectionId(self.next_connection_id);
+        self.next_connection_id += 1;
+
         self.connections
-            .push(Connection::new(a, a_attachment, b, b_attachment));
+            .push(Connection::new(id, a, a_attachment, b, b_attachment, kind));
+
+        Ok(Some(id))
+    }
+
+    /// Copies the given nodes and the connections between them. Connections to nodes outside
+    /// of `ids` are left out.
+    pub fn subgraph(&self, ids: &[NodeId]) -> Subgraph<Data, Attachment>
+    where
+        Data: Clone,
+    {
+        Subgraph {
+            nodes: self
+                .nodes
+                .iter()
+                .filter(|node| ids.contains(&node.id))
+                .cloned()
+                .collect(),
+            connections: self
+                .connections
+                .iter()
+                .filter(|conn| ids.contains(&conn.a.0) && ids.contains(&conn.b.0))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Adds a copy of `subgraph` with fresh ids, moved so that its top left corner lands on
+    /// `position`. Returns the ids of the new nodes.
+    pub fn insert_subgraph(
+        &mut self,
+        subgraph: Subgraph<Data, Attachment>,
+        position: Point,
+    ) -> Vec<NodeId> {
+        let origin = subgraph
+            .nodes
+            .iter()
+            .map(|node| node.position)
+            .reduce(|a, b| Point::new(a.x.min(b.x), a.y.min(b.y)))
+            .unwrap_or(Point::ORIGIN);
 
-        Ok(())
+        let ids: Vec<_> = subgraph
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let new_id = self.add(node.data, position + (node.position - origin));
+                (node.id, new_id)
+            })
+            .collect();
+
+        let new_id = |old| {
+            ids.iter()
+                .find_map(|(old_id, new_id)| (*old_id == old).then_some(*new_id))
+        };
+
+        for conn in subgraph.connections {
+            if let (Some(a), Some(b)) = (new_id(conn.a.0), new_id(conn.b.0)) {
+                let _ = self.connect_with_kind(a, conn.a.1, b, conn.b.1, conn.kind);
+            }
+        }
+
+        ids.into_iter().map(|(_, new_id)| new_id).collect()
     }
 
     pub fn num_nodes(&self) -> usize {
         self.nodes.len()
     }
 
-    pub fn disconnect_all(&mut self, a: usize, b: usize) {
+    pub fn disconnect_all(&mut self, a: NodeId, b: NodeId) {
         self.connections
             .retain(|conn| !((conn.a.0 == a && conn.b.0 == b) || (conn.b.0 == a && conn.a.0 == b)));
     }
 
-    pub fn remove_connection(&mut self, connection_id: usize) {
-        self.connections.remove(connection_id);
+    pub fn get_connection(&self, connection_id: ConnectionId) -> Option<&Connection<Attachment>> {
+        self.connections
+            .iter()
+            .find(|conn| conn.id == connection_id)
     }
 
-    pub fn remove(&mut self, i: usize) {
-        self.nodes.remove(i);
-        self.connections
-            .retain(|conn| conn.a.0 != i && conn.b.0 != i);
+    pub fn remove_connection(
+        &mut self,
+        connection_id: ConnectionId,
+    ) -> Option<Connection<Attachment>> {
+        let index = self
+            .connections
+            .iter()
+            .position(|conn| conn.id == connection_id)?;
 
-        self.connections.iter_mut().for_each(|conn| {
-            if conn.a.0 > i {
-                conn.a.0 -= 1;
-            }
-            if conn.b.0 > i {
-                conn.b.0 -= 1;
-            }
-        });
+        Some(self.connections.remove(index))
+    }
+
+    /// Puts a connection returned by [`GraphData::remove_connection`] back in place. Does
+    /// nothing if it already exists or if one of its nodes is gone.
+    pub fn restore_connection(&mut self, connection: Connection<Attachment>) {
+        if self.get_connection(connection.id).is_some()
+            || !self.contains(connection.a.0)
+            || !self.contains(connection.b.0)
+        {
+            return;
+        }
+
+        self.next_connection_id = self.next_connection_id.max(connection.id.0 + 1);
+        self.connections.push(connection);
+    }
+
+    pub fn remove(&mut self, id: NodeId) -> Option<RemovedNode<Data, Attachment>> {
+        let index = self.index_of(id)?;
+
+        let node = self.nodes.remove(index);
+
+        let (connections, kept) = std::mem::take(&mut self.connections)
+            .into_iter()
+            .partition(|conn| conn.a.0 == id || conn.b.0 == id);
+
+        self.connections = kept;
+
+        Some(RemovedNode {
+            index,
+            node,
+            connections,
+        })
     }
 
-    pub fn is_selected(&self, i: usize) -> Result<bool, GraphError> {
+    /// Puts a node returned by [`GraphData::remove`] back where it was, together with the
+    /// connections it had.
+    pub fn restore(&mut self, removed: RemovedNode<Data, Attachment>) {
+        if self.contains(removed.node.id) {
+            return;
+        }
+
+        self.next_node_id = self.next_node_id.max(removed.node.id.0 + 1);
         self.nodes
-            .get(i)
+            .insert(removed.index.min(self.nodes.len()), removed.node);
+
+        for connection in removed.connections {
+            self.restore_connection(connection);
+        }
+    }
+
+    /// Copies a node and its connections into the same form [`GraphData::remove`] returns,
+    /// without removing anything.
+    pub fn snapshot(&self, id: NodeId) -> Option<RemovedNode<Data, Attachment>>
+    where
+        Data: Clone,
+    {
+        let index = self.index_of(id)?;
+
+        Some(RemovedNode {
+            index,
+            node: self.nodes[index].clone(),
+            connections: self
+                .connections
+                .iter()
+                .filter(|conn| conn.a.0 == id || conn.b.0 == id)
+                .cloned()
+                .collect(),
+        })
+    }
+
+    pub fn is_selected(&self, id: NodeId) -> Result<bool, GraphError> {
+        self.get(id)
             .map(GraphNode::selected)
-            .ok_or(GraphError::NodeNotFound(i))
+            .ok_or(GraphError::NodeNotFound(id))
     }
 
-    pub fn select(&mut self, i: usize) {
-        if let Some(node) = self.nodes.get_mut(i) {
+    pub fn select(&mut self, id: NodeId) {
+        if let Some(node) = self.get_mut(id) {
             node.selected = true;
         }
     }
 
-    pub fn deselect(&mut self, i: usize) {
-        if let Some(node) = self.nodes.get_mut(i) {
+    pub fn deselect(&mut self, id: NodeId) {
+        if let Some(node) = self.get_mut(id) {
             node.selected = false;
         }
     }
@@ -238,27 +519,30 @@ where
         }
     }
 
-    pub fn selection(&self) -> impl Iterator<Item = usize> {
+    pub fn selection(&self) -> impl Iterator<Item = NodeId> {
         self.nodes
             .iter()
-            .enumerate()
-            .filter_map(|(i, node)| node.selected.then_some(i))
+            .filter_map(|node| node.selected.then_some(node.id))
     }
 
     pub fn traverse_iter(
         &self,
-        starting_node: Option<usize>,
-    ) -> impl Iterator<Item = (usize, &GraphNode<Data>)> {
+        starting_node: Option<NodeId>,
+    ) -> impl Iterator<Item = (NodeId, &GraphNode<Data>)> {
         let mut visited = Vec::new();
 
         let mut stack = VecDeque::with_capacity(self.nodes.len());
 
-        stack.push_back(starting_node.unwrap_or(0));
+        let Some(starting_node) = starting_node.or_else(|| self.nodes.first().map(|n| n.id)) else {
+            return Vec::<(NodeId, &GraphNode<Data>)>::new().into_iter();
+        };
 
-        if *stack.iter().next().unwrap() >= self.nodes.len() {
-            return Vec::<(usize, &GraphNode<Data>)>::new().into_iter();
+        if !self.contains(starting_node) {
+            return Vec::<(NodeId, &GraphNode<Data>)>::new().into_iter();
         }
 
+        stack.push_back(starting_node);
+
         while !stack.is_empty() {
             if visited.len() == self.nodes.len() {
                 break;
@@ -286,7 +570,7 @@ where
 
         visited
             .iter()
-            .map(|id| (*id, &self.nodes[*id]))
+            .filter_map(|id| self.get(*id).map(|node| (*id, node)))
             .collect::<Vec<_>>()
             .into_iter()
     }
@@ -299,22 +583,293 @@ where
         self.nodes.iter_mut()
     }
 
+    pub fn connections(&self) -> impl Iterator<Item = &Connection<Attachment>> {
+        self.connections.iter()
+    }
+
+    /// The path `connection` is drawn along, given the size of each node. Node positions are
+    /// shifted by `-offset`, like the graph does with its scroll position.
+    pub fn connection_path(
+        &self,
+        connection: &Connection<Attachment>,
+        node_size: impl Fn(NodeId) -> Size,
+        offset: Vector,
+    ) -> Option<Path> {
+        let (a, a_attachment) = &connection.a;
+        let (b, b_attachment) = &connection.b;
+
+        let from = a_attachment.resolve(node_size(*a), self.get(*a)?.position - offset);
+        let to = b_attachment.resolve(node_size(*b), self.get(*b)?.position - offset);
+
+        Some(Attachment::path(
+            a_attachment.clone(),
+            from,
+            b_attachment.clone(),
+            to,
+        ))
+    }
+
     pub fn iter_dfs<'graph: 'iter, 'iter>(
         &'graph self,
-        starting_node: usize,
+        starting_node: NodeId,
     ) -> DepthFirstIterator<'iter, Data, Attachment> {
         DepthFirstIterator::new(self, starting_node)
     }
 
     pub fn iter_bfs<'graph: 'iter, 'iter>(
         &'graph self,
-        starting_node: usize,
+        starting_node: NodeId,
     ) -> BreadthFirstIterator<'iter, Data, Attachment> {
         BreadthFirstIterator::new(self, starting_node)
     }
+
+    /// Finds the path from `from` to `to` going over the fewest connections.
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Option<GraphPath> {
+        if !self.contains(from) || !self.contains(to) {
+            return None;
+        }
+
+        // node -> (previous node, connection used to get there)
+        let mut came_from = HashMap::new();
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                break;
+            }
+
+            for (connection, _, other, _) in self.get_connections_indexed(current) {
+                if other != from && !came_from.contains_key(&other) {
+                    came_from.insert(other, (current, connection));
+                    queue.push_back(other);
+                }
+            }
+        }
+
+        let mut path = GraphPath {
+            nodes: vec![to],
+            connections: Vec::new(),
+        };
+
+        let mut current = to;
+
+        while current != from {
+            let (previous, connection) = came_from.get(&current)?;
+
+            path.nodes.push(*previous);
+            path.connections.push(*connection);
+
+            current = *previous;
+        }
+
+        path.nodes.reverse();
+        path.connections.reverse();
+
+        Some(path)
+    }
+}
+
+/// Nodes and the connections between them on the way from one node to another, in order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphPath {
+    pub nodes: Vec<NodeId>,
+    pub connections: Vec<ConnectionId>,
+}
+
+impl GraphPath {
+    pub fn contains_node(&self, id: NodeId) -> bool {
+        self.nodes.contains(&id)
+    }
+
+    pub fn contains_connection(&self, id: ConnectionId) -> bool {
+        self.connections.contains(&id)
+    }
+}
+
+/// A detached piece of a [`GraphData`], used for copying nodes around.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subgraph<Data, Attachment>
+where
+    Data: std::fmt::Debug,
+    Attachment: connections::Attachment,
+{
+    nodes: Vec<GraphNode<Data>>,
+    connections: Vec<Connection<Attachment>>,
+}
+
+impl<Data, Attachment> Subgraph<Data, Attachment>
+where
+    Data: std::fmt::Debug,
+    Attachment: connections::Attachment,
+{
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GraphNode<Data>> {
+        self.nodes.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut GraphNode<Data>> {
+        self.nodes.iter_mut()
+    }
+
+    pub fn connections(&self) -> impl Iterator<Item = &Connection<Attachment>> {
+        self.connections.iter()
+    }
+
+    pub fn connections_mut(&mut self) -> impl Iterator<Item = &mut Connection<Attachment>> {
+        self.connections.iter_mut()
+    }
+}
+
+/// A node that was taken out of a [`GraphData`], along with everything needed to put it back.
+#[derive(Debug, Clone)]
+pub struct RemovedNode<Data, Attachment>
+where
+    Data: std::fmt::Debug,
+    Attachment: connections::Attachment,
+{
+    index: usize,
+    node: GraphNode<Data>,
+    connections: Vec<Connection<Attachment>>,
+}
+
+impl<Data, Attachment> RemovedNode<Data, Attachment>
+where
+    Data: std::fmt::Debug,
+    Attachment: connections::Attachment,
+{
+    pub fn id(&self) -> NodeId {
+        self.node.id
+    }
 }
 
 #[derive(Debug)]
 pub enum GraphError {
-    NodeNotFound(usize),
+    NodeNotFound(NodeId),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Graph = GraphData<String>;
+
+    fn round_trip(graph: &Graph) -> Graph {
+        ron::from_str(&ron::to_string(graph).unwrap()).unwrap()
+    }
+
+    fn name(graph: &Graph, id: NodeId) -> Option<&str> {
+        graph.get(id).map(|node| node.data().as_str())
+    }
+
+    fn neighbours(graph: &Graph, id: NodeId) -> Vec<&str> {
+        let mut names: Vec<_> = graph
+            .get_connected_nodes(id)
+            .into_iter()
+            .map(|node| node.data().as_str())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn migrates_index_based_connections() {
+        let graph: Graph = ron::from_str(
+            r#"(
+                nodes: [
+                    (position: (x: 0.0, y: 0.0), data: "Anna"),
+                    (position: (x: 10.0, y: 0.0), data: "Ben"),
+                    (position: (x: 20.0, y: 0.0), data: "Carl"),
+                ],
+                connections: [
+                    (a: (0, Center), b: (2, Center)),
+                    (a: (1, Center), b: (2, Center)),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(name(&graph, NodeId(0)), Some("Anna"));
+        assert_eq!(name(&graph, NodeId(2)), Some("Carl"));
+        assert_eq!(neighbours(&graph, NodeId(2)), ["Anna", "Ben"]);
+        assert_eq!(neighbours(&graph, NodeId(1)), ["Carl"]);
+
+        let ids: Vec<_> = graph.connections().map(|conn| conn.id).collect();
+        assert_eq!(ids, [ConnectionId(0), ConnectionId(1)]);
+
+        let mut graph = graph;
+        assert_eq!(graph.add("Dora".into(), Point::ORIGIN), NodeId(3));
+    }
+
+    #[test]
+    fn ids_survive_removing_and_reloading() {
+        let mut graph = Graph::default();
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        let ben = graph.add("Ben".into(), Point::ORIGIN);
+        let carl = graph.add("Carl".into(), Point::ORIGIN);
+
+        graph
+            .connect(
+                ben,
+                RelativeAttachment::Center,
+                anna,
+                RelativeAttachment::Center,
+            )
+            .unwrap();
+        let carl_anna = graph
+            .connect(
+                carl,
+                RelativeAttachment::Center,
+                anna,
+                RelativeAttachment::Center,
+            )
+            .unwrap()
+            .unwrap();
+
+        graph.remove(ben);
+        let graph = round_trip(&graph);
+
+        assert_eq!(name(&graph, anna), Some("Anna"));
+        assert_eq!(name(&graph, carl), Some("Carl"));
+        assert_eq!(name(&graph, ben), None);
+
+        let connection = graph.get_connection(carl_anna).unwrap();
+        assert_eq!((connection.a.0, connection.b.0), (carl, anna));
+        assert_eq!(graph.connections().count(), 1);
+    }
+
+    #[test]
+    fn ids_arent_reused_after_reloading() {
+        let mut graph = Graph::default();
+        let anna = graph.add("Anna".into(), Point::ORIGIN);
+        let ben = graph.add("Ben".into(), Point::ORIGIN);
+        let connection = graph
+            .connect(
+                anna,
+                RelativeAttachment::Center,
+                ben,
+                RelativeAttachment::Center,
+            )
+            .unwrap()
+            .unwrap();
+
+        graph.remove(ben);
+        let mut graph = round_trip(&graph);
+
+        let carl = graph.add("Carl".into(), Point::ORIGIN);
+        assert_ne!(carl, ben);
+
+        let new_connection = graph
+            .connect(
+                anna,
+                RelativeAttachment::Center,
+                carl,
+                RelativeAttachment::Center,
+            )
+            .unwrap()
+            .unwrap();
+        assert_ne!(new_connection, connection);
+    }
 }
@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::graph::{GraphData, connections, data::GraphNode};
+use crate::graph::{GraphData, NodeId, connections, data::GraphNode};
 
 pub struct DepthFirstIterator<'graph, Data, Attachment>
 where
@@ -8,8 +8,8 @@ where
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
     graph_data: &'graph GraphData<Data, Attachment>,
-    visited: Vec<usize>,
-    stack: VecDeque<usize>,
+    visited: Vec<NodeId>,
+    stack: VecDeque<NodeId>,
     visit_all: bool,
 }
 
@@ -18,11 +18,11 @@ where
     Data: std::fmt::Debug,
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
-    pub(super) fn new(data: &'graph GraphData<Data, Attachment>, starting_node: usize) -> Self {
+    pub(super) fn new(data: &'graph GraphData<Data, Attachment>, starting_node: NodeId) -> Self {
         Self {
             graph_data: data,
             visited: Vec::new(),
-            stack: if data.nodes.get(starting_node).is_some() {
+            stack: if data.contains(starting_node) {
                 let mut stack = VecDeque::with_capacity(data.nodes.len());
                 stack.push_back(starting_node);
                 stack
@@ -44,13 +44,13 @@ where
     Data: std::fmt::Debug,
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
-    type Item = (usize, &'graph GraphNode<Data>);
+    type Item = (NodeId, &'graph GraphNode<Data>);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.visited.is_empty() && self.stack.len() == 1 {
             let next = *self.stack.front().unwrap();
             self.visited.push(next);
-            return Some((next, &self.graph_data.nodes[next]));
+            return Some((next, self.graph_data.get(next)?));
         }
 
         if self.visited.len() == self.graph_data.nodes.len() {
@@ -59,10 +59,15 @@ where
 
         if self.stack.is_empty() {
             if self.visit_all {
-                let next = *self.stack.iter().find(|id| !self.visited.contains(id))?;
+                let next = self
+                    .graph_data
+                    .nodes
+                    .iter()
+                    .map(GraphNode::id)
+                    .find(|id| !self.visited.contains(id))?;
                 self.stack.push_back(next);
                 self.visited.push(next);
-                return Some((next, &self.graph_data.nodes[next]));
+                return Some((next, self.graph_data.get(next)?));
             }
             return None;
         }
@@ -86,7 +91,7 @@ where
             self.stack.pop_back();
         }
 
-        Some((current_node, &self.graph_data.nodes[current_node]))
+        Some((current_node, self.graph_data.get(current_node)?))
     }
 }
 
@@ -96,8 +101,8 @@ where
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
     graph_data: &'a GraphData<Data, Attachment>,
-    visited: Vec<usize>,
-    queue: VecDeque<usize>,
+    visited: Vec<NodeId>,
+    queue: VecDeque<NodeId>,
     visit_all: bool,
 }
 
@@ -106,11 +111,11 @@ where
     Data: std::fmt::Debug,
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
-    pub(super) fn new(data: &'graph GraphData<Data, Attachment>, starting_node: usize) -> Self {
+    pub(super) fn new(data: &'graph GraphData<Data, Attachment>, starting_node: NodeId) -> Self {
         Self {
             graph_data: data,
             visited: Vec::new(),
-            queue: if data.nodes.get(starting_node).is_some() {
+            queue: if data.contains(starting_node) {
                 let mut queue = VecDeque::with_capacity(data.nodes.len());
                 queue.push_back(starting_node);
                 queue
@@ -132,14 +137,14 @@ where
     Data: std::fmt::Debug,
     Attachment: connections::Attachment + std::cmp::PartialEq,
 {
-    type Item = (usize, &'a GraphNode<Data>);
+    type Item = (NodeId, &'a GraphNode<Data>);
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.visited.is_empty() && self.queue.len() == 1 {
             let starting_node = *self.queue.front().unwrap();
             self.visited.push(starting_node);
 
-            return Some((starting_node, &self.graph_data.nodes[starting_node]));
+            return Some((starting_node, self.graph_data.get(starting_node)?));
         }
 
         if self.visited.len() == self.graph_data.nodes.len() {
@@ -148,10 +153,15 @@ where
 
         if self.queue.is_empty() {
             if self.visit_all {
-                let next = *self.queue.iter().find(|id| !self.visited.contains(id))?;
+                let next = self
+                    .graph_data
+                    .nodes
+                    .iter()
+                    .map(GraphNode::id)
+                    .find(|id| !self.visited.contains(id))?;
                 self.queue.push_back(next);
                 self.visited.push(next);
-                return Some((next, &self.graph_data.nodes[next]));
+                return Some((next, self.graph_data.get(next)?));
             }
             return None;
         }
@@ -181,6 +191,6 @@ where
         self.queue.push_back(next);
         self.visited.push(next);
 
-        Some((next, &self.graph_data.nodes[next]))
+        Some((next, self.graph_data.get(next)?))
     }
 }
@@ -7,15 +7,20 @@ mod state;
 
 use std::collections::VecDeque;
 
-pub use crate::graph::connections::{Attachment, RelativeAttachment};
+pub use crate::graph::connections::{
+    Attachment, Connection, ConnectionStyle, LinePattern, RelativeAttachment,
+};
 use crate::graph::state::{CursorState, GraphState, Payload};
-pub use data::{GraphData, GraphNode};
+pub use data::{
+    ConnectionId, ConnectionKind, GraphData, GraphNode, GraphPath, NodeId, RemovedNode, Subgraph,
+};
 
 use iced::{
     Border, Color, Element, Event, Gradient, Length, Padding, Point, Rectangle, Size, Theme,
     Transformation, Vector,
     advanced::{
         Clipboard, Layout, Shell, Widget,
+        clipboard::Kind,
         graphics::{core::event::Status, geometry::Frame},
         layout::{Limits, Node},
         renderer::{self, Quad},
@@ -24,7 +29,7 @@ use iced::{
     gradient::{ColorStop, Linear},
     keyboard::{self, Key, Modifiers, key::Named},
     mouse::{self, Button, Cursor, ScrollDelta},
-    widget::canvas::{LineCap, LineJoin, Path, Stroke},
+    widget::canvas::{self, LineCap, LineDash, LineJoin, Path, Stroke},
 };
 use lyon_algorithms::{
     geom::{
@@ -47,22 +52,30 @@ where
     data: &'a GraphData<Data, Attachment>,
     content: Vec<Element<'a, Message, Theme, Renderer>>,
     get_attachment: Box<dyn Fn(&'a GraphNode<Data>, Vector) -> Option<Attachment> + 'a>,
+    connection_style: Box<dyn Fn(&Connection<Attachment>) -> ConnectionStyle + 'a>,
     on_event: Option<Box<dyn Fn(GraphEvent<Attachment>) -> Message + 'a>>,
     node_positioning: Option<
         Box<
             dyn Fn(
-                Option<(usize, &GraphNode<Data>, &Attachment, &Attachment, Size<f32>)>,
-                usize,
+                Option<(
+                    NodeId,
+                    &GraphNode<Data>,
+                    &Attachment,
+                    &Attachment,
+                    Size<f32>,
+                )>,
+                NodeId,
                 &'a GraphNode<Data>,
                 Size,
                 &'a GraphData<Data, Attachment>,
                 &Layout,
-                Vec<usize>,
+                Vec<NodeId>,
             ) -> Vector,
         >,
     >,
     allow_self_connections: bool,
     allow_similar_connections: bool,
+    highlight: Option<(NodeId, NodeId)>,
 }
 
 impl<'a, Message, Renderer, Data, Attachment> Graph<'a, Message, Renderer, Data, Attachment>
@@ -83,10 +96,12 @@ where
             data,
             content,
             get_attachment: Box::new(|_, _| None),
+            connection_style: Box::new(|_| ConnectionStyle::default()),
             on_event: None,
             allow_self_connections: false,
             allow_similar_connections: false,
             node_positioning: None,
+            highlight: None,
         }
     }
 
@@ -140,13 +155,19 @@ where
     pub fn position_nodes<F>(mut self, f: F) -> Self
     where
         F: Fn(
-                Option<(usize, &GraphNode<Data>, &Attachment, &Attachment, Size<f32>)>,
-                usize,
+                Option<(
+                    NodeId,
+                    &GraphNode<Data>,
+                    &Attachment,
+                    &Attachment,
+                    Size<f32>,
+                )>,
+                NodeId,
                 &'a GraphNode<Data>,
                 Size,
                 &'a GraphData<Data, Attachment>,
                 &Layout,
-                Vec<usize>,
+                Vec<NodeId>,
             ) -> Vector
             + 'static,
     {
@@ -155,11 +176,31 @@ where
         self
     }
 
+    /// Decides how each connection is drawn, e.g. based on its [`ConnectionKind`].
+    pub fn connection_style<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Connection<Attachment>) -> ConnectionStyle + 'a,
+    {
+        self.connection_style = Box::new(f);
+        self
+    }
+
     pub fn allow_similar_connections(mut self, value: bool) -> Self {
         self.allow_similar_connections = value;
         self
     }
 
+    /// Highlights the shortest path between two nodes and dims everything else.
+    pub fn highlight_path(mut self, endpoints: Option<(NodeId, NodeId)>) -> Self {
+        self.highlight = endpoints;
+        self
+    }
+
+    fn find_highlight(&self) -> Option<GraphPath> {
+        self.highlight
+            .and_then(|(a, b)| self.data.shortest_path(a, b))
+    }
+
     pub fn allow_self_connections(mut self, value: bool) -> Self {
         self.allow_self_connections = value;
         self
@@ -173,78 +214,65 @@ where
         self
     }
 
-    fn find_hovered_connection(&self, cursor_pos: Point, layout: &Layout<'_>) -> Option<usize> {
+    fn node_size(&self, layout: &Layout<'_>, id: NodeId) -> Size {
         self.data
-            .connections
-            .iter()
-            .enumerate()
-            .find_map(|(i, connection)| {
-                let a = self.data.get(connection.a.0).unwrap();
-                let b = self.data.get(connection.b.0).unwrap();
-
-                let a_size = layout
-                    .children()
-                    .nth(connection.a.0)
-                    .expect("Invalid connection")
-                    .bounds()
-                    .size();
-
-                let b_size = layout
-                    .children()
-                    .nth(connection.b.0)
-                    .expect("Invalid connection")
-                    .bounds()
-                    .size();
-
-                let a_attachment = connection.a.1.clone();
-                let b_attachment = connection.b.1.clone();
-
-                let from = a_attachment.resolve(a_size, a.position);
-                let to = b_attachment.resolve(b_size, b.position);
+            .index_of(id)
+            .and_then(|i| layout.children().nth(i))
+            .expect("Invalid node")
+            .bounds()
+            .size()
+    }
 
-                let mut path: Vec<_> =
-                    Attachment::path(connection.a.1.clone(), from, connection.b.1.clone(), to)
-                        .transform(&Transform2D::scale(self.zoom, self.zoom))
-                        .raw()
-                        .iter()
-                        .collect();
+    fn find_hovered_connection(
+        &self,
+        cursor_pos: Point,
+        layout: &Layout<'_>,
+    ) -> Option<ConnectionId> {
+        self.data.connections.iter().find_map(|connection| {
+            let mut path: Vec<_> = self
+                .data
+                .connection_path(connection, |id| self.node_size(layout, id), Vector::ZERO)?
+                .transform(&Transform2D::scale(self.zoom, self.zoom))
+                .raw()
+                .iter()
+                .collect();
 
-                // remove end event so that it doesn't connect the last point with the first one
-                path.pop();
+            // remove end event so that it doesn't connect the last point with the first one
+            path.pop();
 
-                let origin = lyon_algorithms::geom::euclid::Point2D::new(
-                    cursor_pos.x - layout.position().x + self.position.x * self.zoom,
-                    cursor_pos.y - layout.position().y + self.position.y * self.zoom,
-                );
+            let origin = lyon_algorithms::geom::euclid::Point2D::new(
+                cursor_pos.x - layout.position().x + self.position.x * self.zoom,
+                cursor_pos.y - layout.position().y + self.position.y * self.zoom,
+            );
 
-                let directions: Vec<_> = [
-                    Angle::zero(),
-                    Angle::pi() / 2.0,
-                    Angle::pi(),
-                    (Angle::pi() * 3.0) / 2.0,
-                ]
-                .iter()
-                .map(|angle| Vector2D::from_angle_and_length(*angle, 1.0))
-                .collect();
+            let directions: Vec<_> = [
+                Angle::zero(),
+                Angle::pi() / 2.0,
+                Angle::pi(),
+                (Angle::pi() * 3.0) / 2.0,
+            ]
+            .iter()
+            .map(|angle| Vector2D::from_angle_and_length(*angle, 1.0))
+            .collect();
 
-                let hit = directions.iter().any(|direction| {
-                    raycast_path(
-                        &Ray {
-                            origin,
-                            direction: *direction,
-                        },
-                        path.clone(),
-                        15.0,
-                    )
-                    .is_some_and(|hit| hit.position.distance_to(origin) < 10.0)
-                });
+            let hit = directions.iter().any(|direction| {
+                raycast_path(
+                    &Ray {
+                        origin,
+                        direction: *direction,
+                    },
+                    path.clone(),
+                    15.0,
+                )
+                .is_some_and(|hit| hit.position.distance_to(origin) < 10.0)
+            });
 
-                if hit {
-                    return Some(i);
-                }
+            if hit {
+                return Some(connection.id);
+            }
 
-                None
-            })
+            None
+        })
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -264,9 +292,8 @@ where
             .zip(layout.children())
             .zip(tree_children.iter_mut())
             .zip(self.data.nodes.iter())
-            .enumerate()
             .find_map(
-                |(i, (((element, node_layout), tree), node))| match cursor.position() {
+                |(((element, node_layout), tree), node)| match cursor.position() {
                     Some(cursor_pos) => {
                         // make sure the cursor position is transformed properly
                         let cursor = match cursor {
@@ -311,11 +338,11 @@ where
                         let hovered_attachment = (self.get_attachment)(node, relative_cursor_pos);
 
                         if !hovered && let Some(hovered_attachment) = hovered_attachment {
-                            return Some(Payload::Attachment(i, hovered_attachment));
+                            return Some(Payload::Attachment(node.id, hovered_attachment));
                         }
 
                         if hovered {
-                            Some(Payload::Node(i, node_status))
+                            Some(Payload::Node(node.id, node_status))
                         } else {
                             None
                         }
@@ -386,15 +413,15 @@ where
                         let selections_positions: Vec<_> = self
                             .data
                             .selection()
-                            .filter_map(|selected: usize| {
+                            .filter_map(|selected: NodeId| {
                                 if selected == *id {
                                     return None;
                                 }
 
-                                let node = &self.data.nodes[selected];
+                                let node = self.data.get(selected)?;
 
                                 let new_position =
-                                    node.position + (new_position - self.data.nodes[*id].position);
+                                    node.position + (new_position - self.data.get(*id)?.position);
 
                                 if new_position.x < 0.0 {
                                     correction.x = correction.x.max(-new_position.x);
@@ -440,7 +467,7 @@ where
                     };
                 }
                 Payload::Node(id, status) => 'cursor_state: {
-                    let Some(node) = self.data.nodes.get(*id) else {
+                    let Some(node) = self.data.get(*id) else {
                         state.cursor_state = CursorState::Hovering(Payload::Background);
                         break 'cursor_state;
                     };
@@ -464,7 +491,7 @@ where
                     };
                 }
                 Payload::Attachment(id, _) => {
-                    state.drag_origin = self.data.nodes[*id].position;
+                    state.drag_origin = self.data.get(*id).unwrap().position;
                     state.drag_start_point = cursor_pos;
                     state.cursor_state = if state.pressed_mb == Some(Button::Left) {
                         CursorState::Dragging(new_payload)
@@ -482,25 +509,15 @@ where
                     let connection = self
                         .data
                         .connections
-                        .get(*connection)
+                        .iter()
+                        .find(|conn| conn.id == *connection)
                         .expect("Invalid connection");
 
                     let a = self.data.get(connection.a.0).unwrap();
                     let b = self.data.get(connection.b.0).unwrap();
 
-                    let a_size = layout
-                        .children()
-                        .nth(connection.a.0)
-                        .expect("Invalid connection")
-                        .bounds()
-                        .size();
-
-                    let b_size = layout
-                        .children()
-                        .nth(connection.b.0)
-                        .expect("Invalid connection")
-                        .bounds()
-                        .size();
+                    let a_size = self.node_size(layout, connection.a.0);
+                    let b_size = self.node_size(layout, connection.b.0);
 
                     let a_attachment = connection.a.1.clone();
                     let b_attachment = connection.b.1.clone();
@@ -562,13 +579,14 @@ where
                         }
 
                         if !self.allow_similar_connections
-                            && let Some((connection_id, _)) =
-                                self.data.connections.iter().enumerate().find(|(_, conn)| {
-                                    (conn.a.0 == *a && conn.b.0 == b)
-                                        || (conn.a.0 == b && conn.b.0 == *a)
-                                })
+                            && let Some(conn) = self.data.connections.iter().find(|conn| {
+                                (conn.a.0 == *a && conn.b.0 == b)
+                                    || (conn.a.0 == b && conn.b.0 == *a)
+                            })
                         {
-                            shell.publish(on_event(GraphEvent::Disconnect { connection_id }));
+                            shell.publish(on_event(GraphEvent::Disconnect {
+                                connection_id: conn.id,
+                            }));
                         }
 
                         if allowed {
@@ -587,6 +605,11 @@ where
                     }
                 }
             }
+            CursorState::Dragging(Payload::Node(..)) => {
+                if let Some(on_event) = &self.on_event {
+                    shell.publish(on_event(GraphEvent::DragEnded));
+                }
+            }
             CursorState::Dragging(Payload::SelectionRect) => {
                 let mut top_left = state.drag_start_point;
                 let mut bottom_right = state.cursor_pos;
@@ -603,15 +626,14 @@ where
                 let selected: Vec<_> = layout
                     .children()
                     .zip(self.data.nodes.iter())
-                    .enumerate()
-                    .filter_map(|(i, (child, node))| {
+                    .filter_map(|(child, node)| {
                         rect.intersects(&transform_node_bounds(
                             child.bounds(),
                             self.zoom,
                             self.position,
                             node.position,
                         ))
-                        .then_some(i)
+                        .then_some(node.id)
                     })
                     .collect();
 
@@ -636,8 +658,8 @@ where
                     if !state.shift_pressed {
                         shell.publish(on_event(GraphEvent::ClearSelection));
                     }
-                    if let Some(index) = self.data.selection().position(|s| s == *id) {
-                        shell.publish(on_event(GraphEvent::Deselect(index)));
+                    if self.data.is_selected(*id).is_ok_and(|selected| selected) {
+                        shell.publish(on_event(GraphEvent::Deselect(*id)));
                     } else {
                         shell.publish(on_event(GraphEvent::Select(*id)));
                     }
@@ -717,60 +739,81 @@ where
                             Frame::new(renderer, layout.bounds().size() * (1.0 / self.zoom));
 
                         // draw connections
-                        for (i, connection) in self.data.connections.iter().enumerate() {
-                            let a = self.data.get(connection.a.0).unwrap();
-                            let b = self.data.get(connection.b.0).unwrap();
-
-                            let a_size = layout
-                                .children()
-                                .nth(connection.a.0)
-                                .unwrap_or_else(|| {
-                                    dbg!(&self.data);
-
-                                    panic!(
-                                        "Invalid connection: {:?}\nnode not found: {}",
-                                        connection, connection.a.0
-                                    )
-                                })
-                                .bounds()
-                                .size();
-
-                            let b_size = layout
-                                .children()
-                                .nth(connection.b.0)
-                                .expect("Invalid connection")
-                                .bounds()
-                                .size();
-
-                            let a_attachment = connection.a.1.clone();
-                            let b_attachment = connection.b.1.clone();
-
-                            let from = a_attachment.resolve(a_size, a.position - self.position);
-                            let to = b_attachment.resolve(b_size, b.position - self.position);
-
-                            let path = Attachment::path(a_attachment, from, b_attachment, to);
+                        for connection in self.data.connections.iter() {
+                            let Some(path) = self.data.connection_path(
+                                connection,
+                                |id| self.node_size(&layout, id),
+                                self.position,
+                            ) else {
+                                continue;
+                            };
+
+                            let connection_style = (self.connection_style)(connection);
+                            let mut color = connection_style
+                                .color
+                                .unwrap_or(palette.secondary.strong.color);
+                            let width = 5.0 / self.zoom;
+
+                            if let Some(highlight) = &state.highlight {
+                                if highlight.contains_connection(connection.id) {
+                                    frame.stroke(
+                                        &path,
+                                        Stroke::default()
+                                            .with_color(
+                                                palette.primary.strong.color.scale_alpha(0.5),
+                                            )
+                                            .with_width(12.0 / self.zoom)
+                                            .with_line_join(LineJoin::Bevel)
+                                            .with_line_cap(LineCap::Round),
+                                    );
+                                } else {
+                                    color = color.scale_alpha(0.2);
+                                }
+                            }
+                            let segments = connection_style.pattern.segments(width);
 
                             frame.stroke(
                                 &path,
-                                Stroke::default()
-                                    .with_color(palette.secondary.strong.color)
-                                    .with_width(5.0 / self.zoom)
-                                    .with_line_join(LineJoin::Bevel)
-                                    .with_line_cap(LineCap::Round),
+                                Stroke {
+                                    line_dash: LineDash {
+                                        segments: segments.as_ref().map_or(&[], |s| s.as_slice()),
+                                        offset: 0,
+                                    },
+                                    ..Stroke::default()
+                                        .with_color(color)
+                                        .with_width(width)
+                                        .with_line_join(LineJoin::Bevel)
+                                        .with_line_cap(LineCap::Round)
+                                },
                             );
 
-                            if let CursorState::Hovering(Payload::Connection(j)) =
+                            if let CursorState::Hovering(Payload::Connection(id)) =
                                 &state.cursor_state
-                                && *j == i
+                                && *id == connection.id
                             {
                                 frame.stroke(
                                     &path,
                                     Stroke::default()
-                                        .with_color(palette.secondary.strong.color.scale_alpha(0.3))
+                                        .with_color(color.scale_alpha(0.3))
                                         .with_width(10.0 / self.zoom)
                                         .with_line_join(LineJoin::Bevel)
                                         .with_line_cap(LineCap::Round),
                                 );
+
+                                if let Some(label) = connection_style.label {
+                                    let cursor_pos = (state.cursor_pos
+                                        - Vector::new(bounds_position.x, bounds_position.y))
+                                        * Transformation::scale(1.0 / self.zoom);
+
+                                    frame.fill_text(canvas::Text {
+                                        content: label,
+                                        position: cursor_pos
+                                            + Vector::new(12.0, 12.0) * (1.0 / self.zoom),
+                                        color,
+                                        size: (14.0 / self.zoom).into(),
+                                        ..canvas::Text::default()
+                                    });
+                                }
                             }
                         }
 
@@ -779,12 +822,7 @@ where
                         {
                             let node = self.data.get(*i).unwrap();
 
-                            let size = layout
-                                .children()
-                                .nth(*i)
-                                .expect("Invalid connection")
-                                .bounds()
-                                .size();
+                            let size = self.node_size(&layout, *i);
 
                             let attachment_point =
                                 attachment.resolve(size, node.position - self.position);
@@ -801,12 +839,7 @@ where
                         {
                             let node = self.data.get(*i).unwrap();
 
-                            let size = layout
-                                .children()
-                                .nth(*i)
-                                .expect("Invalid connection")
-                                .bounds()
-                                .size();
+                            let size = self.node_size(&layout, *i);
 
                             let from = attachment.resolve(size, node.position - self.position);
 
@@ -883,9 +916,14 @@ where
                     data.position,
                 )
                 .intersection(&layout.bounds())
-                .map(|bounds| (element, tree, node_layout, bounds))
+                .map(|bounds| (element, tree, node_layout, bounds, data.id))
             })
-            .for_each(|(node, tree, node_layout, bounds)| {
+            .for_each(|(node, tree, node_layout, bounds, id)| {
+                let dimmed = state
+                    .highlight
+                    .as_ref()
+                    .is_some_and(|highlight| !highlight.contains_node(id));
+
                 renderer.with_layer(bounds, |renderer| {
                     renderer.with_transformation(Transformation::scale(self.zoom), |renderer| {
                         let node_pos = Vector::new(layout.position().x, layout.position().y);
@@ -913,6 +951,19 @@ where
                                     cursor,
                                     &Rectangle::with_size(Size::INFINITY),
                                 );
+
+                                // needs its own layer to end up above the node's images and text
+                                if dimmed {
+                                    renderer.with_layer(node_layout.bounds(), |renderer| {
+                                        renderer.fill_quad(
+                                            Quad {
+                                                bounds: node_layout.bounds(),
+                                                ..Default::default()
+                                            },
+                                            palette.background.base.color.scale_alpha(0.7),
+                                        );
+                                    });
+                                }
                             },
                         );
                     });
@@ -1066,7 +1117,10 @@ where
     }
 
     fn state(&self) -> tree::State {
-        tree::State::Some(Box::new(GraphState::<Attachment>::default()))
+        tree::State::Some(Box::new(GraphState::<Attachment> {
+            highlight: self.find_highlight(),
+            ..GraphState::default()
+        }))
     }
 
     fn children(&self) -> Vec<Tree> {
@@ -1074,6 +1128,10 @@ where
     }
 
     fn diff(&self, tree: &mut Tree) {
+        tree.state
+            .downcast_mut::<GraphState<Attachment>>()
+            .highlight = self.find_highlight();
+
         tree.diff_children(&self.content);
     }
 
@@ -1120,7 +1178,9 @@ where
                     break 'ev Status::Ignored;
                 };
 
-                if !layout.bounds().contains(cursor_pos) {
+                state.focused = layout.bounds().contains(cursor_pos);
+
+                if !state.focused {
                     break 'ev Status::Ignored;
                 }
 
@@ -1172,37 +1232,21 @@ where
             Event::Keyboard(keyboard::Event::KeyPressed {
                 key: Key::Named(Named::Delete),
                 ..
-            }) => {
+            }) if state.focused => {
                 if let Some(on_event) = &self.on_event {
-                    for (selection_index, selected_node_id) in self.data.selection().enumerate() {
-                        // find how many of the previous selections have an id less than the
-                        // current one and subtract the current one from that. we don't want an
-                        // an out of bounds memory access
-                        let correction = self
-                            .data
-                            .selection()
-                            .take(selection_index)
-                            .filter(|s| *s < selected_node_id)
-                            .count();
-
-                        let corrected_node_id = selected_node_id - correction.min(selection_index);
-
-                        // disconnect
-                        for (connection_id, _, _, _) in
-                            self.data.get_connections_indexed(corrected_node_id)
-                        {
-                            on_event(GraphEvent::Disconnect { connection_id });
-                        }
-
-                        match new_payload {
+                    for selected_node_id in self.data.selection() {
+                        match &new_payload {
                             Payload::Node(id, _) | Payload::Attachment(id, _)
-                                if id == selected_node_id =>
+                                if *id == selected_node_id =>
                             {
                                 state.cursor_state = CursorState::Hovering(Payload::Background)
                             }
                             Payload::Connection(id)
-                                if self.data.connections[id].a.0 == selected_node_id
-                                    || self.data.connections[id].b.0 == selected_node_id =>
+                                if self.data.connections.iter().any(|conn| {
+                                    conn.id == *id
+                                        && (conn.a.0 == selected_node_id
+                                            || conn.b.0 == selected_node_id)
+                                }) =>
                             {
                                 state.cursor_state = CursorState::Hovering(Payload::Background);
                             }
@@ -1210,7 +1254,7 @@ where
                         }
 
                         shell.publish(on_event(GraphEvent::Delete {
-                            id: corrected_node_id,
+                            id: selected_node_id,
                         }));
                     }
 
@@ -1234,6 +1278,35 @@ where
                     state.debug = !state.debug;
                     Status::Captured
                 }
+                'c' | 'x' | 'v'
+                    if modifiers.control()
+                        && cursor
+                            .position()
+                            .is_some_and(|pos| layout.bounds().contains(pos)) =>
+                {
+                    let event = match char.as_str() {
+                        "c" => Some(GraphEvent::Copy),
+                        "x" => Some(GraphEvent::Cut),
+                        // without text on the clipboard there might still be an image
+                        _ => {
+                            let cursor_pos = cursor.position().unwrap_or_default();
+
+                            Some(GraphEvent::Paste {
+                                contents: clipboard.read(Kind::Standard).unwrap_or_default(),
+                                position: Point::ORIGIN
+                                    + (cursor_pos - layout.position())
+                                        * Transformation::scale(1.0 / self.zoom)
+                                    + self.position,
+                            })
+                        }
+                    };
+
+                    if let Some(event) = event {
+                        shell.publish(on_event(event));
+                    }
+
+                    Status::Captured
+                }
                 _ => Status::Ignored,
             },
             _ => Status::Ignored,
@@ -1252,14 +1325,16 @@ where
             let mut queue = VecDeque::with_capacity(num_nodes);
             let mut visited = Vec::with_capacity(num_nodes);
 
-            queue.push_back(0);
-            visited.push(0);
+            let first = &self.data.nodes[0];
+
+            queue.push_back(first.id);
+            visited.push(first.id);
 
             let first_pos = Point::ORIGIN
                 + node_positioning(
                     None,
-                    0,
-                    &self.data.nodes[0],
+                    first.id,
+                    first,
                     layout.children().next().unwrap().bounds().size(),
                     self.data,
                     &layout,
@@ -1285,6 +1360,8 @@ where
                     .data
                     .connections
                     .iter()
+                    // connections with a kind don't say anything about where nodes belong
+                    .filter(|conn| conn.kind.is_none())
                     .filter_map(|conn| {
                         (conn.a.0 == current_node)
                             .then_some((conn.b.0, &conn.b.1, &conn.a.1))
@@ -1305,14 +1382,14 @@ where
                         + node_positioning(
                             Some((
                                 current_node,
-                                &self.data.nodes[current_node],
+                                self.data.get(current_node).unwrap(),
                                 attachment,
                                 conn_attachment,
-                                layout.children().nth(current_node).unwrap().bounds().size(),
+                                self.node_size(&layout, current_node),
                             )),
                             *node,
-                            &self.data.nodes[*node],
-                            layout.children().nth(*node).unwrap().bounds().size(),
+                            self.data.get(*node).unwrap(),
+                            self.node_size(&layout, *node),
                             self.data,
                             &layout,
                             visited.clone(),
@@ -1340,13 +1417,14 @@ where
 
                     correction = Vector::ZERO;
 
-                    if let Some((next, node)) = self
+                    if let Some(node) = self
                         .data
                         .nodes
                         .iter()
-                        .enumerate()
-                        .find(|(id, _)| !visited.contains(id))
+                        .find(|node| !visited.contains(&node.id))
                     {
+                        let next = node.id;
+
                         visited.push(next);
 
                         let new_position = Point::ORIGIN
@@ -1354,7 +1432,7 @@ where
                                 None,
                                 next,
                                 node,
-                                layout.children().nth(next).unwrap().bounds().size(),
+                                self.node_size(&layout, next),
                                 self.data,
                                 &layout,
                                 visited.clone(),
@@ -1414,30 +1492,40 @@ where
     Move(Point),
     Zoom(f32),
     MoveNode {
-        id: usize,
+        id: NodeId,
         new_position: Point,
         was_dragged: bool,
     },
+    /// Published when the user lets go of the nodes they were dragging.
+    DragEnded,
     Connect {
-        a: usize,
+        a: NodeId,
         a_attachment: Attachment,
-        b: usize,
+        b: NodeId,
         b_attachment: Attachment,
     },
     Disconnect {
-        connection_id: usize,
+        connection_id: ConnectionId,
     },
     Delete {
-        id: usize,
+        id: NodeId,
     },
     ConnectionDropped {
-        id: usize,
+        id: NodeId,
         attachment: Attachment,
     },
-    Select(usize),
-    Deselect(usize),
+    Select(NodeId),
+    Deselect(NodeId),
     ClearSelection,
     SelectAll,
+    Copy,
+    Cut,
+    /// Clipboard contents that were pasted while the cursor was over the graph, along with
+    /// the cursor position in graph coordinates.
+    Paste {
+        contents: String,
+        position: Point,
+    },
 }
 
 fn transform_node_bounds(
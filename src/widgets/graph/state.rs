@@ -1,6 +1,6 @@
 use iced::{Point, event::Status, mouse::Button};
 
-use crate::graph::{RelativeAttachment, connections};
+use crate::graph::{ConnectionId, GraphPath, NodeId, RelativeAttachment, connections};
 
 pub struct GraphState<Attachment = RelativeAttachment>
 where
@@ -13,6 +13,11 @@ where
     pub(super) shift_pressed: bool,
     pub(super) cursor_pos: Point,
     pub(super) debug: bool,
+    /// Whether the last click landed on the graph, so keys typed into other widgets are left
+    /// alone.
+    pub(super) focused: bool,
+    /// Path that gets highlighted while everything else is dimmed.
+    pub(super) highlight: Option<GraphPath>,
 }
 
 impl<Attachment: connections::Attachment> Default for GraphState<Attachment> {
@@ -25,6 +30,8 @@ impl<Attachment: connections::Attachment> Default for GraphState<Attachment> {
             shift_pressed: false,
             cursor_pos: Point::ORIGIN,
             debug: false,
+            focused: false,
+            highlight: None,
         }
     }
 }
@@ -35,9 +42,9 @@ where
     Attachment: connections::Attachment + std::fmt::Debug,
 {
     Background,
-    Node(usize, Status),
-    Attachment(usize, Attachment),
-    Connection(usize),
+    Node(NodeId, Status),
+    Attachment(NodeId, Attachment),
+    Connection(ConnectionId),
     SelectionRect,
 }
 